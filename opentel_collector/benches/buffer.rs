@@ -0,0 +1,88 @@
+//! Concurrent insert throughput of the telemetry buffer by shard count.
+//!
+//! Run with `cargo bench --bench buffer`.
+
+use opentel_collector::buffer::TelemetryBuffer;
+use opentel_collector::telemetry::{LogEntry, LogLevel};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Concurrent writers, like file monitor tasks
+const WRITERS: usize = 16;
+const ENTRIES_PER_WRITER: usize = 20_000;
+
+fn entry(source: &str, i: usize) -> LogEntry {
+    let mut log_entry = LogEntry::new(
+        LogLevel::Info,
+        format!("request {} handled", i),
+        "bench-service".to_string(),
+        "bench-pod".to_string(),
+        "bench-namespace".to_string(),
+    );
+    log_entry.attributes.insert("log.source".to_string(), source.to_string());
+    log_entry
+}
+
+async fn run(shards: usize) -> Duration {
+    // Large enough that nothing overflows; batches are drained once full,
+    // as the collector's flush loop does
+    let buffer = Arc::new(TelemetryBuffer::new(WRITERS * ENTRIES_PER_WRITER, 500).with_shards(shards));
+    let done = Arc::new(AtomicBool::new(false));
+    let start = Instant::now();
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|writer| {
+            let buffer = Arc::clone(&buffer);
+            tokio::spawn(async move {
+                let source = format!("/var/log/app/{}.log", writer);
+                for i in 0..ENTRIES_PER_WRITER {
+                    buffer.add_log(entry(&source, i)).await.unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let drainer = {
+        let buffer = Arc::clone(&buffer);
+        let done = Arc::clone(&done);
+        tokio::spawn(async move {
+            while !done.load(Ordering::Relaxed) {
+                if buffer.should_flush().await {
+                    buffer.drain_logs().await;
+                } else {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        })
+    };
+
+    for writer in writers {
+        writer.await.unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+    drainer.await.unwrap();
+    while !buffer.drain_logs().await.is_empty() {}
+    start.elapsed()
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    let total = WRITERS * ENTRIES_PER_WRITER;
+    println!("{} writers x {} entries", WRITERS, ENTRIES_PER_WRITER);
+
+    for shards in [1, 2, 4, 8, 16] {
+        // Warm up once, then report the best of three
+        run(shards).await;
+        let mut best = Duration::MAX;
+        for _ in 0..3 {
+            best = best.min(run(shards).await);
+        }
+        println!(
+            "shards={:>2}  {:>8.1} ms  {:>10.0} entries/s",
+            shards,
+            best.as_secs_f64() * 1000.0,
+            total as f64 / best.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Cost of serializing a batch and handing it to each send attempt, with the
+//! body grown on demand and copied per attempt versus pre-sized from
+//! `TelemetryBatch::estimated_bytes` and shared as `Bytes`, as
+//! `HttpTransport` does.
+//!
+//! Run with `cargo bench --bench serialize`.
+
+use bytes::Bytes;
+use opentel_collector::telemetry::{LogEntry, LogLevel, TelemetryBatch};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Send attempts per batch: the first try plus the default `MAX_RETRIES`
+const ATTEMPTS: usize = 4;
+const ITERATIONS: usize = 200;
+
+fn batch(logs: usize) -> TelemetryBatch {
+    let logs = (0..logs)
+        .map(|i| {
+            let mut log_entry = LogEntry::new(
+                LogLevel::Info,
+                format!("GET /api/orders/{} completed with status 200 in {} ms", i, i % 250),
+                "bench-service".to_string(),
+                "bench-pod-7d9f8c6b5-x2k4q".to_string(),
+                "bench-namespace".to_string(),
+            );
+            log_entry.attributes.insert("log.source".to_string(), "/var/log/app/app.log".to_string());
+            log_entry.attributes.insert("http.route".to_string(), "/api/orders/{id}".to_string());
+            log_entry
+        })
+        .collect();
+    TelemetryBatch::new(
+        logs,
+        Vec::new(),
+        "bench-namespace/bench-pod".to_string(),
+        "bench-pod".to_string(),
+        "bench-namespace".to_string(),
+    )
+}
+
+/// Serialized with `to_vec`, each attempt taking its own copy
+fn grown_and_copied(batch: &TelemetryBatch) -> usize {
+    let body = serde_json::to_vec(batch).unwrap();
+    (0..ATTEMPTS).map(|_| black_box(body.to_vec()).len()).sum()
+}
+
+/// Serialized into a pre-sized buffer, each attempt sharing it
+fn presized_and_shared(batch: &TelemetryBatch) -> usize {
+    let estimate = batch.estimated_bytes();
+    let mut buffer = Vec::with_capacity(estimate + estimate / 8);
+    serde_json::to_writer(&mut buffer, batch).unwrap();
+    let body = Bytes::from(buffer);
+    (0..ATTEMPTS).map(|_| black_box(body.clone()).len()).sum()
+}
+
+fn best_of(batch: &TelemetryBatch, serialize: fn(&TelemetryBatch) -> usize) -> Duration {
+    // Warm up once, then report the best of five runs
+    serialize(batch);
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..ITERATIONS {
+                black_box(serialize(black_box(batch)));
+            }
+            start.elapsed() / ITERATIONS as u32
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    println!("{} attempts per batch, best of 5 x {} batches", ATTEMPTS, ITERATIONS);
+
+    for logs in [10, 100, 1000] {
+        let batch = batch(logs);
+        let grown = best_of(&batch, grown_and_copied);
+        let presized = best_of(&batch, presized_and_shared);
+        println!(
+            "logs={:>4}  {:>7} bytes  grown+copied {:>9.1} us  presized+shared {:>9.1} us  ({:.2}x)",
+            logs,
+            serde_json::to_vec(&batch).unwrap().len(),
+            grown.as_secs_f64() * 1e6,
+            presized.as_secs_f64() * 1e6,
+            grown.as_secs_f64() / presized.as_secs_f64()
+        );
+    }
+}
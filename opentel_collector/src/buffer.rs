@@ -1,62 +1,176 @@
 //! In-memory buffering for telemetry data
 
-use crate::telemetry::{LogEntry, TraceSpan, TelemetryBatch};
+use crate::config::Config;
+use crate::telemetry::{LogEntry, LogLevel, SpanStatus, TraceSpan, TelemetryBatch};
 use crate::errors::{CollectorError, Result};
-use std::collections::VecDeque;
+use regex::Regex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::ops::DerefMut;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tracing::{debug, warn};
 
-/// Thread-safe buffer for telemetry data
+/// Thread-safe buffer for telemetry data. Entries are spread over one or
+/// more shards, each behind its own lock, so tasks tailing different files
+/// do not wait on each other to buffer. Entries from one source (a log file,
+/// or a trace for spans) always share a shard and drain in the order they
+/// were added; shards are merged by timestamp when draining, so entries
+/// from different sources can go out in a different order than they arrived.
 #[derive(Debug)]
 pub struct TelemetryBuffer {
-    logs: Arc<RwLock<VecDeque<LogEntry>>>,
-    spans: Arc<RwLock<VecDeque<TraceSpan>>>,
+    logs: Vec<RwLock<VecDeque<LogEntry>>>,
+    spans: Vec<RwLock<VecDeque<TraceSpan>>>,
+    /// Entries across all shards, read without taking a shard lock
+    log_count: AtomicUsize,
+    span_count: AtomicUsize,
+    /// When the buffer last went from empty to holding data
+    oldest_entry: Arc<RwLock<Option<Instant>>>,
     max_size: usize,
     batch_size: usize,
+    /// Largest batch drained as the buffer fills; at or below `batch_size`
+    /// batches are a fixed size
+    max_batch_size: usize,
 }
 
 impl TelemetryBuffer {
     /// Create a new telemetry buffer
     pub fn new(max_size: usize, batch_size: usize) -> Self {
         Self {
-            logs: Arc::new(RwLock::new(VecDeque::new())),
-            spans: Arc::new(RwLock::new(VecDeque::new())),
+            logs: shards(1),
+            spans: shards(1),
+            log_count: AtomicUsize::new(0),
+            span_count: AtomicUsize::new(0),
+            oldest_entry: Arc::new(RwLock::new(None)),
             max_size,
             batch_size,
+            max_batch_size: batch_size,
         }
     }
 
+    /// Grow batches up to `max_batch_size` as the buffer fills, so a backlog
+    /// drains in fewer, larger requests
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// Split the buffer into `count` shards. `max_size` still bounds the
+    /// whole buffer; an entry overflowing it drops the oldest entry of its
+    /// own shard, or of the shard with the oldest entry when its own is empty.
+    pub fn with_shards(mut self, count: usize) -> Self {
+        self.logs = shards(count);
+        self.spans = shards(count);
+        self
+    }
+
+    /// Number of shards per signal
+    pub fn shard_count(&self) -> usize {
+        self.logs.len()
+    }
+
+    /// Entries of one signal at which the buffer flushes regardless of batch size
+    fn flush_threshold(&self) -> usize {
+        self.max_size * 3 / 4
+    }
+
+    /// Batch size for a signal holding `buffered` entries: `batch_size` while
+    /// the buffer is nearly empty, growing linearly to `max_batch_size` as it
+    /// reaches the flush threshold
+    pub fn batch_size_for(&self, buffered: usize) -> usize {
+        if self.max_batch_size <= self.batch_size {
+            return self.batch_size;
+        }
+
+        let fill = (buffered as f64 / self.flush_threshold().max(1) as f64).min(1.0);
+        let growth = ((self.max_batch_size - self.batch_size) as f64 * fill).round() as usize;
+        self.batch_size + growth
+    }
+
     /// Add a log entry to the buffer
     pub async fn add_log(&self, log_entry: LogEntry) -> Result<()> {
-        let mut logs = self.logs.write().await;
+        let shard = shard_index(log_entry.attributes.get("log.source"), self.logs.len());
+        let (size, dropped) = self
+            .push_bounded(&self.logs, &self.log_count, shard, log_entry, |log| log.timestamp)
+            .await;
 
-        if logs.len() >= self.max_size {
-            logs.pop_front();
+        if dropped {
             warn!("Log buffer overflow, dropping oldest entry");
         }
-
-        logs.push_back(log_entry);
-        debug!("Added log entry to buffer, current size: {}", logs.len());
+        debug!("Added log entry to buffer, current size: {}", size);
 
         Ok(())
     }
 
     /// Add a trace span to the buffer
     pub async fn add_span(&self, span: TraceSpan) -> Result<()> {
-        let mut spans = self.spans.write().await;
+        let shard = shard_index(Some(&span.trace_id), self.spans.len());
+        let (size, dropped) = self
+            .push_bounded(&self.spans, &self.span_count, shard, span, |span| span.start_time)
+            .await;
 
-        if spans.len() >= self.max_size {
-            spans.pop_front();
+        if dropped {
             warn!("Span buffer overflow, dropping oldest entry");
         }
-
-        spans.push_back(span);
-        debug!("Added span to buffer, current size: {}", spans.len());
+        debug!("Added span to buffer, current size: {}", size);
 
         Ok(())
     }
 
+    /// Add `entry` to `shards[shard]` without letting `count` pass
+    /// `max_size`. Room is reserved atomically, so concurrent adds cannot
+    /// overshoot; a full buffer drops the oldest entry of the same shard, or
+    /// when that is empty the oldest front entry of any shard. Returns the
+    /// new size and whether an entry was dropped.
+    async fn push_bounded<T>(
+        &self,
+        shards: &[RwLock<VecDeque<T>>],
+        count: &AtomicUsize,
+        shard: usize,
+        entry: T,
+        timestamp: impl Fn(&T) -> u64,
+    ) -> (usize, bool) {
+        let mut dropped = false;
+        loop {
+            {
+                let mut entries = shards[shard].write().await;
+                let reserved = count
+                    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |size| (size < self.max_size).then_some(size + 1))
+                    .is_ok();
+
+                if reserved || entries.pop_front().is_some() {
+                    dropped |= !reserved;
+                    entries.push_back(entry);
+                    self.mark_oldest_entry().await;
+                    return (count.load(Ordering::Acquire), dropped);
+                }
+            }
+
+            // Full with nothing of its own to drop. The shard lock is released
+            // first, since drains take every shard lock in order.
+            if !evict_oldest(shards, count, &timestamp).await {
+                // Nothing buffered anywhere, e.g. a zero-sized buffer
+                shards[shard].write().await.push_back(entry);
+                let size = count.fetch_add(1, Ordering::AcqRel) + 1;
+                self.mark_oldest_entry().await;
+                return (size, dropped);
+            }
+            dropped = true;
+        }
+    }
+
+    /// Record when the buffer started holding data. Called with the shard
+    /// lock held, so a drain cannot clear the mark for the entry just added.
+    async fn mark_oldest_entry(&self) {
+        // Nearly every add finds it already set, so look before writing
+        if self.oldest_entry.read().await.is_none() {
+            self.oldest_entry.write().await.get_or_insert_with(Instant::now);
+        }
+    }
+
     /// Drain a batch of telemetry data from the buffer
     pub async fn drain_batch(
         &self,
@@ -65,18 +179,27 @@ impl TelemetryBuffer {
         source_namespace: String,
     ) -> Result<Option<TelemetryBatch>> {
         let (logs, spans) = {
-            let mut log_buffer = self.logs.write().await;
-            let mut span_buffer = self.spans.write().await;
+            // Locks are always taken logs first, in shard order
+            let mut log_shards = write_all(&self.logs).await;
+            let mut span_shards = write_all(&self.spans).await;
+            let buffered_logs = self.log_count.load(Ordering::Relaxed);
+            let buffered_spans = self.span_count.load(Ordering::Relaxed);
 
-            let log_count = std::cmp::min(self.batch_size, log_buffer.len());
-            let span_count = std::cmp::min(self.batch_size, span_buffer.len());
+            let log_count = std::cmp::min(self.batch_size_for(buffered_logs), buffered_logs);
+            let span_count = std::cmp::min(self.batch_size_for(buffered_spans), buffered_spans);
 
             if log_count == 0 && span_count == 0 {
                 return Ok(None);
             }
 
-            let logs: Vec<LogEntry> = log_buffer.drain(..log_count).collect();
-            let spans: Vec<TraceSpan> = span_buffer.drain(..span_count).collect();
+            let logs = take_oldest(&mut log_shards, log_count, |log| log.timestamp);
+            let spans = take_oldest(&mut span_shards, span_count, |span| span.start_time);
+            self.log_count.fetch_sub(logs.len(), Ordering::Relaxed);
+            self.span_count.fetch_sub(spans.len(), Ordering::Relaxed);
+
+            if buffered_logs == logs.len() && buffered_spans == spans.len() {
+                *self.oldest_entry.write().await = None;
+            }
 
             (logs, spans)
         };
@@ -96,11 +219,60 @@ impl TelemetryBuffer {
         )))
     }
 
+    /// Drain up to a batch of log entries, leaving spans untouched
+    pub async fn drain_logs(&self) -> Vec<LogEntry> {
+        let mut log_shards = write_all(&self.logs).await;
+        let _span_shards = read_all(&self.spans).await;
+        let buffered = self.log_count.load(Ordering::Relaxed);
+        let count = std::cmp::min(self.batch_size_for(buffered), buffered);
+        let logs = take_oldest(&mut log_shards, count, |log| log.timestamp);
+        self.log_count.fetch_sub(logs.len(), Ordering::Relaxed);
+
+        if buffered == logs.len() && self.span_count.load(Ordering::Relaxed) == 0 {
+            *self.oldest_entry.write().await = None;
+        }
+
+        logs
+    }
+
+    /// Drain up to a batch of spans, leaving log entries untouched
+    pub async fn drain_spans(&self) -> Vec<TraceSpan> {
+        // Locks are always taken logs first
+        let _log_shards = read_all(&self.logs).await;
+        let mut span_shards = write_all(&self.spans).await;
+        let buffered = self.span_count.load(Ordering::Relaxed);
+        let count = std::cmp::min(self.batch_size_for(buffered), buffered);
+        let spans = take_oldest(&mut span_shards, count, |span| span.start_time);
+        self.span_count.fetch_sub(spans.len(), Ordering::Relaxed);
+
+        if buffered == spans.len() && self.log_count.load(Ordering::Relaxed) == 0 {
+            *self.oldest_entry.write().await = None;
+        }
+
+        spans
+    }
+
     /// Get the current buffer sizes
     pub async fn sizes(&self) -> (usize, usize) {
-        let logs = self.logs.read().await;
-        let spans = self.spans.read().await;
-        (logs.len(), spans.len())
+        (self.log_count.load(Ordering::Relaxed), self.span_count.load(Ordering::Relaxed))
+    }
+
+    /// Copies of everything buffered, oldest first, leaving the buffer as is
+    pub async fn snapshot(&self) -> (Vec<LogEntry>, Vec<TraceSpan>) {
+        let log_shards = read_all(&self.logs).await;
+        let span_shards = read_all(&self.spans).await;
+        let mut logs: Vec<VecDeque<LogEntry>> = log_shards.iter().map(|shard| (**shard).clone()).collect();
+        let mut spans: Vec<VecDeque<TraceSpan>> = span_shards.iter().map(|shard| (**shard).clone()).collect();
+        (
+            take_oldest(&mut logs.iter_mut().collect::<Vec<_>>(), usize::MAX, |log| log.timestamp),
+            take_oldest(&mut spans.iter_mut().collect::<Vec<_>>(), usize::MAX, |span| span.start_time),
+        )
+    }
+
+    /// How long the buffer has been holding data; entries left behind by a
+    /// partial drain keep the age of the first entry
+    pub async fn oldest_age(&self) -> Option<Duration> {
+        self.oldest_entry.read().await.map(|added| added.elapsed())
     }
 
     /// Check if the buffer has data ready for batching
@@ -111,12 +283,18 @@ impl TelemetryBuffer {
 
     /// Check if the buffer should be flushed (has enough data or is getting full)
     pub async fn should_flush(&self) -> bool {
+        self.flush_readiness().await.any()
+    }
+
+    /// Report which signals have enough data (or are close enough to full) to flush
+    pub async fn flush_readiness(&self) -> FlushReadiness {
         let (log_count, span_count) = self.sizes().await;
+        let threshold = self.flush_threshold();
 
-        log_count >= self.batch_size
-            || span_count >= self.batch_size
-            || log_count >= (self.max_size * 3 / 4)
-            || span_count >= (self.max_size * 3 / 4)
+        FlushReadiness {
+            logs: log_count >= self.batch_size_for(log_count) || log_count >= threshold,
+            spans: span_count >= self.batch_size_for(span_count) || span_count >= threshold,
+        }
     }
 
     /// Force flush all buffered data
@@ -142,15 +320,25 @@ impl TelemetryBuffer {
 
     /// Clear all buffered data
     pub async fn clear(&self) {
-        let mut logs = self.logs.write().await;
-        let mut spans = self.spans.write().await;
+        let mut log_shards = write_all(&self.logs).await;
+        let mut span_shards = write_all(&self.spans).await;
 
-        logs.clear();
-        spans.clear();
+        log_shards.iter_mut().for_each(|shard| shard.clear());
+        span_shards.iter_mut().for_each(|shard| shard.clear());
+        self.log_count.store(0, Ordering::Relaxed);
+        self.span_count.store(0, Ordering::Relaxed);
+        *self.oldest_entry.write().await = None;
 
         debug!("Cleared all buffered data");
     }
 
+    /// Utilization of the fuller signal as a percentage; each signal
+    /// overflows on its own once it holds `max_size` entries
+    pub async fn peak_utilization(&self) -> f64 {
+        let (log_count, span_count) = self.sizes().await;
+        (log_count.max(span_count) as f64 / self.max_size as f64) * 100.0
+    }
+
     /// Get buffer utilization as a percentage
     pub async fn utilization(&self) -> f64 {
         let (log_count, span_count) = self.sizes().await;
@@ -161,12 +349,114 @@ impl TelemetryBuffer {
     }
 }
 
+fn shards<T>(count: usize) -> Vec<RwLock<VecDeque<T>>> {
+    (0..count.max(1)).map(|_| RwLock::new(VecDeque::new())).collect()
+}
+
+/// Shard for entries from `source`, stable for the life of the process
+fn shard_index(source: Option<&String>, shard_count: usize) -> usize {
+    if shard_count <= 1 {
+        return 0;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    (hasher.finish() % shard_count as u64) as usize
+}
+
+async fn write_all<T>(shards: &[RwLock<VecDeque<T>>]) -> Vec<RwLockWriteGuard<'_, VecDeque<T>>> {
+    let mut guards = Vec::with_capacity(shards.len());
+    for shard in shards {
+        guards.push(shard.write().await);
+    }
+    guards
+}
+
+async fn read_all<T>(shards: &[RwLock<VecDeque<T>>]) -> Vec<RwLockReadGuard<'_, VecDeque<T>>> {
+    let mut guards = Vec::with_capacity(shards.len());
+    for shard in shards {
+        guards.push(shard.read().await);
+    }
+    guards
+}
+
+/// Drop the oldest front entry across `shards`, taking one shard lock at a
+/// time. Returns false when every shard is empty.
+async fn evict_oldest<T>(shards: &[RwLock<VecDeque<T>>], count: &AtomicUsize, timestamp: impl Fn(&T) -> u64) -> bool {
+    let mut oldest: Option<(usize, u64)> = None;
+    for (index, shard) in shards.iter().enumerate() {
+        if let Some(front) = shard.read().await.front().map(&timestamp)
+            && oldest.is_none_or(|(_, oldest)| front < oldest)
+        {
+            oldest = Some((index, front));
+        }
+    }
+
+    let Some((index, _)) = oldest else {
+        return false;
+    };
+    // A drain may have emptied the shard meanwhile, which made room too
+    if shards[index].write().await.pop_front().is_some() {
+        count.fetch_sub(1, Ordering::AcqRel);
+    }
+    true
+}
+
+/// Take up to `count` entries across `shards`, repeatedly taking the front
+/// entry with the earliest timestamp so each shard keeps its own order
+fn take_oldest<T, S>(shards: &mut [S], count: usize, timestamp: impl Fn(&T) -> u64) -> Vec<T>
+where
+    S: DerefMut<Target = VecDeque<T>>,
+{
+    if let [shard] = shards {
+        let count = count.min(shard.len());
+        return shard.drain(..count).collect();
+    }
+
+    let mut taken = Vec::new();
+    while taken.len() < count {
+        let oldest = shards
+            .iter()
+            .enumerate()
+            .filter_map(|(index, shard)| shard.front().map(|entry| (timestamp(entry), index)))
+            .min();
+        let Some((_, index)) = oldest else { break };
+        taken.extend(shards[index].pop_front());
+    }
+    taken
+}
+
+/// Which signals are ready to be flushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlushReadiness {
+    pub logs: bool,
+    pub spans: bool,
+}
+
+impl FlushReadiness {
+    /// Whether any signal is ready
+    pub fn any(&self) -> bool {
+        self.logs || self.spans
+    }
+}
+
 /// Configuration for buffer behavior
 #[derive(Debug, Clone)]
 pub struct BufferConfig {
     pub max_size: usize,
     pub batch_size: usize,
+    /// Largest normal-priority batch as the buffer fills; at or below
+    /// `batch_size` batches are a fixed size
+    pub max_batch_size: usize,
     pub flush_threshold: f64,
+    /// Shards per sub-buffer, see `TelemetryBuffer::with_shards`
+    pub shards: usize,
+    /// Split the buffer into high- and normal-priority parts; when off all
+    /// telemetry shares one buffer of `max_size`
+    pub enable_priority: bool,
+    /// Fraction of `max_size` given to the high-priority buffer, in (0, 1)
+    pub high_priority_fraction: f64,
+    pub priority_rules: PriorityRules,
 }
 
 impl Default for BufferConfig {
@@ -174,52 +464,196 @@ impl Default for BufferConfig {
         Self {
             max_size: 10000,
             batch_size: 100,
+            max_batch_size: 0,
             flush_threshold: 75.0,
+            shards: 1,
+            enable_priority: true,
+            high_priority_fraction: 0.25,
+            priority_rules: PriorityRules::default(),
+        }
+    }
+}
+
+impl BufferConfig {
+    /// Build buffer configuration from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let buffer_config = Self {
+            max_size: config.max_buffer_size,
+            batch_size: config.batch_size,
+            max_batch_size: config.max_batch_size,
+            shards: config.buffer_shards,
+            enable_priority: config.enable_priority_buffer,
+            high_priority_fraction: config.high_priority_buffer_fraction,
+            priority_rules: PriorityRules::from_config(config)?,
+            ..Self::default()
+        };
+        buffer_config.validate()?;
+        Ok(buffer_config)
+    }
+
+    /// Check that the priority split is usable
+    pub fn validate(&self) -> Result<()> {
+        if !(self.high_priority_fraction > 0.0 && self.high_priority_fraction < 1.0) {
+            return Err(CollectorError::Config(format!(
+                "high priority fraction must be between 0 and 1, got {}",
+                self.high_priority_fraction
+            )));
         }
+        Ok(())
+    }
+
+    /// Capacity of the high-priority buffer
+    pub fn high_priority_capacity(&self) -> usize {
+        ((self.max_size as f64 * self.high_priority_fraction).round() as usize).max(1)
+    }
+
+    /// Capacity of the normal-priority buffer
+    pub fn normal_priority_capacity(&self) -> usize {
+        self.max_size.saturating_sub(self.high_priority_capacity()).max(1)
+    }
+
+    /// Batch size for the high-priority buffer, scaled by twice its share of
+    /// the buffer so the default 25% split drains half-size batches
+    pub fn high_priority_batch_size(&self) -> usize {
+        let scale = (self.high_priority_fraction * 2.0).min(1.0);
+        ((self.batch_size as f64 * scale).round() as usize).max(1)
+    }
+}
+
+/// Rules deciding which telemetry is routed to the high-priority buffer
+#[derive(Debug, Clone)]
+pub struct PriorityRules {
+    pub log_levels: Vec<LogLevel>,
+    pub log_message_patterns: Vec<Regex>,
+    pub span_statuses: Vec<SpanStatus>,
+    pub span_duration_threshold_ms: u64,
+    /// Thresholds for specific operations, replacing `span_duration_threshold_ms`
+    pub span_duration_overrides_ms: HashMap<String, u64>,
+    pub span_tag_patterns: Vec<Regex>,
+}
+
+impl Default for PriorityRules {
+    fn default() -> Self {
+        Self::from_config(&Config::default()).expect("default priority patterns are valid")
     }
 }
 
-/// A more advanced buffer with priority handling
+impl PriorityRules {
+    /// Build priority rules from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self {
+            log_levels: config.high_priority_levels.clone(),
+            log_message_patterns: compile_patterns(&config.high_priority_patterns)?,
+            span_statuses: config.high_priority_span_statuses.clone(),
+            span_duration_threshold_ms: config.high_priority_span_duration_ms,
+            span_duration_overrides_ms: config.high_priority_span_duration_overrides_ms.clone(),
+            span_tag_patterns: compile_patterns(&config.high_priority_span_tag_patterns)?,
+        })
+    }
+
+    /// Determine if a log entry should be high priority
+    pub fn is_high_priority_log(&self, log_entry: &LogEntry) -> bool {
+        self.log_levels.contains(&log_entry.level)
+            || self.log_message_patterns.iter().any(|p| p.is_match(&log_entry.message))
+    }
+
+    /// Duration above which a span of `operation` is high priority
+    pub fn span_duration_threshold_ms(&self, operation: &str) -> u64 {
+        self.span_duration_overrides_ms
+            .get(operation)
+            .copied()
+            .unwrap_or(self.span_duration_threshold_ms)
+    }
+
+    /// Determine if a span should be high priority
+    pub fn is_high_priority_span(&self, span: &TraceSpan) -> bool {
+        self.span_statuses.contains(&span.status)
+            || span.duration_ms > self.span_duration_threshold_ms(&span.operation_name)
+            || span.tags.values().any(|v| self.span_tag_patterns.iter().any(|p| p.is_match(v)))
+    }
+}
+
+/// Compile case-insensitive priority patterns
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(&format!("(?i){}", pattern)).map_err(|e| {
+                CollectorError::Config(format!("invalid priority pattern '{}': {}", pattern, e))
+            })
+        })
+        .collect()
+}
+
+/// A more advanced buffer with priority handling. High-priority telemetry
+/// has its own share of the buffer and is drained first, so a flood of
+/// normal logs cannot evict errors. With priority disabled everything goes
+/// to the normal-priority buffer, which then has the whole capacity.
 #[derive(Debug)]
 pub struct PriorityTelemetryBuffer {
-    high_priority: TelemetryBuffer,
+    high_priority: Option<TelemetryBuffer>,
     normal_priority: TelemetryBuffer,
     config: BufferConfig,
 }
 
 impl PriorityTelemetryBuffer {
     pub fn new(config: BufferConfig) -> Self {
+        if !config.enable_priority {
+            return Self {
+                high_priority: None,
+                normal_priority: TelemetryBuffer::new(config.max_size, config.batch_size)
+                    .with_max_batch_size(config.max_batch_size)
+                    .with_shards(config.shards),
+                config,
+            };
+        }
+
         Self {
-            high_priority: TelemetryBuffer::new(
-                config.max_size / 4,
-                config.batch_size / 2,
-            ),
-            normal_priority: TelemetryBuffer::new(
-                config.max_size * 3 / 4,
-                config.batch_size,
+            // High-priority batches stay small so they go out promptly
+            high_priority: Some(
+                TelemetryBuffer::new(config.high_priority_capacity(), config.high_priority_batch_size())
+                    .with_shards(config.shards),
             ),
+            normal_priority: TelemetryBuffer::new(config.normal_priority_capacity(), config.batch_size)
+                .with_max_batch_size(config.max_batch_size)
+                .with_shards(config.shards),
             config,
         }
     }
 
+    /// The sub-buffers, high priority first
+    fn buffers(&self) -> impl Iterator<Item = &TelemetryBuffer> {
+        self.high_priority.iter().chain(std::iter::once(&self.normal_priority))
+    }
+
     /// Add a log entry with priority
     pub async fn add_log(&self, log_entry: LogEntry, high_priority: bool) -> Result<()> {
-        if high_priority {
-            self.high_priority.add_log(log_entry).await
-        } else {
-            self.normal_priority.add_log(log_entry).await
+        match &self.high_priority {
+            Some(buffer) if high_priority => buffer.add_log(log_entry).await,
+            _ => self.normal_priority.add_log(log_entry).await,
         }
     }
 
     /// Add a span with priority
     pub async fn add_span(&self, span: TraceSpan, high_priority: bool) -> Result<()> {
-        if high_priority {
-            self.high_priority.add_span(span).await
-        } else {
-            self.normal_priority.add_span(span).await
+        match &self.high_priority {
+            Some(buffer) if high_priority => buffer.add_span(span).await,
+            _ => self.normal_priority.add_span(span).await,
         }
     }
 
+    /// Add a log entry, classifying its priority with the configured rules
+    pub async fn add_classified_log(&self, log_entry: LogEntry) -> Result<()> {
+        let high_priority = self.config.priority_rules.is_high_priority_log(&log_entry);
+        self.add_log(log_entry, high_priority).await
+    }
+
+    /// Add a span, classifying its priority with the configured rules
+    pub async fn add_classified_span(&self, span: TraceSpan) -> Result<()> {
+        let high_priority = self.config.priority_rules.is_high_priority_span(&span);
+        self.add_span(span, high_priority).await
+    }
+
     /// Drain a batch, prioritizing high-priority data
     pub async fn drain_batch(
         &self,
@@ -227,25 +661,83 @@ impl PriorityTelemetryBuffer {
         source_pod: String,
         source_namespace: String,
     ) -> Result<Option<TelemetryBatch>> {
-        if let Some(batch) = self.high_priority.drain_batch(
-            collector_id.clone(),
-            source_pod.clone(),
-            source_namespace.clone(),
-        ).await? {
+        if let Some(high_priority) = &self.high_priority
+            && let Some(batch) = high_priority.drain_batch(
+                collector_id.clone(),
+                source_pod.clone(),
+                source_namespace.clone(),
+            ).await?
+        {
             return Ok(Some(batch));
         }
 
         self.normal_priority.drain_batch(collector_id, source_pod, source_namespace).await
     }
 
+    /// Get the current buffer sizes across both priorities
+    pub async fn sizes(&self) -> (usize, usize) {
+        let mut totals = (0, 0);
+        for buffer in self.buffers() {
+            let (logs, spans) = buffer.sizes().await;
+            totals.0 += logs;
+            totals.1 += spans;
+        }
+        totals
+    }
+
+    /// Check if either buffer has data ready for batching
+    pub async fn has_data(&self) -> bool {
+        let (log_count, span_count) = self.sizes().await;
+        log_count > 0 || span_count > 0
+    }
+
     /// Check if should flush any buffer
     pub async fn should_flush(&self) -> bool {
-        self.high_priority.should_flush().await || self.normal_priority.should_flush().await
+        for buffer in self.buffers() {
+            if buffer.should_flush().await {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// How long the longest-waiting buffer has been holding data
+    pub async fn oldest_age(&self) -> Option<Duration> {
+        let mut oldest = None;
+        for buffer in self.buffers() {
+            oldest = oldest.max(buffer.oldest_age().await);
+        }
+        oldest
+    }
+
+    /// Copies of everything buffered, high priority first, leaving the
+    /// buffers as they are
+    pub async fn snapshot(&self) -> (Vec<LogEntry>, Vec<TraceSpan>) {
+        let (mut logs, mut spans) = (Vec::new(), Vec::new());
+        for buffer in self.buffers() {
+            let (buffered_logs, buffered_spans) = buffer.snapshot().await;
+            logs.extend(buffered_logs);
+            spans.extend(buffered_spans);
+        }
+        (logs, spans)
+    }
+
+    /// Utilization of the fullest signal of either buffer as a percentage;
+    /// each overflows on its own
+    pub async fn peak_utilization(&self) -> f64 {
+        let mut peak: f64 = 0.0;
+        for buffer in self.buffers() {
+            peak = peak.max(buffer.peak_utilization().await);
+        }
+        peak
     }
 
     /// Get combined buffer statistics
     pub async fn stats(&self) -> BufferStats {
-        let (hp_logs, hp_spans) = self.high_priority.sizes().await;
+        let (hp_logs, hp_spans) = match &self.high_priority {
+            Some(buffer) => buffer.sizes().await,
+            None => (0, 0),
+        };
         let (np_logs, np_spans) = self.normal_priority.sizes().await;
 
         BufferStats {
@@ -259,12 +751,12 @@ impl PriorityTelemetryBuffer {
         }
     }
 
-    async fn utilization(&self) -> f64 {
-        let stats = self.stats().await;
-        let total_used = stats.total_logs + stats.total_spans;
+    /// Get buffer utilization as a percentage
+    pub async fn utilization(&self) -> f64 {
+        let (log_count, span_count) = self.sizes().await;
         let total_capacity = self.config.max_size * 2;
 
-        (total_used as f64 / total_capacity as f64) * 100.0
+        ((log_count + span_count) as f64 / total_capacity as f64) * 100.0
     }
 }
 
@@ -281,32 +773,25 @@ pub struct BufferStats {
 }
 
 /// Helper function to determine if a log entry should be high priority
+/// using the default rules
 pub fn is_high_priority_log(log_entry: &LogEntry) -> bool {
-    use crate::telemetry::LogLevel;
-
-    matches!(log_entry.level, LogLevel::Error | LogLevel::Fatal)
-        || log_entry.message.to_lowercase().contains("critical")
-        || log_entry.message.to_lowercase().contains("security")
-        || log_entry.message.to_lowercase().contains("alert")
+    default_priority_rules().is_high_priority_log(log_entry)
 }
 
 /// Helper function to determine if a span should be high priority
+/// using the default rules
 pub fn is_high_priority_span(span: &TraceSpan) -> bool {
-    use crate::telemetry::SpanStatus;
-
-    matches!(span.status, SpanStatus::Error | SpanStatus::Timeout)
-        || span.duration_ms > 10000 // Spans longer than 10 seconds
-        || span.tags.values().any(|v| {
-            v.to_lowercase().contains("error")
-                || v.to_lowercase().contains("timeout")
-                || v.to_lowercase().contains("critical")
-        })
+    default_priority_rules().is_high_priority_span(span)
+}
+
+fn default_priority_rules() -> &'static PriorityRules {
+    static RULES: std::sync::OnceLock<PriorityRules> = std::sync::OnceLock::new();
+    RULES.get_or_init(PriorityRules::default)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::telemetry::{LogLevel, SpanStatus};
 
     #[tokio::test]
     async fn test_basic_buffer_operations() {
@@ -423,4 +908,377 @@ mod tests {
         assert!(is_high_priority_log(&error_log));
         assert!(!is_high_priority_log(&info_log));
     }
+
+    #[tokio::test]
+    async fn test_custom_priority_rules_promote_warn() {
+        let config = Config {
+            enable_priority_buffer: true,
+            high_priority_levels: vec![LogLevel::Warn, LogLevel::Error, LogLevel::Fatal],
+            high_priority_patterns: vec![r"auth(entication)? failure".to_string()],
+            ..Config::default()
+        };
+        let buffer = PriorityTelemetryBuffer::new(BufferConfig::from_config(&config).unwrap());
+
+        let warn_log = LogEntry::new(
+            LogLevel::Warn,
+            "Suspicious login attempt".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        let pattern_log = LogEntry::new(
+            LogLevel::Info,
+            "Authentication failure for user bob".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        let info_log = LogEntry::new(
+            LogLevel::Info,
+            "Critical path warmed".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        buffer.add_classified_log(warn_log).await.unwrap();
+        buffer.add_classified_log(pattern_log).await.unwrap();
+        buffer.add_classified_log(info_log).await.unwrap();
+
+        let stats = buffer.stats().await;
+        assert_eq!(stats.high_priority_logs, 2);
+        assert_eq!(stats.normal_priority_logs, 1); // "critical" is no longer a configured pattern
+    }
+
+    #[tokio::test]
+    async fn test_span_duration_override() {
+        let config = Config {
+            enable_priority_buffer: true,
+            high_priority_span_duration_overrides_ms: [("nightly.export".to_string(), 600_000)].into(),
+            ..Config::default()
+        };
+        let buffer = PriorityTelemetryBuffer::new(BufferConfig::from_config(&config).unwrap());
+        let span = |operation: &str, status: SpanStatus, duration_ms: u64| {
+            TraceSpan::new(
+                "trace-1".to_string(),
+                "span-1".to_string(),
+                operation.to_string(),
+                "test-service".to_string(),
+            )
+            .with_status(status)
+            .set_duration_ms(duration_ms)
+        };
+
+        // Five minutes is normal for the export job but not for a request
+        buffer.add_classified_span(span("nightly.export", SpanStatus::Ok, 300_000)).await.unwrap();
+        buffer.add_classified_span(span("GET /orders", SpanStatus::Ok, 300_000)).await.unwrap();
+        buffer.add_classified_span(span("nightly.export", SpanStatus::Error, 1_000)).await.unwrap();
+        buffer.add_classified_span(span("nightly.export", SpanStatus::Ok, 900_000)).await.unwrap();
+
+        let stats = buffer.stats().await;
+        assert_eq!(stats.high_priority_spans, 3);
+        assert_eq!(stats.normal_priority_spans, 1);
+    }
+
+    #[test]
+    fn test_custom_span_priority_rules() {
+        let config = Config {
+            high_priority_span_statuses: vec![SpanStatus::Error],
+            high_priority_span_duration_ms: 60000,
+            high_priority_span_tag_patterns: vec!["^degraded$".to_string()],
+            ..Config::default()
+        };
+        let rules = PriorityRules::from_config(&config).unwrap();
+
+        let span = TraceSpan::new(
+            "trace-123".to_string(),
+            "span-456".to_string(),
+            "batch_job".to_string(),
+            "test-service".to_string(),
+        );
+
+        let long_span = span.clone().set_duration_ms(30000);
+        let timeout_span = span.clone().with_status(SpanStatus::Timeout);
+        let error_span = span.clone().with_status(SpanStatus::Error);
+        let tagged_span = span.with_tag("health".to_string(), "Degraded".to_string());
+
+        assert!(!rules.is_high_priority_span(&long_span));
+        assert!(!rules.is_high_priority_span(&timeout_span));
+        assert!(rules.is_high_priority_span(&error_span));
+        assert!(rules.is_high_priority_span(&tagged_span));
+    }
+
+    #[test]
+    fn test_invalid_priority_pattern() {
+        let config = Config {
+            high_priority_patterns: vec!["(unclosed".to_string()],
+            ..Config::default()
+        };
+
+        assert!(PriorityRules::from_config(&config).is_err());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_custom_priority_split() {
+        let config = BufferConfig {
+            max_size: 1000,
+            batch_size: 100,
+            high_priority_fraction: 0.4,
+            ..BufferConfig::default()
+        };
+        let buffer = PriorityTelemetryBuffer::new(config);
+
+        let high_priority = buffer.high_priority.as_ref().unwrap();
+        assert_eq!(high_priority.max_size, 400);
+        assert_eq!(buffer.normal_priority.max_size, 600);
+        assert_eq!(high_priority.batch_size, 80);
+        assert_eq!(buffer.normal_priority.batch_size, 100);
+
+        let default_buffer = PriorityTelemetryBuffer::new(BufferConfig::default());
+        let high_priority = default_buffer.high_priority.as_ref().unwrap();
+        assert_eq!(high_priority.max_size, 2500);
+        assert_eq!(default_buffer.normal_priority.max_size, 7500);
+        assert_eq!(high_priority.batch_size, 50);
+
+        // Without priority the normal buffer gets everything
+        let single = PriorityTelemetryBuffer::new(BufferConfig {
+            enable_priority: false,
+            ..BufferConfig::default()
+        });
+        assert!(single.high_priority.is_none());
+        assert_eq!(single.normal_priority.max_size, 10000);
+    }
+
+    #[test]
+    fn test_invalid_priority_split() {
+        for fraction in [0.0, 1.0, -0.5, f64::NAN] {
+            let config = Config {
+                high_priority_buffer_fraction: fraction,
+                ..Config::default()
+            };
+
+            assert!(BufferConfig::from_config(&config).is_err());
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_independent_draining() {
+        let buffer = TelemetryBuffer::new(100, 2);
+
+        for i in 0..3 {
+            buffer.add_log(LogEntry::new(
+                LogLevel::Info,
+                format!("Message {}", i),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )).await.unwrap();
+        }
+        buffer.add_span(TraceSpan::new(
+            "trace-1".to_string(),
+            "span-1".to_string(),
+            "operation".to_string(),
+            "test-service".to_string(),
+        )).await.unwrap();
+
+        let readiness = buffer.flush_readiness().await;
+        assert!(readiness.logs);
+        assert!(!readiness.spans);
+        assert!(buffer.should_flush().await);
+
+        let logs = buffer.drain_logs().await;
+        assert_eq!(logs.len(), 2); // bounded by batch_size
+        assert_eq!(logs[0].message, "Message 0");
+        assert_eq!(buffer.sizes().await, (1, 1));
+
+        let spans = buffer.drain_spans().await;
+        assert_eq!(spans.len(), 1);
+        assert_eq!(buffer.sizes().await, (1, 0));
+
+        assert!(buffer.drain_spans().await.is_empty());
+        assert_eq!(buffer.drain_logs().await[0].message, "Message 2");
+        assert!(!buffer.has_data().await);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_batch_size() {
+        let buffer = TelemetryBuffer::new(1000, 10).with_max_batch_size(100);
+        let log = || LogEntry::new(
+            LogLevel::Info,
+            "Test message".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        // Nearly empty: close to the base batch size
+        for _ in 0..20 {
+            buffer.add_log(log()).await.unwrap();
+        }
+        assert!(buffer.should_flush().await);
+        assert_eq!(buffer.drain_logs().await.len(), 12);
+
+        // At the flush threshold (750 of 1000) batches reach the maximum
+        for _ in 0..742 {
+            buffer.add_log(log()).await.unwrap();
+        }
+        assert_eq!(buffer.drain_logs().await.len(), 100);
+
+        // Smaller again as the backlog drains
+        let mut drained = Vec::new();
+        for _ in 0..6 {
+            drained.push(buffer.drain_logs().await.len());
+        }
+        assert_eq!(drained, vec![88, 77, 68, 60, 53, 46]);
+
+        // A fixed batch size without a larger maximum
+        let fixed = TelemetryBuffer::new(1000, 10);
+        assert_eq!(fixed.batch_size_for(750), 10);
+    }
+
+    #[tokio::test]
+    async fn test_oldest_age() {
+        let buffer = TelemetryBuffer::new(10, 1);
+        assert_eq!(buffer.oldest_age().await, None);
+
+        for i in 0..2 {
+            buffer.add_log(LogEntry::new(
+                LogLevel::Info,
+                format!("Message {}", i),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )).await.unwrap();
+        }
+        let first_age = buffer.oldest_age().await.unwrap();
+
+        // A partial drain keeps the age of the earliest entry
+        buffer.drain_batch("c".to_string(), "p".to_string(), "n".to_string()).await.unwrap();
+        assert!(buffer.oldest_age().await.unwrap() >= first_age);
+
+        buffer.drain_batch("c".to_string(), "p".to_string(), "n".to_string()).await.unwrap();
+        assert_eq!(buffer.oldest_age().await, None);
+    }
+
+    #[test]
+    fn test_take_oldest_merges_shards_by_timestamp() {
+        let mut shards = [VecDeque::from([3, 4, 9]), VecDeque::from([1, 7]), VecDeque::new(), VecDeque::from([2, 8])];
+        let mut refs: Vec<_> = shards.iter_mut().collect();
+
+        assert_eq!(take_oldest(&mut refs, 4, |n: &u64| *n), vec![1, 2, 3, 4]);
+        assert_eq!(take_oldest(&mut refs, usize::MAX, |n: &u64| *n), vec![7, 8, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_sharded_buffer_keeps_source_order() {
+        let buffer = TelemetryBuffer::new(100, 100).with_shards(4);
+        assert_eq!(buffer.shard_count(), 4);
+
+        // Lines are read out of timestamp order across files, and one file
+        // has a clock step backwards
+        for (timestamp, source) in [(3, "b.log"), (1, "a.log"), (2, "c.log"), (5, "a.log"), (4, "b.log"), (0, "c.log")] {
+            let mut log_entry = LogEntry::new(
+                LogLevel::Info,
+                format!("{}@{}", source, timestamp),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            );
+            log_entry.timestamp = timestamp;
+            log_entry.attributes.insert("log.source".to_string(), source.to_string());
+            buffer.add_log(log_entry).await.unwrap();
+        }
+        assert_eq!(buffer.sizes().await, (6, 0));
+
+        let batch = buffer.drain_batch("c".to_string(), "p".to_string(), "n".to_string()).await.unwrap().unwrap();
+        let from = |source: &str| -> Vec<String> {
+            batch.logs.iter().filter(|log| log.message.starts_with(source)).map(|log| log.message.clone()).collect()
+        };
+        assert_eq!(from("a.log"), vec!["a.log@1", "a.log@5"]);
+        assert_eq!(from("b.log"), vec!["b.log@3", "b.log@4"]);
+        assert_eq!(from("c.log"), vec!["c.log@2", "c.log@0"]);
+        assert_eq!(buffer.sizes().await, (0, 0));
+        assert_eq!(buffer.oldest_age().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_sharded_buffer_stays_bounded() {
+        let buffer = TelemetryBuffer::new(10, 10).with_shards(4);
+
+        for i in 0..100 {
+            let mut log_entry = LogEntry::new(
+                LogLevel::Info,
+                format!("Message {}", i),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            );
+            log_entry.attributes.insert("log.source".to_string(), format!("{}.log", i % 8));
+            buffer.add_log(log_entry).await.unwrap();
+        }
+
+        let (log_count, _) = buffer.sizes().await;
+        assert_eq!(log_count, 10);
+    }
+
+    fn sourced_log(source: &str, timestamp: u64) -> LogEntry {
+        let mut log_entry = LogEntry::new(
+            LogLevel::Info,
+            format!("{}@{}", source, timestamp),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        log_entry.timestamp = timestamp;
+        log_entry.attributes.insert("log.source".to_string(), source.to_string());
+        log_entry
+    }
+
+    #[tokio::test]
+    async fn test_overflow_into_empty_shard_evicts_oldest_elsewhere() {
+        let buffer = TelemetryBuffer::new(3, 10).with_shards(8);
+        // Two sources landing in different shards
+        let busy = "0.log".to_string();
+        let quiet = (1..)
+            .map(|i| format!("{}.log", i))
+            .find(|source| shard_index(Some(source), 8) != shard_index(Some(&busy), 8))
+            .unwrap();
+
+        for timestamp in 1..=3 {
+            buffer.add_log(sourced_log(&busy, timestamp)).await.unwrap();
+        }
+        buffer.add_log(sourced_log(&quiet, 4)).await.unwrap();
+
+        assert_eq!(buffer.sizes().await, (3, 0));
+        let batch = buffer.drain_batch("c".to_string(), "p".to_string(), "n".to_string()).await.unwrap().unwrap();
+        let messages: Vec<_> = batch.logs.iter().map(|log| log.message.clone()).collect();
+        assert_eq!(messages, vec![format!("{}@2", busy), format!("{}@3", busy), format!("{}@4", quiet)]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_adds_stay_bounded() {
+        let buffer = Arc::new(TelemetryBuffer::new(50, 10).with_shards(4));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let buffer = Arc::clone(&buffer);
+                tokio::spawn(async move {
+                    for i in 0..200 {
+                        buffer.add_log(sourced_log(&format!("{}.log", task), i)).await.unwrap();
+                        assert!(buffer.sizes().await.0 <= 50);
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(buffer.sizes().await.0, 50);
+        assert_eq!(buffer.snapshot().await.0.len(), 50);
+    }
 }
@@ -0,0 +1,85 @@
+//! On-disk snapshots of buffered telemetry, restored after a crash
+
+use crate::errors::Result;
+use crate::files;
+use crate::telemetry::{LogEntry, TraceSpan};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Buffered entries written to disk so a crash between flushes loses at
+/// most one checkpoint interval of data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub logs: Vec<LogEntry>,
+    pub spans: Vec<TraceSpan>,
+}
+
+impl Checkpoint {
+    pub fn new(logs: Vec<LogEntry>, spans: Vec<TraceSpan>) -> Self {
+        Self { logs, spans }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.logs.is_empty() && self.spans.is_empty()
+    }
+
+    /// Read the checkpoint at `path`, if there is one
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace the checkpoint at `path` with this one, or remove it when
+    /// there is nothing buffered. A crash mid-write keeps the previous
+    /// checkpoint intact, see [`files::replace`].
+    pub fn store(&self, path: &Path) -> Result<()> {
+        if self.is_empty() {
+            return Self::remove(path);
+        }
+
+        files::replace(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Delete the checkpoint at `path`; a missing file is not an error
+    pub fn remove(path: &Path) -> Result<()> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::LogLevel;
+
+    #[test]
+    fn test_store_load_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("buffer.checkpoint");
+        assert!(Checkpoint::load(&path).unwrap().is_none());
+
+        let log = LogEntry::new(
+            LogLevel::Error,
+            "payment failed".to_string(),
+            "checkout".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        );
+        Checkpoint::new(vec![log.clone()], Vec::new()).store(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.logs, vec![log]);
+        assert!(!path.with_extension("tmp").exists());
+
+        // Storing an empty checkpoint removes the file
+        Checkpoint::default().store(&path).unwrap();
+        assert!(!path.exists());
+        Checkpoint::remove(&path).unwrap();
+    }
+}
@@ -1,105 +1,506 @@
 //! Main sidecar collector implementation
 
-use crate::config::Config;
-use crate::telemetry::{LogEntry, TraceSpan};
-use crate::log_parser::{LogParser, LogParserFactory};
-use crate::buffer::{TelemetryBuffer, is_high_priority_log, is_high_priority_span};
-use crate::transport::{HttpTransport, EnhancedTransport};
+use crate::config::{Config, LogFileFormat, StartPosition, TransportKind, Utf8Decoding};
+use crate::telemetry::{LogEntry, LogLevel, MetricPoint, TelemetryBatch, TraceSpan};
+use crate::log_parser::{self, LogParser, LogParserFactory, ParserConfig};
+use crate::buffer::{BufferConfig, PriorityTelemetryBuffer};
+use crate::transport::{HttpTransport, EnhancedTransport, PayloadSize, Transport};
 use crate::errors::{CollectorError, Result};
+use crate::correlation::CorrelationBuffer;
+use crate::tail_sampling::{SampledTraces, TailSampler};
+use crate::orphan_spans::{self, OrphanDetector};
+use crate::checkpoint::Checkpoint;
+use crate::dead_letter::{self, BatchOutcome, DeadLetterReport};
+use crate::files;
+use crate::file_sink::{FileTransport, MirroredTransport};
+use crate::cri::{CriDecoder, CriRecord};
+use crate::podinfo;
+use crate::sampling::LogSampler;
+use crate::rate_limit::LevelRateLimiter;
+use crate::filter::LineFilter;
+use crate::recent::{RecentLogs, RecentQuery};
+use crate::recent_errors::{RecentErrors, RecordedError};
+use crate::self_logs::{self, SelfLogReceiver};
+use crate::state::{CollectorState, FileOffset, FileOffsets};
+use crate::stats_server;
+use crate::span_metrics::{OperationMetrics, SpanMetrics};
 
-use std::path::Path;
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use serde::Serialize;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader, SeekFrom};
 use tokio::time::{interval, Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{info, warn, error, debug, instrument};
-use uuid::Uuid;
+
+/// Batch sequence numbers reserved in the state file at a time; a crashed
+/// collector skips at most this many numbers after restarting
+const SEQUENCE_RESERVATION: u64 = 1000;
 
 /// Main sidecar collector orchestrating log collection and transmission
 pub struct SidecarCollector {
     config: Config,
-    parser: Box<dyn LogParser>,
-    buffer: Arc<TelemetryBuffer>,
+    parser: Arc<dyn LogParser>,
+    sampler: Arc<LogSampler>,
+    line_filter: Arc<LineFilter>,
+    buffer: Arc<PriorityTelemetryBuffer>,
     transport: Arc<EnhancedTransport>,
     collector_id: String,
+    /// When this collector was created, for uptime
+    started_at: Instant,
+    /// Starts before this one recorded in `state_path`
+    restarts: Arc<AtomicU64>,
     file_states: Arc<RwLock<Vec<FileState>>>,
+    resource_attributes: Arc<RwLock<HashMap<String, String>>>,
+    /// Host and process attributes, fixed at startup
+    host_attributes: Arc<HashMap<String, String>>,
+    invalid_entries_dropped: Arc<AtomicU64>,
+    /// Spans discarded by the parser for malformed trace or span ids
+    invalid_id_spans_dropped: Arc<AtomicU64>,
+    /// Raw lines discarded by line filters
+    lines_filtered: Arc<AtomicU64>,
+    /// Batches appended to the dead-letter file
+    batches_dead_lettered: Arc<AtomicU64>,
+    /// Error-free traces dropped by tail sampling
+    traces_sampled_out: Arc<AtomicU64>,
+    /// Logs dropped by per-level rate limits
+    logs_rate_limited: Arc<AtomicU64>,
+    /// Spans tagged as missing their same-service parent
+    orphan_spans: Arc<AtomicU64>,
+    /// Sequence number of the last batch handed to the transport
+    batch_sequence: Arc<AtomicU64>,
+    /// Highest sequence number recorded in the state file, see
+    /// [`SEQUENCE_RESERVATION`]
+    sequence_reserved: Arc<Mutex<u64>>,
+    /// While set, periodic flushes are skipped and data stays buffered
+    paused: Arc<AtomicBool>,
+    /// Whether file reads are waiting for the buffer to drain
+    reading_throttled: Arc<AtomicBool>,
+    read_throttles: Arc<AtomicU64>,
+    /// Readiness inputs: whether the gateway was reached and a log file
+    /// checked at least once, sends failed in a row, and whether shutdown
+    /// has begun
+    gateway_reached: Arc<AtomicBool>,
+    source_checked: Arc<AtomicBool>,
+    consecutive_send_failures: Arc<AtomicU64>,
+    shutting_down: Arc<AtomicBool>,
+    correlation: Option<Arc<Mutex<CorrelationBuffer>>>,
+    level_limiter: Option<Arc<Mutex<LevelRateLimiter>>>,
+    tail_sampler: Option<Arc<Mutex<TailSampler>>>,
+    orphan_detector: Option<Arc<Mutex<OrphanDetector>>>,
+    /// Copies of batches being sent, by batch id, kept for the dead letter
+    /// and buffer checkpoints when either is enabled
+    sending: Arc<Mutex<HashMap<String, TelemetryBatch>>>,
+    span_metrics: Option<Arc<Mutex<SpanMetrics>>>,
+    /// Recently parsed entries queryable from the stats server
+    recent_logs: Option<Arc<Mutex<RecentLogs>>>,
+    /// Recent errors and counts by category for the stats server
+    recent_errors: Arc<Mutex<RecentErrors>>,
+    /// The collector's own log events waiting to be buffered
+    self_logs: Option<Arc<Mutex<SelfLogReceiver>>>,
 }
 
 /// File tracking state for log tailing
 #[derive(Debug, Clone)]
 struct FileState {
     path: String,
+    /// Value of the `log.source` attribute for entries from this file
+    source: String,
     last_position: u64,
     last_modified: Option<std::time::SystemTime>,
     inode: Option<u64>,
+    mtime_unsupported: bool,
+    cri: CriDecoder,
+    /// When a line was last read, or when monitoring started
+    last_read_at: Instant,
+    /// Whether the file was idle past its threshold at the last check
+    idle: bool,
+    /// Whether the last check of the file failed, other than by the file
+    /// not existing yet
+    failing: bool,
+}
+
+impl FileState {
+    /// Initial state for a tailed file. In `End` mode content that already
+    /// exists is skipped; rotation still restarts from the beginning.
+    fn new(path: &str, source: String, start_position: StartPosition) -> Self {
+        let mut state = Self {
+            path: path.to_string(),
+            source,
+            last_position: 0,
+            last_modified: None,
+            inode: None,
+            mtime_unsupported: false,
+            cri: CriDecoder::new(),
+            last_read_at: Instant::now(),
+            idle: false,
+            failing: false,
+        };
+
+        if start_position == StartPosition::End
+            && let Ok(metadata) = std::fs::metadata(path)
+        {
+            let snapshot = FileSnapshot::from_metadata(&metadata);
+            state.last_position = snapshot.size;
+            state.last_modified = snapshot.modified;
+            state.inode = snapshot.inode;
+        }
+
+        state
+    }
+
+    /// Decide where to resume reading, if at all, given the file's current
+    /// metadata. Falls back to size and inode when mtime is unavailable.
+    fn read_start(&self, snapshot: &FileSnapshot) -> Option<u64> {
+        let replaced = matches!(
+            (self.inode, snapshot.inode),
+            (Some(previous), Some(current)) if previous != current
+        );
+
+        // Rotated, replaced or truncated: start over from the beginning
+        if replaced || snapshot.size < self.last_position {
+            return Some(0);
+        }
+
+        if snapshot.size > self.last_position {
+            return Some(self.last_position);
+        }
+
+        match snapshot.modified {
+            Some(modified) if self.last_modified != Some(modified) => Some(self.last_position),
+            _ => None,
+        }
+    }
+}
+
+/// Consecutive-failure tracking for one file monitor. Once failures reach the
+/// threshold, checks are spaced out with exponential backoff and jitter.
+#[derive(Debug)]
+struct MonitorBackoff {
+    threshold: u32,
+    base: Duration,
+    max: Duration,
+    consecutive_errors: u32,
+}
+
+impl MonitorBackoff {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            threshold: config.file_error_threshold.max(1),
+            base: config.file_error_backoff,
+            max: config.file_error_max_backoff,
+            consecutive_errors: 0,
+        }
+    }
+
+    /// Record a failed check, returning how long to pause before the next one
+    fn record_error<R: rand::Rng + ?Sized>(&mut self, rng: &mut R) -> Option<Duration> {
+        self.consecutive_errors = self.consecutive_errors.saturating_add(1);
+        let doublings = self.consecutive_errors.checked_sub(self.threshold)?.min(16);
+        let delay = self.base.saturating_mul(1 << doublings).min(self.max);
+
+        // Jitter keeps monitors that failed together from retrying together
+        Some(delay.mul_f64(rng.random_range(0.5..=1.0)))
+    }
+
+    /// Whether the last failure was the one that started the backoff
+    fn just_started(&self) -> bool {
+        self.consecutive_errors == self.threshold
+    }
+
+    /// Record a successful check, returning how many failures preceded it
+    fn record_success(&mut self) -> u32 {
+        std::mem::take(&mut self.consecutive_errors)
+    }
+}
+
+/// Metadata of a tailed file at a single point in time
+#[derive(Debug, Clone, Copy)]
+struct FileSnapshot {
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+    inode: Option<u64>,
+}
+
+impl FileSnapshot {
+    fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode: file_inode(metadata),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_inode(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_inode(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Find the file in the same directory as `path` with the given inode,
+/// other than `path` itself
+async fn find_by_inode(path: &str, inode: u64) -> Option<String> {
+    let path = std::path::Path::new(path);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+
+    let mut entries = tokio::fs::read_dir(directory).await.ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let candidate = entry.path();
+        if candidate.file_name() == path.file_name() {
+            continue;
+        }
+
+        if let Ok(metadata) = entry.metadata().await
+            && metadata.is_file()
+            && file_inode(&metadata) == Some(inode)
+        {
+            return Some(candidate.to_string_lossy().into_owned());
+        }
+    }
+
+    None
 }
 
 impl SidecarCollector {
     /// Create a new sidecar collector
     pub fn new(config: Config) -> Result<Self> {
+        // Create transport
+        match config.transport {
+            TransportKind::Http => {
+                let http_transport = HttpTransport::from_config(&config)?;
+                Self::with_file_mirror(config, http_transport)
+            }
+            TransportKind::File => {
+                let file_transport = FileTransport::from_config(&config)?;
+                Self::with_transport(config, file_transport)
+            }
+            #[cfg(feature = "nats")]
+            TransportKind::Nats => {
+                let nats_transport = crate::nats::NatsTransport::from_config(&config);
+                Self::with_file_mirror(config, nats_transport)
+            }
+            #[cfg(not(feature = "nats"))]
+            TransportKind::Nats => Err(CollectorError::Config(
+                "the nats transport requires building with the `nats` feature".to_string(),
+            )),
+            #[cfg(feature = "grpc")]
+            TransportKind::Grpc => {
+                let grpc_transport = crate::grpc::GrpcTransport::from_config(&config)?;
+                Self::with_file_mirror(config, grpc_transport)
+            }
+            #[cfg(not(feature = "grpc"))]
+            TransportKind::Grpc => Err(CollectorError::Config(
+                "the grpc transport requires building with the `grpc` feature".to_string(),
+            )),
+        }
+    }
+
+    /// Wrap `transport` so every batch is also written to the file sink when
+    /// `file_sink_mirror` is set
+    fn with_file_mirror(config: Config, transport: impl Transport + 'static) -> Result<Self> {
+        if config.file_sink_mirror {
+            let mirror = FileTransport::from_config(&config)?;
+            Self::with_transport(config, MirroredTransport::new(transport, mirror))
+        } else {
+            Self::with_transport(config, transport)
+        }
+    }
+
+    /// Create a new sidecar collector sending batches through the given transport
+    pub fn with_transport(config: Config, transport: impl Transport + 'static) -> Result<Self> {
         config.validate().map_err(CollectorError::Config)?;
 
         // Create log parser
-        let parser = LogParserFactory::create_parser(
-            "composite",
-            config.enable_trace_correlation,
-        );
+        let parser_config = ParserConfig::from_config(&config)?;
+        let invalid_id_spans_dropped = Arc::clone(&parser_config.invalid_id_spans_dropped);
+        let parser: Arc<dyn LogParser> = Arc::from(LogParserFactory::create_parser_with_config(
+            &config.log_parser,
+            parser_config,
+        ));
+
+        let sampler = Arc::new(LogSampler::from_config(&config)?);
+        let line_filter = Arc::new(LineFilter::from_config(&config)?);
 
         // Create buffer
-        let buffer = Arc::new(TelemetryBuffer::new(
-            config.max_buffer_size,
-            config.batch_size,
-        ));
+        let buffer = Arc::new(PriorityTelemetryBuffer::new(BufferConfig::from_config(&config)?));
 
-        // Create transport
-        let http_transport = HttpTransport::new(
-            config.gateway_url.clone(),
-            config.http_timeout,
-            config.max_retries,
-            config.retry_backoff_ms,
-        )?;
-        let transport = Arc::new(EnhancedTransport::new(http_transport));
+        let transport = Arc::new(
+            EnhancedTransport::new(transport)
+                .with_max_in_flight(config.max_in_flight_batches)
+                .with_max_batch_send_age(config.max_batch_send_age)
+        );
 
         // Initialize file states
         let file_states = Arc::new(RwLock::new(
             config.log_paths.iter()
-                .map(|path| FileState {
-                    path: path.clone(),
-                    last_position: 0,
-                    last_modified: None,
-                    inode: None,
-                })
+                .map(|path| FileState::new(path, config.log_source_name(path), config.start_position))
                 .collect()
         ));
 
+        // Hold trace-linked logs briefly so late spans can attach context
+        let correlation = (config.enable_trace_correlation && !config.trace_correlation_window.is_zero())
+            .then(|| Arc::new(Mutex::new(CorrelationBuffer::new(
+                config.trace_correlation_window,
+                config.trace_correlation_max_pending,
+            ))));
+
+        let level_limiter = Some(LevelRateLimiter::from_config(&config))
+            .filter(|limiter| !limiter.is_empty())
+            .map(|limiter| Arc::new(Mutex::new(limiter)));
+
+        // Hold traces briefly so ones containing an error are kept whole
+        let tail_sampler = (!config.tail_sampling_window.is_zero())
+            .then(|| Arc::new(Mutex::new(TailSampler::new(
+                config.tail_sampling_window,
+                config.tail_sampling_ratio,
+                config.tail_sampling_max_pending,
+            ))));
+
+        // Hold spans briefly so ones whose parent never arrives can be flagged
+        let orphan_detector = (!config.orphan_span_window.is_zero())
+            .then(|| Arc::new(Mutex::new(OrphanDetector::new(
+                config.orphan_span_window,
+                config.orphan_span_max_traces,
+                &config.orphan_span_entry_kinds,
+            ))));
+
+        let span_metrics = (!config.span_metrics_interval.is_zero())
+            .then(|| Arc::new(Mutex::new(SpanMetrics::new(
+                config.span_metrics_interval,
+                config.span_metrics_max_operations,
+            ))));
+
+        let recent_logs = (config.recent_logs_capacity > 0)
+            .then(|| Arc::new(Mutex::new(RecentLogs::new(config.recent_logs_capacity))));
+        let recent_errors = Arc::new(Mutex::new(RecentErrors::new(config.recent_errors_capacity)));
+
+        // Load downward API resource attributes
+        let host_attributes = Arc::new(if config.include_host_attributes {
+            podinfo::host_attributes()
+        } else {
+            HashMap::new()
+        });
+        let resource_attributes = Arc::new(RwLock::new(
+            merged_resource_attributes(&config, &host_attributes)
+        ));
+
+        let collector_id = config.resolve_collector_id();
+
         Ok(Self {
             config,
             parser,
+            sampler,
+            line_filter,
             buffer,
             transport,
-            collector_id: Uuid::new_v4().to_string(),
+            collector_id,
+            started_at: Instant::now(),
+            restarts: Arc::new(AtomicU64::new(0)),
             file_states,
+            resource_attributes,
+            host_attributes,
+            invalid_entries_dropped: Arc::new(AtomicU64::new(0)),
+            invalid_id_spans_dropped,
+            lines_filtered: Arc::new(AtomicU64::new(0)),
+            batches_dead_lettered: Arc::new(AtomicU64::new(0)),
+            traces_sampled_out: Arc::new(AtomicU64::new(0)),
+            logs_rate_limited: Arc::new(AtomicU64::new(0)),
+            orphan_spans: Arc::new(AtomicU64::new(0)),
+            batch_sequence: Arc::new(AtomicU64::new(0)),
+            sequence_reserved: Arc::new(Mutex::new(0)),
+            paused: Arc::new(AtomicBool::new(false)),
+            reading_throttled: Arc::new(AtomicBool::new(false)),
+            read_throttles: Arc::new(AtomicU64::new(0)),
+            gateway_reached: Arc::new(AtomicBool::new(false)),
+            source_checked: Arc::new(AtomicBool::new(false)),
+            consecutive_send_failures: Arc::new(AtomicU64::new(0)),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            correlation,
+            level_limiter,
+            tail_sampler,
+            orphan_detector,
+            sending: Arc::new(Mutex::new(HashMap::new())),
+            span_metrics,
+            recent_logs,
+            recent_errors,
+            self_logs: None,
         })
     }
 
-    /// Start the collector
+    /// Buffer the collector's own log events from `receiver` alongside
+    /// application logs, see [`self_logs::channel`]
+    pub fn with_self_logs(mut self, receiver: SelfLogReceiver) -> Self {
+        self.self_logs = Some(Arc::new(Mutex::new(receiver)));
+        self
+    }
+
+    /// Start the collector and run until a shutdown signal is received
     #[instrument(skip(self))]
     pub async fn start(&self) -> Result<()> {
+        self.run_until(async {
+            tokio::signal::ctrl_c().await.map_err(|e| {
+                CollectorError::Other(format!("Failed to wait for shutdown signal: {}", e))
+            })
+        }).await
+    }
+
+    /// Run the collector until the `shutdown` future completes, then shut down gracefully
+    #[instrument(skip(self, shutdown))]
+    pub async fn run_until<F>(&self, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
         info!(
             "Starting sidecar collector {} for service: {}",
             self.collector_id, self.config.service_name
         );
 
-        if !self.transport.test_connectivity().await {
+        if self.config.dry_run {
+            info!("Dry run enabled, batches will be logged instead of sent");
+            self.gateway_reached.store(true, Ordering::Relaxed);
+        } else if self.transport.test_connectivity().await {
+            self.gateway_reached.store(true, Ordering::Relaxed);
+        } else {
             warn!("Gateway connectivity test failed, but continuing anyway");
         }
 
+        if let Err(e) = self.record_start().await {
+            error!("Failed to record collector start: {}", e);
+            self.record_error("record start", &e).await;
+        }
+
+        if let Err(e) = self.restore_offsets().await {
+            error!("Failed to restore file offsets: {}", e);
+            self.record_error("restore file offsets", &e).await;
+        }
+
+        if let Err(e) = self.restore_checkpoint().await {
+            error!("Failed to restore buffer checkpoint: {}", e);
+            self.record_error("restore buffer checkpoint", &e).await;
+        }
+
         for (index, _) in self.config.log_paths.iter().enumerate() {
             let collector = self.clone_for_task();
             tokio::spawn(async move {
                 if let Err(e) = collector.monitor_file(index).await {
                     error!("File monitoring task {} failed: {}", index, e);
+                    collector.record_error(&format!("monitor {}", collector.config.log_paths[index]), &e).await;
                 }
             });
         }
@@ -114,9 +515,47 @@ impl SidecarCollector {
             metrics_collector.report_metrics().await;
         });
 
-        tokio::signal::ctrl_c().await.map_err(|e| {
-            CollectorError::Other(format!("Failed to wait for shutdown signal: {}", e))
-        })?;
+        if self.checkpoint_path().is_some() {
+            let checkpoint_collector = self.clone_for_task();
+            tokio::spawn(async move {
+                checkpoint_collector.periodic_checkpoint().await;
+            });
+        }
+
+        if !self.config.self_telemetry_interval.is_zero() {
+            let heartbeat_collector = self.clone_for_task();
+            tokio::spawn(async move {
+                heartbeat_collector.emit_heartbeats().await;
+            });
+        }
+
+        if let Some(addr) = &self.config.stats_addr {
+            match tokio::net::TcpListener::bind(addr).await {
+                Ok(listener) => {
+                    let stats_collector = Arc::new(self.clone_for_task());
+                    tokio::spawn(async move {
+                        if let Err(e) = stats_server::serve(listener, Arc::clone(&stats_collector)).await {
+                            error!("Stats server failed: {}", e);
+                            stats_collector.record_error("stats server", &e).await;
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!("Failed to bind stats server to {}: {}", addr, e);
+                    self.record_error(&format!("bind stats server to {}", addr), &e.into()).await;
+                }
+            }
+        }
+
+        #[cfg(unix)]
+        {
+            let reload_collector = self.clone_for_task();
+            tokio::spawn(async move {
+                reload_collector.reload_on_sighup().await;
+            });
+        }
+
+        shutdown.await?;
 
         info!("Shutting down sidecar collector");
         self.shutdown().await?;
@@ -129,39 +568,187 @@ impl SidecarCollector {
         let path = &self.config.log_paths[file_index];
         info!("Starting file monitor for: {}", path);
 
-        let mut check_interval = interval(Duration::from_millis(500));
-        let mut consecutive_errors = 0;
-        const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+        let mut check_interval = interval(self.config.file_poll_interval);
+        let mut backoff = MonitorBackoff::from_config(&self.config);
+        let mut waiting_for_file = false;
+        self.file_states.write().await[file_index].last_read_at = Instant::now();
 
         loop {
             check_interval.tick().await;
 
-            match self.check_and_read_file(file_index).await {
+            // New lines wait in the file rather than overflow the buffer. A
+            // paused reader is not an idle source.
+            if self.update_backpressure().await {
+                self.file_states.write().await[file_index].last_read_at = Instant::now();
+                continue;
+            }
+
+            let result = self.check_and_read_file(file_index).await;
+            self.update_freshness(file_index, *result.as_ref().unwrap_or(&0)).await;
+            // Waiting for the application to create its log file is normal
+            let missing = matches!(&result, Err(CollectorError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound);
+            self.file_states.write().await[file_index].failing = result.is_err() && !missing;
+            if result.is_ok() || missing {
+                self.source_checked.store(true, Ordering::Relaxed);
+            }
+
+            match result {
                 Ok(lines_read) => {
-                    consecutive_errors = 0;
+                    waiting_for_file = false;
+                    let failures = backoff.record_success();
+                    if failures > 0 {
+                        info!("Resumed reading {} after {} failed checks", path, failures);
+                    }
                     if lines_read > 0 {
                         debug!("Read {} lines from {}", lines_read, path);
                     }
                 }
+                // A missing file is expected until the application creates
+                // it, so it is checked at the normal interval, not backed off
+                Err(_) if missing => {
+                    if !waiting_for_file {
+                        info!("Log file {} not found, waiting for it to appear", path);
+                        waiting_for_file = true;
+                    }
+                }
                 Err(e) => {
-                    consecutive_errors += 1;
-                    if consecutive_errors <= MAX_CONSECUTIVE_ERRORS {
-                        warn!("Error reading file {} (attempt {}): {}", path, consecutive_errors, e);
+                    let pause = backoff.record_error(&mut rand::rng());
+                    let attempt = backoff.consecutive_errors;
+                    if attempt <= backoff.threshold {
+                        warn!("Error reading file {} (attempt {}): {}", path, attempt, e);
                     }
+                    self.record_error(&format!("read {}", path), &e).await;
+
+                    if let Some(pause) = pause {
+                        if backoff.just_started() {
+                            warn!(
+                                "File {} unreadable for {} consecutive checks, backing off up to {:?} between checks: {}",
+                                path, attempt, backoff.max, e
+                            );
+                        } else {
+                            debug!("File {} still unreadable, checking again in {:?}", path, pause);
+                        }
 
-                    if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
-                        error!(
-                            "Too many consecutive errors reading file {}, pausing for 30 seconds",
-                            path
-                        );
-                        tokio::time::sleep(Duration::from_secs(30)).await;
-                        consecutive_errors = 0;
+                        tokio::time::sleep(pause).await;
+                        check_interval.reset();
                     }
                 }
             }
         }
     }
 
+    /// Whether file reads should wait for the buffer to drain. Reads pause
+    /// once utilization reaches the high-water mark and resume only when it
+    /// falls to the low-water mark, so they do not flap around one level.
+    async fn update_backpressure(&self) -> bool {
+        let high_water = self.config.backpressure_high_water_percent;
+        if high_water <= 0.0 {
+            return false;
+        }
+
+        let utilization = self.buffer.peak_utilization().await;
+        let throttled = self.reading_throttled.load(Ordering::Relaxed);
+        if !throttled && utilization >= high_water {
+            if !self.reading_throttled.swap(true, Ordering::Relaxed) {
+                self.read_throttles.fetch_add(1, Ordering::Relaxed);
+                warn!("Buffer {:.1}% full, pausing file reads until it drains", utilization);
+            }
+            true
+        } else if throttled && utilization <= self.config.backpressure_low_water_percent {
+            if self.reading_throttled.swap(false, Ordering::Relaxed) {
+                info!("Buffer down to {:.1}%, resuming file reads", utilization);
+            }
+            false
+        } else {
+            throttled
+        }
+    }
+
+    /// Note lines read from a file, logging when it goes idle past its
+    /// threshold and when it recovers
+    async fn update_freshness(&self, file_index: usize, lines_read: usize) {
+        let mut file_states = self.file_states.write().await;
+        let state = &mut file_states[file_index];
+        if lines_read > 0 {
+            state.last_read_at = Instant::now();
+        }
+
+        let max_idle = self.config.log_max_idle_for(&state.path);
+        let idle = !max_idle.is_zero() && state.last_read_at.elapsed() > max_idle;
+        if idle && !state.idle {
+            warn!("No new lines read from {} in over {:?}", state.path, max_idle);
+        } else if !idle && state.idle {
+            info!("Reading new lines from {} again", state.path);
+        }
+        state.idle = idle;
+    }
+
+    /// Freshness of each monitored log path
+    pub async fn log_sources(&self) -> Vec<LogSourceHealth> {
+        self.file_states
+            .read()
+            .await
+            .iter()
+            .map(|state| {
+                let max_idle = self.config.log_max_idle_for(&state.path);
+                let idle = state.last_read_at.elapsed();
+                LogSourceHealth {
+                    path: state.path.clone(),
+                    source: state.source.clone(),
+                    idle_seconds: idle.as_secs(),
+                    max_idle_seconds: (!max_idle.is_zero()).then_some(max_idle.as_secs()),
+                    healthy: max_idle.is_zero() || idle <= max_idle,
+                }
+            })
+            .collect()
+    }
+
+    /// Log paths idle past their threshold when freshness counts toward
+    /// health, otherwise none
+    pub async fn unhealthy_log_sources(&self) -> Vec<LogSourceHealth> {
+        if !self.config.log_freshness_health {
+            return Vec::new();
+        }
+        self.log_sources().await.into_iter().filter(|source| !source.healthy).collect()
+    }
+
+    /// Whether the collector is collecting. It is `Starting` until a log
+    /// file has been checked, whether it exists yet or not, and the gateway
+    /// has been reached when `readiness_require_gateway` is set; then
+    /// `Ready`, or `Degraded` while sends keep failing or every log file
+    /// fails to read, and `ShuttingDown` once shutdown begins.
+    pub async fn readiness(&self) -> Readiness {
+        if self.shutting_down.load(Ordering::Relaxed) {
+            return Readiness::new(ReadinessState::ShuttingDown, Vec::new());
+        }
+
+        let mut reasons = Vec::new();
+        if self.config.readiness_require_gateway && !self.gateway_reached.load(Ordering::Relaxed) {
+            reasons.push("gateway not reached yet".to_string());
+        }
+        if !self.source_checked.load(Ordering::Relaxed) {
+            reasons.push("no log file checked yet".to_string());
+        }
+        if !reasons.is_empty() {
+            return Readiness::new(ReadinessState::Starting, reasons);
+        }
+
+        let max_failures = self.config.readiness_max_send_failures;
+        let failures = self.consecutive_send_failures.load(Ordering::Relaxed);
+        if max_failures > 0 && failures >= max_failures {
+            reasons.push(format!("last {} batch sends failed", failures));
+        }
+        if self.file_states.read().await.iter().all(|state| state.failing) {
+            reasons.push("every log file is failing to read".to_string());
+        }
+
+        if reasons.is_empty() {
+            Readiness::new(ReadinessState::Ready, reasons)
+        } else {
+            Readiness::new(ReadinessState::Degraded, reasons)
+        }
+    }
+
     /// Check file for changes and read new content
     async fn check_and_read_file(&self, file_index: usize) -> Result<usize> {
         let path = {
@@ -169,38 +756,69 @@ impl SidecarCollector {
             file_states[file_index].path.clone()
         };
 
-        if !Path::new(&path).exists() {
-            return Ok(0);
-        }
-
-        let metadata = tokio::fs::metadata(&path).await?;
-        let current_size = metadata.len();
-        let current_modified = metadata.modified().ok();
+        let metadata = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                // Renamed away but not yet recreated: the rotated file may
+                // still hold unread lines
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    let inode = self.file_states.read().await[file_index].inode;
+                    self.drain_rotated(&path, file_index, inode).await?;
+                }
+                return Err(e.into());
+            }
+        };
+        let snapshot = FileSnapshot::from_metadata(&metadata);
 
-        let (should_read, start_position) = {
+        let (start_position, previous_inode) = {
             let mut file_states = self.file_states.write().await;
             let state = &mut file_states[file_index];
 
-            // Check if file was truncated or rotated
-            if current_size < state.last_position {
-                debug!("File {} appears to have been truncated or rotated", path);
-                state.last_position = 0;
-                state.last_modified = current_modified;
-                (true, 0)
+            if snapshot.modified.is_none() && !state.mtime_unsupported {
+                warn!(
+                    "Modification time unavailable for {}, detecting changes by size and inode",
+                    path
+                );
+                state.mtime_unsupported = true;
             }
-            // Check if file was modified
-            else if state.last_modified != current_modified || current_size > state.last_position {
-                (true, state.last_position)
-            } else {
-                (false, state.last_position)
+
+            let start_position = state.read_start(&snapshot);
+            if start_position == Some(0) && state.last_position > 0 {
+                debug!("File {} appears to have been truncated or rotated", path);
             }
+            let previous_inode = std::mem::replace(&mut state.inode, snapshot.inode);
+            (start_position, previous_inode)
+        };
+
+        // Finish the old file before starting on its replacement
+        if matches!((previous_inode, snapshot.inode), (Some(previous), Some(current)) if previous != current) {
+            self.drain_rotated(&path, file_index, previous_inode).await?;
+        }
+
+        match start_position {
+            Some(position) => self.read_file_from_position(&path, file_index, position).await,
+            None => Ok(0),
+        }
+    }
+
+    /// After a rename-style rotation, read what is left of the previous file,
+    /// found by its inode next to `path`, so lines written just before the
+    /// rotation are not lost
+    async fn drain_rotated(&self, path: &str, file_index: usize, inode: Option<u64>) -> Result<usize> {
+        let Some(inode) = inode else {
+            return Ok(0);
+        };
+        let Some(rotated) = find_by_inode(path, inode).await else {
+            return Ok(0);
         };
 
-        if !should_read {
+        let last_position = self.file_states.read().await[file_index].last_position;
+        if tokio::fs::metadata(&rotated).await?.len() <= last_position {
             return Ok(0);
         }
 
-        self.read_file_from_position(&path, file_index, start_position).await
+        info!("Reading the rest of {} from rotated file {}", path, rotated);
+        self.read_file_from_position(&rotated, file_index, last_position).await
     }
 
     /// Read file content from a specific position
@@ -217,34 +835,44 @@ impl SidecarCollector {
         let mut lines_read = 0;
         let mut current_position = start_position;
 
+        // Partial CRI lines carry over between reads of the same file
+        let (mut decoder, source) = {
+            let mut file_states = self.file_states.write().await;
+            let state = &mut file_states[file_index];
+            (std::mem::take(&mut state.cri), state.source.clone())
+        };
+        if start_position == 0 {
+            for record in decoder.flush() {
+                self.process_log_line(&record.message, false, Some(&record), &source).await?;
+            }
+        }
+
         loop {
-            let mut line = String::new();
-            let bytes_read = reader.read_line(&mut line).await?;
+            let mut raw = Vec::new();
+            let bytes_read = reader.read_until(b'\n', &mut raw).await?;
 
             if bytes_read == 0 {
                 break;
             }
 
+            let at_file_start = current_position == 0;
             current_position += bytes_read as u64;
             lines_read += 1;
 
-            if line.ends_with('\n') {
-                line.pop();
-                if line.ends_with('\r') {
-                    line.pop();
-                }
-            }
-
-            if line.trim().is_empty() {
+            let Some((line, lossy)) = decode_line(&raw, self.config.utf8_decoding) else {
+                warn!("Skipping line in {} at byte {} that is not valid UTF-8", path, current_position - bytes_read as u64);
                 continue;
-            }
+            };
 
-            self.process_log_line(&line).await?;
+            for log_line in split_raw_line(&line, at_file_start) {
+                self.process_file_line(log_line, lossy, &mut decoder, &source).await?;
+            }
         }
 
         {
             let mut file_states = self.file_states.write().await;
             let state = &mut file_states[file_index];
+            state.cri = decoder;
             state.last_position = current_position;
             state.last_modified = tokio::fs::metadata(path).await?.modified().ok();
         }
@@ -252,88 +880,991 @@ impl SidecarCollector {
         Ok(lines_read)
     }
 
-    /// Process a single log line
-    async fn process_log_line(&self, line: &str) -> Result<()> {
-        if let Some(log_entry) = self.parser.parse_log(
+    /// Process one line read from a file, decoding CRI framing when configured.
+    /// `lossy` marks lines whose invalid UTF-8 bytes were replaced.
+    async fn process_file_line(
+        &self,
+        line: &str,
+        lossy: bool,
+        decoder: &mut CriDecoder,
+        source: &str,
+    ) -> Result<LineOutcome> {
+        match self.config.log_file_format {
+            LogFileFormat::Plain => self.process_log_line(line, lossy, None, source).await,
+            LogFileFormat::Cri => match decoder.decode(line) {
+                Some(record) => self.process_log_line(&record.message, lossy, Some(&record), source).await,
+                None => Ok(LineOutcome::default()),
+            },
+        }
+    }
+
+    /// Process a single log line read from `source`, with the CRI record it
+    /// came from if any. Concatenated JSON objects are processed one by one
+    /// when splitting is enabled.
+    async fn process_log_line(
+        &self,
+        line: &str,
+        lossy: bool,
+        record: Option<&CriRecord>,
+        source: &str,
+    ) -> Result<LineOutcome> {
+        if self.config.split_concatenated_json
+            && let Some(objects) = log_parser::split_json_objects(line, self.config.max_json_objects_per_line)
+        {
+            let mut outcome = LineOutcome::default();
+            for object in objects {
+                outcome.merge(self.process_log_object(object, lossy, record, source).await?);
+            }
+            return Ok(outcome);
+        }
+
+        self.process_log_object(line, lossy, record, source).await
+    }
+
+    /// Filter and parse one log record, buffering what it produces
+    async fn process_log_object(
+        &self,
+        line: &str,
+        lossy: bool,
+        record: Option<&CriRecord>,
+        source: &str,
+    ) -> Result<LineOutcome> {
+        let mut outcome = LineOutcome::default();
+        // Log attributes allowed onto a span parsed from the same line
+        let mut span_tags = Vec::new();
+
+        if !self.line_filter.should_keep(line) {
+            self.lines_filtered.fetch_add(1, Ordering::Relaxed);
+            outcome.filtered = 1;
+            return Ok(outcome);
+        }
+
+        if let Some(mut log_entry) = self.parser.parse_log(
             line,
             &self.config.service_name,
             &self.config.pod_name,
             &self.config.namespace,
         )? {
-            self.buffer.add_log(log_entry).await?;
+            log_entry.attributes.insert("log.source".to_string(), source.to_string());
+            if self.config.preserve_raw {
+                let raw = truncate_to_char_boundary(line, self.config.max_raw_bytes);
+                log_entry.attributes.insert("log.raw".to_string(), raw.to_string());
+            }
+            if lossy {
+                log_entry.attributes.insert("encoding.lossy".to_string(), "true".to_string());
+            }
+            if let Some(record) = record {
+                record.apply_to(&mut log_entry, self.config.max_timestamp_skew);
+            }
+            span_tags = self
+                .config
+                .span_tag_allowlist
+                .iter()
+                .filter_map(|key| Some((key.clone(), log_entry.attributes.get(key)?.clone())))
+                .collect();
+            if let Some(recent_logs) = &self.recent_logs {
+                recent_logs.lock().await.record(&log_entry);
+            }
+
+            if let Some(level_limiter) = &self.level_limiter
+                && !level_limiter.lock().await.allow(&log_entry.level, std::time::Instant::now())
+            {
+                self.logs_rate_limited.fetch_add(1, Ordering::Relaxed);
+                outcome.rate_limited = 1;
+            } else if self.sampler.should_keep(&log_entry) {
+                match &self.tail_sampler {
+                    Some(tail_sampler) => {
+                        let sampled = tail_sampler.lock().await.add_log(log_entry, std::time::Instant::now());
+                        self.buffer_sampled(sampled).await?;
+                    }
+                    None => self.buffer_log(log_entry).await?,
+                }
+                outcome.log = 1;
+            } else {
+                outcome.sampled_out = 1;
+            }
         }
 
-        if let Some(span) = self.parser.parse_span(line, &self.config.service_name)? {
-            self.buffer.add_span(span).await?;
+        if let Some(mut span) = self.parser.parse_span(line, &self.config.service_name)? {
+            // The span's own tags win
+            for (key, value) in span_tags {
+                span.tags.entry(key).or_insert(value);
+            }
+            if let Some(span_metrics) = &self.span_metrics {
+                span_metrics.lock().await.record(&span);
+            }
+            match &self.tail_sampler {
+                Some(tail_sampler) => {
+                    let sampled = tail_sampler.lock().await.add_span(span, std::time::Instant::now());
+                    self.buffer_sampled(sampled).await?;
+                }
+                None => self.buffer_span(span).await?,
+            }
+            outcome.span = 1;
         }
 
-        Ok(())
+        Ok(outcome)
     }
 
-    /// Periodic flush of buffered data
-    async fn periodic_flush(&self) {
-        let mut flush_interval = interval(self.config.flush_interval);
+    /// Release traces held for tail sampling, spans held for orphan
+    /// detection and logs held for trace correlation into the buffer: only
+    /// those whose window has passed, or all of them when shutting down
+    async fn release_held_logs(&self, all: bool) -> Result<()> {
+        if let Some(tail_sampler) = &self.tail_sampler {
+            let sampled = {
+                let mut tail_sampler = tail_sampler.lock().await;
+                if all {
+                    tail_sampler.drain()
+                } else {
+                    tail_sampler.expire(std::time::Instant::now())
+                }
+            };
+            self.buffer_sampled(sampled).await?;
+        }
 
-        loop {
-            flush_interval.tick().await;
+        if let Some(orphan_detector) = &self.orphan_detector {
+            let decided = {
+                let mut orphan_detector = orphan_detector.lock().await;
+                if all {
+                    orphan_detector.drain()
+                } else {
+                    orphan_detector.expire(std::time::Instant::now())
+                }
+            };
+            self.buffer_spans(decided).await?;
+        }
 
-            if let Err(e) = self.flush_buffers().await {
-                error!("Failed to flush buffers: {}", e);
+        let Some(correlation) = &self.correlation else {
+            return Ok(());
+        };
+
+        let released = {
+            let mut correlation = correlation.lock().await;
+            if all {
+                correlation.drain()
+            } else {
+                correlation.expire(std::time::Instant::now())
             }
-        }
+        };
+
+        self.buffer_logs(released).await
     }
 
-    /// Flush buffered telemetry data
-    async fn flush_buffers(&self) -> Result<()> {
-        if !self.buffer.has_data().await {
-            return Ok(());
+    /// Buffer a log, holding it first if it is waiting for span context
+    async fn buffer_log(&self, log_entry: LogEntry) -> Result<()> {
+        match &self.correlation {
+            Some(correlation) => {
+                let ready = correlation.lock().await.add_log(log_entry, std::time::Instant::now());
+                self.buffer_logs(ready).await
+            }
+            None => self.buffer.add_classified_log(log_entry).await,
         }
+    }
 
-        let batches = self.buffer.flush_all(
-            self.collector_id.clone(),
-            self.config.pod_name.clone(),
-            self.config.namespace.clone(),
-        ).await?;
-
-        debug!("Flushing {} batches", batches.len());
+    /// Buffer a span, releasing logs from the same trace that were waiting
+    /// for it. With orphan detection the span itself may be held until its
+    /// parent arrives.
+    async fn buffer_span(&self, span: TraceSpan) -> Result<()> {
+        if let Some(correlation) = &self.correlation {
+            let released = correlation.lock().await.add_span(&span, std::time::Instant::now());
+            self.buffer_logs(released).await?;
+        }
+        match &self.orphan_detector {
+            Some(orphan_detector) => {
+                let ready = orphan_detector.lock().await.add_span(span, std::time::Instant::now());
+                self.buffer_spans(ready).await
+            }
+            None => self.buffer.add_classified_span(span).await,
+        }
+    }
 
-        for batch in batches {
-            if let Err(e) = self.transport.send_batch(batch).await {
-                error!("Failed to send batch: {}", e);
-                // TODO: Persistent retry logic
+    async fn buffer_spans(&self, spans: Vec<TraceSpan>) -> Result<()> {
+        for span in spans {
+            if orphan_spans::is_orphan(&span) {
+                self.orphan_spans.fetch_add(1, Ordering::Relaxed);
             }
+            self.buffer.add_classified_span(span).await?;
         }
+        Ok(())
+    }
 
+    /// Buffer the members of traces kept by tail sampling
+    async fn buffer_sampled(&self, sampled: SampledTraces) -> Result<()> {
+        if sampled.dropped > 0 {
+            self.traces_sampled_out.fetch_add(sampled.dropped as u64, Ordering::Relaxed);
+        }
+        for span in sampled.spans {
+            self.buffer_span(span).await?;
+        }
+        for log_entry in sampled.logs {
+            self.buffer_log(log_entry).await?;
+        }
         Ok(())
     }
 
-    /// Report metrics periodically
-    async fn report_metrics(&self) {
-        let mut metrics_interval = interval(Duration::from_secs(60));
+    async fn buffer_logs(&self, logs: Vec<LogEntry>) -> Result<()> {
+        for log_entry in logs {
+            self.buffer.add_classified_log(log_entry).await?;
+        }
+        Ok(())
+    }
 
-        loop {
-            metrics_interval.tick().await;
+    /// Ingest an entire file once and send it, without tailing or waiting for a signal
+    #[instrument(skip(self))]
+    pub async fn replay_file(&self, path: &str) -> Result<ReplayReport> {
+        info!("Replaying log file {}", path);
 
-            let (log_count, span_count) = self.buffer.sizes().await;
-            let utilization = self.buffer.utilization().await;
-            let transport_metrics = self.transport.metrics().await;
+        let file = File::open(path).await?;
+        let mut reader = BufReader::new(file);
+        let mut report = ReplayReport::default();
+        let mut decoder = CriDecoder::new();
+        let source = self.config.log_source_name(path);
 
-            info!(
-                "Collector metrics - Buffered: {} logs, {} spans ({:.1}% utilization), Transport: {:.1}% success rate, {} attempts",
+        loop {
+            let mut raw = Vec::new();
+            if reader.read_until(b'\n', &mut raw).await? == 0 {
+                break;
+            }
+
+            report.lines_read += 1;
+
+            let Some((line, lossy)) = decode_line(&raw, self.config.utf8_decoding) else {
+                warn!("Skipping line {} of {} that is not valid UTF-8", report.lines_read, path);
+                continue;
+            };
+
+            for log_line in split_raw_line(&line, report.lines_read == 1) {
+                let outcome = self.process_file_line(log_line, lossy, &mut decoder, &source).await?;
+                report.add_line(outcome);
+            }
+
+            // Send full batches as we go so the buffer never overflows
+            if self.buffer.should_flush().await {
+                report.add_flush(self.flush_buffers().await?);
+            }
+        }
+
+        for record in decoder.flush() {
+            let outcome = self.process_log_line(&record.message, false, Some(&record), &source).await?;
+            report.add_line(outcome);
+        }
+
+        self.release_held_logs(true).await?;
+        report.add_flush(self.flush_buffers().await?);
+
+        info!(
+            "Replay of {} complete - {} lines, {} filtered, {} logs, {} sampled out, {} rate limited, {} spans, {} batches sent, {} batches failed, {} batches expired",
+            path,
+            report.lines_read,
+            report.lines_filtered,
+            report.logs_parsed,
+            report.logs_sampled_out,
+            report.logs_rate_limited,
+            report.spans_parsed,
+            report.batches_sent,
+            report.batches_failed,
+            report.batches_expired
+        );
+
+        Ok(report)
+    }
+
+    /// Send each batch in a dead-letter file with the normal transport and
+    /// retries, one at a time in file order. The file is left untouched
+    /// unless `delete_on_success` is set; then delivered batches are removed
+    /// from it and the file is deleted once none are left. Lines that are
+    /// not valid batches count as failed and are always kept.
+    #[instrument(skip(self))]
+    pub async fn replay_dead_letter(&self, path: &str, delete_on_success: bool) -> Result<DeadLetterReport> {
+        info!("Replaying dead-letter file {}", path);
+
+        let mut report = DeadLetterReport::default();
+        let mut retained = Vec::new();
+
+        for (line, content) in dead_letter::read_lines(Path::new(path))? {
+            let result = match serde_json::from_str::<TelemetryBatch>(&content) {
+                Ok(batch) => {
+                    let batch_id = batch.metadata.batch_id.clone();
+                    let sent = if self.config.dry_run {
+                        info!("Dry run: would resend batch {} from line {}", batch_id, line);
+                        Ok(())
+                    } else {
+                        self.transport.send_batch(batch).await.map(|_| ())
+                    };
+                    (Some(batch_id), sent)
+                }
+                Err(e) => (None, Err(CollectorError::from(e))),
+            };
+
+            let outcome = match result {
+                (batch_id, Ok(())) => {
+                    info!("Line {}: batch {} delivered", line, batch_id.as_deref().unwrap_or("-"));
+                    BatchOutcome { line, batch_id, error: None }
+                }
+                (batch_id, Err(e)) => {
+                    error!("Line {}: batch {} not delivered: {}", line, batch_id.as_deref().unwrap_or("-"), e);
+                    self.record_error("replay dead letter", &e).await;
+                    retained.push(content);
+                    BatchOutcome { line, batch_id, error: Some(e.to_string()) }
+                }
+            };
+            report.batches.push(outcome);
+        }
+
+        if delete_on_success && !self.config.dry_run {
+            report.file_deleted = retained.is_empty();
+            let dead_letter_path = PathBuf::from(path);
+            files::blocking(move || dead_letter::retain(&dead_letter_path, &retained)).await?;
+        }
+
+        info!(
+            "Replay of dead-letter file {} complete - {} batches delivered, {} kept{}",
+            path,
+            report.sent(),
+            report.failed(),
+            if report.file_deleted { ", file deleted" } else { "" }
+        );
+
+        Ok(report)
+    }
+
+    /// Periodic flush of buffered data
+    async fn periodic_flush(&self) {
+        // Low latency mode checks often enough that nothing waits longer
+        // than its max delay
+        let period = if self.config.low_latency {
+            self.config.flush_interval.min(self.config.low_latency_max_delay)
+        } else {
+            self.config.flush_interval
+        };
+        let mut flush_interval = interval(period);
+
+        loop {
+            flush_interval.tick().await;
+
+            self.buffer_self_logs().await;
+            if let Err(e) = self.release_held_logs(false).await {
+                error!("Failed to release held logs: {}", e);
+                self.record_error("release held logs", &e).await;
+            }
+
+            if self.is_paused() {
+                debug!("Collection paused, leaving data buffered");
+                continue;
+            }
+
+            if !self.flush_due().await {
+                continue;
+            }
+
+            if let Err(e) = self.flush_buffers().await {
+                error!("Failed to flush buffers: {}", e);
+                self.record_error("flush buffers", &e).await;
+            }
+
+            // Drop what was just sent from the checkpoint right away
+            self.write_checkpoint().await;
+        }
+    }
+
+    /// Count this start in the persisted collector state, so crash-looping
+    /// sidecars show up in the restart count
+    async fn record_start(&self) -> Result<()> {
+        let Some(path) = &self.config.state_path else {
+            return Ok(());
+        };
+
+        let state_path = PathBuf::from(path);
+        let state = files::blocking(move || CollectorState::record_start(&state_path)).await?;
+        self.restarts.store(state.restarts(), Ordering::Relaxed);
+        self.batch_sequence.store(state.batch_sequence, Ordering::Relaxed);
+        *self.sequence_reserved.lock().await = state.batch_sequence;
+        if state.restarts() > 0 {
+            info!("Collector has restarted {} times", state.restarts());
+        }
+        Ok(())
+    }
+
+    /// Where buffer checkpoints are kept, when checkpointing is enabled
+    fn checkpoint_path(&self) -> Option<&Path> {
+        if self.config.buffer_checkpoint_interval.is_zero() {
+            return None;
+        }
+        self.config.buffer_checkpoint_path.as_deref().map(Path::new)
+    }
+
+    /// Resume each log path from the offset saved by the previous run. A
+    /// file replaced meanwhile no longer matches the saved inode, so its
+    /// first check reads the rest of the rotated file, when that is still
+    /// next to it, and then the new file from the beginning.
+    async fn restore_offsets(&self) -> Result<()> {
+        let Some(path) = &self.config.offsets_path else {
+            return Ok(());
+        };
+
+        let offsets = FileOffsets::load(Path::new(path))?;
+        let mut file_states = self.file_states.write().await;
+        for state in file_states.iter_mut() {
+            if let Some(offset) = offsets.files.get(&state.path) {
+                info!("Resuming {} from offset {}", state.path, offset.position);
+                state.last_position = offset.position;
+                state.inode = offset.inode;
+                state.last_modified = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Buffer the entries from a checkpoint left by a previous run. Without
+    /// saved offsets this only happens when tailing starts at the end of
+    /// existing files; from the beginning they are read again anyway and
+    /// restoring would send them twice.
+    async fn restore_checkpoint(&self) -> Result<()> {
+        let Some(path) = self.checkpoint_path() else {
+            return Ok(());
+        };
+        let Some(checkpoint) = Checkpoint::load(path)? else {
+            return Ok(());
+        };
+
+        if self.config.start_position == StartPosition::Beginning && self.config.offsets_path.is_none() {
+            info!(
+                "Discarding buffer checkpoint {}; existing log content is re-read from the beginning",
+                path.display()
+            );
+            return Checkpoint::remove(path);
+        }
+
+        info!(
+            "Restoring {} logs and {} spans from buffer checkpoint {}",
+            checkpoint.logs.len(),
+            checkpoint.spans.len(),
+            path.display()
+        );
+        for log_entry in checkpoint.logs {
+            self.buffer.add_classified_log(log_entry).await?;
+        }
+        for span in checkpoint.spans {
+            self.buffer.add_classified_span(span).await?;
+        }
+        Ok(())
+    }
+
+    /// Snapshot the buffer to disk on the checkpoint interval
+    async fn periodic_checkpoint(&self) {
+        let mut checkpoint_interval = interval(self.config.buffer_checkpoint_interval);
+
+        loop {
+            checkpoint_interval.tick().await;
+            self.write_checkpoint().await;
+        }
+    }
+
+    /// Replace the checkpoint with every entry read but not yet delivered,
+    /// removing it once there are none, and save the file offsets. Entries
+    /// held for correlation, tail sampling or orphan detection, buffered,
+    /// and in batches being sent are all included, so saved offsets never
+    /// skip an undelivered line. Offsets are taken first and entries are
+    /// collected in the order they move through the pipeline, so a line
+    /// read or moved meanwhile is at worst sent twice after a restart.
+    async fn write_checkpoint(&self) {
+        let offsets = self.file_offsets().await;
+
+        if let Some(path) = self.checkpoint_path() {
+            let (logs, spans) = self.undelivered_entries().await;
+            let checkpoint = Checkpoint::new(logs, spans);
+            let checkpoint_path = path.to_path_buf();
+            if let Err(e) = files::blocking(move || checkpoint.store(&checkpoint_path)).await {
+                warn!("Failed to write buffer checkpoint {}: {}", path.display(), e);
+                self.record_error("write buffer checkpoint", &e).await;
+            }
+        }
+
+        if let (Some(path), Some(offsets)) = (&self.config.offsets_path, offsets) {
+            let offsets_path = PathBuf::from(path);
+            if let Err(e) = files::blocking(move || offsets.store(&offsets_path)).await {
+                warn!("Failed to write file offsets {}: {}", path, e);
+                self.record_error("write file offsets", &e).await;
+            }
+        }
+    }
+
+    /// Copies of the entries read but not yet delivered, for a checkpoint
+    async fn undelivered_entries(&self) -> (Vec<LogEntry>, Vec<TraceSpan>) {
+        let (mut logs, mut spans) = (Vec::new(), Vec::new());
+        if let Some(tail_sampler) = &self.tail_sampler {
+            let (held_logs, held_spans) = tail_sampler.lock().await.snapshot();
+            logs.extend(held_logs);
+            spans.extend(held_spans);
+        }
+        if let Some(correlation) = &self.correlation {
+            logs.extend(correlation.lock().await.snapshot());
+        }
+        if let Some(orphan_detector) = &self.orphan_detector {
+            spans.extend(orphan_detector.lock().await.snapshot());
+        }
+
+        let sending = self.sending.lock().await;
+        let (buffered_logs, buffered_spans) = self.buffer.snapshot().await;
+        logs.extend(buffered_logs);
+        spans.extend(buffered_spans);
+        for batch in sending.values() {
+            logs.extend(batch.logs.iter().cloned());
+            spans.extend(batch.spans.iter().cloned());
+        }
+        (logs, spans)
+    }
+
+    /// Current read offset of each log path, when offsets are persisted
+    async fn file_offsets(&self) -> Option<FileOffsets> {
+        self.config.offsets_path.as_ref()?;
+
+        let files = self.file_states.read().await.iter()
+            .map(|state| {
+                let offset = FileOffset {
+                    inode: state.inode,
+                    position: state.last_position,
+                };
+                (state.path.clone(), offset)
+            })
+            .collect();
+        Some(FileOffsets { files })
+    }
+
+    /// Stop sending until `resume` is called. Files are still read and
+    /// entries buffered up to capacity; shutdown still flushes.
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::Relaxed) {
+            warn!("Collection paused, batches will not be sent until resumed");
+        }
+    }
+
+    /// Resume sending after `pause`, starting with what was buffered meanwhile
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::Relaxed) {
+            info!("Collection resumed");
+        }
+    }
+
+    /// Token the stats server requires to pause and resume sending; `None`
+    /// when those endpoints are disabled, including by an empty token
+    pub fn stats_control_token(&self) -> Option<&str> {
+        self.config.stats_control_token.as_deref().filter(|token| !token.trim().is_empty())
+    }
+
+    /// Whether sending is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Whether a periodic flush should send now. Small amounts of data are
+    /// held back to coalesce into fuller batches until they reach
+    /// `min_flush_size` or have waited `max_flush_delay`. Low latency mode
+    /// flushes whatever is buffered.
+    async fn flush_due(&self) -> bool {
+        let (buffered_logs, buffered_spans) = self.buffer.sizes().await;
+        let buffered = buffered_logs + buffered_spans;
+
+        // An empty buffer still flushes so derived metrics go out
+        if buffered == 0 || self.config.low_latency || buffered >= self.config.min_flush_size {
+            return true;
+        }
+
+        let waited = self.buffer.oldest_age().await.unwrap_or_default();
+        if waited >= self.config.max_flush_delay {
+            return true;
+        }
+
+        debug!(
+            "Holding {} buffered entries until min_flush_size {} is reached",
+            buffered, self.config.min_flush_size
+        );
+        false
+    }
+
+    /// Flush buffered telemetry data. Batches are drained one at a time as
+    /// send slots free up, so data waiting on a slow gateway stays in the
+    /// bounded buffer instead of piling up in drained batches.
+    async fn flush_buffers(&self) -> Result<FlushSummary> {
+        // Events logged while sending are not forwarded, or each send would
+        // log something that needs sending in turn
+        self_logs::suppressed(self.send_buffered()).await
+    }
+
+    /// Drain the buffer into batches and send them
+    async fn send_buffered(&self) -> Result<FlushSummary> {
+        let mut summary = FlushSummary::default();
+
+        let mut metrics = match &self.span_metrics {
+            Some(span_metrics) => span_metrics
+                .lock()
+                .await
+                .due_points(&self.config.service_name, std::time::Instant::now()),
+            None => Vec::new(),
+        };
+
+        if !self.buffer.has_data().await && metrics.is_empty() {
+            return Ok(summary);
+        }
+
+        let resource_attributes = self.resource_attributes.read().await.clone();
+        let max_in_flight = self.config.max_in_flight_batches.max(1);
+        let mut in_flight = FuturesUnordered::new();
+        let mut drain_error = None;
+
+        loop {
+            while in_flight.len() >= max_in_flight {
+                if let Some((result, batch_id)) = in_flight.next().await {
+                    self.record_send(&mut summary, result, batch_id).await;
+                }
+            }
+
+            // Drained and registered under one lock, so a checkpoint finds
+            // every entry either in the buffer or among the batches sending
+            let mut sending = self.sending.lock().await;
+            let batch = match self.next_batch(&mut metrics, &resource_attributes).await {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                Err(e) => {
+                    // Let sends already under way finish before reporting
+                    drain_error = Some(e);
+                    break;
+                }
+            };
+
+            if self.config.dry_run {
+                info!(
+                    "Dry run: would send batch {} (sequence {}) with {} logs, {} spans and {} metrics",
+                    batch.metadata.batch_id,
+                    batch.metadata.sequence,
+                    batch.logs.len(),
+                    batch.spans.len(),
+                    batch.metrics.len()
+                );
+                summary.sent += 1;
+                continue;
+            }
+
+            // Sending consumes the batch, so keep a copy until it finishes
+            let batch_id = batch.metadata.batch_id.clone();
+            if self.config.dead_letter_path.is_some() || self.checkpoint_path().is_some() {
+                sending.insert(batch_id.clone(), batch.clone());
+            }
+            drop(sending);
+
+            let send = self.transport.send_batch(batch);
+            in_flight.push(async move { (send.await, batch_id) });
+        }
+
+        while let Some((result, batch_id)) = in_flight.next().await {
+            self.record_send(&mut summary, result, batch_id).await;
+        }
+
+        debug!(
+            "Flushed {} batches ({} failed, {} expired)",
+            summary.sent + summary.failed + summary.expired,
+            summary.failed,
+            summary.expired
+        );
+
+        match drain_error {
+            Some(e) => Err(e),
+            None => Ok(summary),
+        }
+    }
+
+    /// Count a finished send, tracking failures in a row for readiness.
+    /// Batches that expired or failed every retry are kept in the
+    /// dead-letter file when one is set.
+    async fn record_send(&self, summary: &mut FlushSummary, result: Result<PayloadSize>, batch_id: String) {
+        let batch = self.sending.lock().await.remove(&batch_id);
+        match summary.record(result) {
+            Some(e) => {
+                self.consecutive_send_failures.fetch_add(1, Ordering::Relaxed);
+                self.record_error("send batch", &e).await;
+                if let Some(batch) = batch {
+                    self.write_dead_letter(batch).await;
+                }
+            }
+            None => {
+                self.consecutive_send_failures.store(0, Ordering::Relaxed);
+                self.gateway_reached.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Append a batch that could not be delivered to `dead_letter_path`
+    async fn write_dead_letter(&self, batch: TelemetryBatch) {
+        let Some(path) = self.config.dead_letter_path.clone() else {
+            return;
+        };
+
+        let batch_id = batch.metadata.batch_id.clone();
+        let written = files::blocking(move || dead_letter::append(Path::new(&path), &batch)).await;
+
+        match written {
+            Ok(()) => {
+                self.batches_dead_lettered.fetch_add(1, Ordering::Relaxed);
+                info!("Batch {} written to the dead-letter file", batch_id);
+            }
+            Err(e) => {
+                error!("Failed to write batch {} to the dead-letter file: {}", batch_id, e);
+                self.record_error("write dead letter", &e).await;
+            }
+        }
+    }
+
+    /// Drain the next batch ready to send, or `None` once the buffer is
+    /// empty. Derived metrics ride along with the first batch, or alone when
+    /// nothing else is buffered.
+    async fn next_batch(
+        &self,
+        metrics: &mut Vec<MetricPoint>,
+        resource_attributes: &HashMap<String, String>,
+    ) -> Result<Option<TelemetryBatch>> {
+        loop {
+            let drained = self.buffer.drain_batch(
+                self.collector_id.clone(),
+                self.config.pod_name.clone(),
+                self.config.namespace.clone(),
+            ).await?;
+
+            let mut batch = match drained {
+                Some(batch) => batch,
+                None if !metrics.is_empty() => TelemetryBatch::new(
+                    Vec::new(),
+                    Vec::new(),
+                    self.collector_id.clone(),
+                    self.config.pod_name.clone(),
+                    self.config.namespace.clone(),
+                ),
+                None => return Ok(None),
+            };
+
+            if !metrics.is_empty() {
+                batch = batch.with_metrics(std::mem::take(metrics));
+            }
+            batch = batch.with_resource_attributes(resource_attributes.clone());
+
+            if self.config.validate_before_send {
+                self.drop_invalid_entries(&mut batch);
+                if batch.is_empty() {
+                    continue;
+                }
+            }
+
+            // Numbered once final so the gateway sees a gap only for batches
+            // that were built for sending and then lost
+            let sequence = self.next_sequence().await;
+            return Ok(Some(batch.with_sequence(sequence)));
+        }
+    }
+
+    /// Number the next batch. With `state_path` set, sequence numbers are
+    /// reserved in the state file a range at a time before use, so a
+    /// restarted collector never repeats a number, even after a crash.
+    async fn next_sequence(&self) -> u64 {
+        let sequence = self.batch_sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some(path) = &self.config.state_path else {
+            return sequence;
+        };
+
+        let mut reserved = self.sequence_reserved.lock().await;
+        if sequence > *reserved {
+            let reserve_to = sequence + SEQUENCE_RESERVATION - 1;
+            let state_path = PathBuf::from(path);
+            match files::blocking(move || CollectorState::record_batch_sequence(&state_path, reserve_to)).await {
+                Ok(()) => *reserved = reserve_to,
+                Err(e) => {
+                    warn!("Failed to reserve batch sequence numbers in {}: {}", path, e);
+                    self.record_error("reserve batch sequence", &e).await;
+                }
+            }
+        }
+        sequence
+    }
+
+    /// Record the last batch sequence number in the state file, so the next
+    /// run continues right after it
+    async fn save_batch_sequence(&self) {
+        let Some(path) = &self.config.state_path else {
+            return;
+        };
+
+        let sequence = self.batch_sequence.load(Ordering::Relaxed);
+        let state_path = PathBuf::from(path);
+        if let Err(e) = files::blocking(move || CollectorState::record_batch_sequence(&state_path, sequence)).await {
+            warn!("Failed to save batch sequence to {}: {}", path, e);
+            self.record_error("save batch sequence", &e).await;
+        }
+    }
+
+    /// Re-read the downward API files
+    pub async fn reload_resource_attributes(&self) {
+        let attributes = merged_resource_attributes(&self.config, &self.host_attributes);
+        info!("Loaded {} resource attributes from pod info files", attributes.len());
+        *self.resource_attributes.write().await = attributes;
+    }
+
+    /// Reload resource attributes whenever SIGHUP is received
+    #[cfg(unix)]
+    async fn reload_on_sighup(&self) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!("Failed to install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            info!("Received SIGHUP, reloading pod info");
+            self.reload_resource_attributes().await;
+        }
+    }
+
+    /// Drop entries the gateway would reject rather than failing the whole batch
+    fn drop_invalid_entries(&self, batch: &mut TelemetryBatch) {
+        let reasons = batch.drop_invalid();
+
+        if let Some(first) = reasons.first() {
+            warn!(
+                "Dropped {} invalid entries from batch {} (first: {})",
+                reasons.len(),
+                batch.metadata.batch_id,
+                first
+            );
+            self.invalid_entries_dropped.fetch_add(reasons.len() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Report metrics periodically
+    async fn report_metrics(&self) {
+        let mut metrics_interval = interval(Duration::from_secs(60));
+
+        loop {
+            metrics_interval.tick().await;
+
+            let (log_count, span_count) = self.buffer.sizes().await;
+            let utilization = self.buffer.utilization().await;
+            let transport_metrics = self.transport.metrics().await;
+
+            info!(
+                "Collector metrics - Buffered: {} logs, {} spans ({:.1}% utilization), Transport: {:.1}% success rate, {} attempts, {} bytes sent ({} per batch, {:.2}x compression), {} stale batches dropped, {} invalid entries dropped, {} lines filtered",
                 log_count,
                 span_count,
                 utilization,
                 transport_metrics.success_rate,
-                transport_metrics.attempts
+                transport_metrics.attempts,
+                transport_metrics.bytes_sent_compressed,
+                transport_metrics.avg_batch_bytes,
+                transport_metrics.compression_ratio,
+                transport_metrics.expired,
+                self.invalid_entries_dropped.load(Ordering::Relaxed),
+                self.lines_filtered.load(Ordering::Relaxed)
             );
         }
     }
 
+    /// Periodically buffer a heartbeat entry so a quiet pod still shows the
+    /// collector is alive and delivering
+    async fn emit_heartbeats(&self) {
+        let mut heartbeat_interval = interval(self.config.self_telemetry_interval);
+
+        loop {
+            heartbeat_interval.tick().await;
+            self.send_heartbeat().await;
+        }
+    }
+
+    /// Send a heartbeat in a batch of its own. Heartbeats skip the buffer,
+    /// and with it sampling and correlation, so they never evict buffered
+    /// telemetry; they are not sent while sending is paused. They are left
+    /// out of the transport metrics they report on.
+    async fn send_heartbeat(&self) {
+        if self.is_paused() {
+            return;
+        }
+
+        let resource_attributes = self.resource_attributes.read().await.clone();
+        let batch = TelemetryBatch::new(
+            vec![self.heartbeat().await],
+            Vec::new(),
+            self.collector_id.clone(),
+            self.config.pod_name.clone(),
+            self.config.namespace.clone(),
+        )
+        .with_resource_attributes(resource_attributes)
+        .with_sequence(self.next_sequence().await);
+
+        if self.config.dry_run {
+            info!("Dry run: would send heartbeat batch {} (sequence {})", batch.metadata.batch_id, batch.metadata.sequence);
+            return;
+        }
+
+        match self.transport.send_untracked(batch).await {
+            Ok(_) => self.gateway_reached.store(true, Ordering::Relaxed),
+            Err(e) => {
+                warn!("Failed to send collector heartbeat: {}", e);
+                self.record_error("send heartbeat", &e).await;
+            }
+        }
+    }
+
+    /// Buffer the collector's own log events logged since the last call,
+    /// under this collector's identity. They bypass sampling, rate limits
+    /// and correlation.
+    async fn buffer_self_logs(&self) {
+        let Some(self_logs) = &self.self_logs else {
+            return;
+        };
+
+        let mut receiver = self_logs.lock().await;
+        while let Some(mut log_entry) = receiver.try_recv() {
+            log_entry.service_name = self.config.service_name.clone();
+            log_entry.pod_name = self.config.pod_name.clone();
+            log_entry.namespace = self.config.namespace.clone();
+
+            // A full buffer must not log a warning that is forwarded in turn
+            if let Err(e) = self_logs::suppressed(self.buffer.add_classified_log(log_entry)).await {
+                self.record_error("buffer self log", &e).await;
+            }
+        }
+    }
+
+    /// Build a heartbeat entry from the current collector statistics
+    async fn heartbeat(&self) -> LogEntry {
+        let stats = self.stats().await;
+
+        LogEntry::new(
+            LogLevel::Info,
+            "collector heartbeat".to_string(),
+            self.config.service_name.clone(),
+            self.config.pod_name.clone(),
+            self.config.namespace.clone(),
+        )
+        .with_attribute("telemetry.self".to_string(), "true".to_string())
+        .with_attribute("collector.id".to_string(), stats.collector_id)
+        .with_attribute("collector.uptime_seconds".to_string(), stats.uptime_seconds.to_string())
+        .with_attribute("collector.restarts".to_string(), stats.restarts.to_string())
+        .with_attribute("buffer.logs".to_string(), stats.buffered_logs.to_string())
+        .with_attribute("buffer.spans".to_string(), stats.buffered_spans.to_string())
+        .with_attribute("buffer.utilization".to_string(), format!("{:.1}", stats.buffer_utilization))
+        .with_attribute("transport.success_rate".to_string(), format!("{:.1}", stats.transport_success_rate))
+    }
+
     /// Graceful shutdown
     async fn shutdown(&self) -> Result<()> {
         info!("Performing graceful shutdown");
+        self.shutting_down.store(true, Ordering::Relaxed);
 
+        self.buffer_self_logs().await;
+        self.release_held_logs(true).await?;
         self.flush_buffers().await?;
+        self.write_checkpoint().await;
+        self.save_batch_sequence().await;
 
         // Report final metrics
         let transport_metrics = self.transport.metrics().await;
@@ -352,14 +1883,42 @@ impl SidecarCollector {
     fn clone_for_task(&self) -> Self {
         Self {
             config: self.config.clone(),
-            parser: LogParserFactory::create_parser(
-                "composite",
-                self.config.enable_trace_correlation,
-            ),
+            parser: Arc::clone(&self.parser),
+            sampler: Arc::clone(&self.sampler),
+            line_filter: Arc::clone(&self.line_filter),
             buffer: Arc::clone(&self.buffer),
             transport: Arc::clone(&self.transport),
             collector_id: self.collector_id.clone(),
+            started_at: self.started_at,
+            restarts: Arc::clone(&self.restarts),
             file_states: Arc::clone(&self.file_states),
+            resource_attributes: Arc::clone(&self.resource_attributes),
+            host_attributes: Arc::clone(&self.host_attributes),
+            invalid_entries_dropped: Arc::clone(&self.invalid_entries_dropped),
+            invalid_id_spans_dropped: Arc::clone(&self.invalid_id_spans_dropped),
+            lines_filtered: Arc::clone(&self.lines_filtered),
+            batches_dead_lettered: Arc::clone(&self.batches_dead_lettered),
+            traces_sampled_out: Arc::clone(&self.traces_sampled_out),
+            logs_rate_limited: Arc::clone(&self.logs_rate_limited),
+            orphan_spans: Arc::clone(&self.orphan_spans),
+            batch_sequence: Arc::clone(&self.batch_sequence),
+            sequence_reserved: Arc::clone(&self.sequence_reserved),
+            paused: Arc::clone(&self.paused),
+            reading_throttled: Arc::clone(&self.reading_throttled),
+            read_throttles: Arc::clone(&self.read_throttles),
+            gateway_reached: Arc::clone(&self.gateway_reached),
+            source_checked: Arc::clone(&self.source_checked),
+            consecutive_send_failures: Arc::clone(&self.consecutive_send_failures),
+            shutting_down: Arc::clone(&self.shutting_down),
+            correlation: self.correlation.clone(),
+            level_limiter: self.level_limiter.clone(),
+            tail_sampler: self.tail_sampler.clone(),
+            orphan_detector: self.orphan_detector.clone(),
+            sending: Arc::clone(&self.sending),
+            span_metrics: self.span_metrics.clone(),
+            recent_logs: self.recent_logs.clone(),
+            recent_errors: Arc::clone(&self.recent_errors),
+            self_logs: self.self_logs.clone(),
         }
     }
 
@@ -368,9 +1927,15 @@ impl SidecarCollector {
         let (buffered_logs, buffered_spans) = self.buffer.sizes().await;
         let buffer_utilization = self.buffer.utilization().await;
         let transport_metrics = self.transport.metrics().await;
+        let span_metrics = match &self.span_metrics {
+            Some(span_metrics) => span_metrics.lock().await.snapshot(),
+            None => Vec::new(),
+        };
 
         CollectorStats {
             collector_id: self.collector_id.clone(),
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+            restarts: self.restarts.load(Ordering::Relaxed),
             service_name: self.config.service_name.clone(),
             pod_name: self.config.pod_name.clone(),
             namespace: self.config.namespace.clone(),
@@ -380,14 +1945,229 @@ impl SidecarCollector {
             transport_success_rate: transport_metrics.success_rate,
             transport_attempts: transport_metrics.attempts,
             avg_transport_duration_ms: transport_metrics.avg_duration_ms,
+            bytes_sent_uncompressed: transport_metrics.bytes_sent_uncompressed,
+            bytes_sent_compressed: transport_metrics.bytes_sent_compressed,
+            compression_ratio: transport_metrics.compression_ratio,
+            invalid_entries_dropped: self.invalid_entries_dropped.load(Ordering::Relaxed),
+            invalid_id_spans_dropped: self.invalid_id_spans_dropped.load(Ordering::Relaxed),
+            lines_filtered: self.lines_filtered.load(Ordering::Relaxed),
+            traces_sampled_out: self.traces_sampled_out.load(Ordering::Relaxed),
+            orphan_spans: self.orphan_spans.load(Ordering::Relaxed),
+            logs_rate_limited: self.logs_rate_limited.load(Ordering::Relaxed),
+            batches_expired: transport_metrics.expired,
+            batches_dead_lettered: self.batches_dead_lettered.load(Ordering::Relaxed),
+            paused: self.is_paused(),
+            readiness: self.readiness().await.status,
+            reading_throttled: self.reading_throttled.load(Ordering::Relaxed),
+            read_throttles: self.read_throttles.load(Ordering::Relaxed),
+            errors_by_category: self.recent_errors.lock().await.counts(),
+            log_sources: self.log_sources().await,
+            span_metrics,
+        }
+    }
+
+    /// Keep an error for `GET /errors` and count it by category
+    async fn record_error(&self, context: &str, error: &CollectorError) {
+        self.recent_errors.lock().await.record(context, error);
+    }
+
+    /// Recently recorded collector errors, newest first
+    pub async fn recent_errors(&self) -> Vec<RecordedError> {
+        self.recent_errors.lock().await.recent()
+    }
+
+    /// Recently parsed log entries matching `query`, newest first; `None`
+    /// when the recent log index is disabled
+    pub async fn recent_logs(&self, query: &RecentQuery) -> Option<Vec<LogEntry>> {
+        let recent_logs = self.recent_logs.as_ref()?;
+        Some(recent_logs.lock().await.query(query))
+    }
+}
+
+/// Host attributes overlaid with those from the downward API files, which
+/// win on collisions
+fn merged_resource_attributes(config: &Config, host_attributes: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut attributes = host_attributes.clone();
+    attributes.extend(podinfo::load_resource_attributes(&config.pod_info_paths));
+    attributes
+}
+
+/// Decode a raw line read from a file, reporting whether invalid UTF-8 bytes
+/// were replaced; `None` when the line is invalid and decoding is strict
+fn decode_line(raw: &[u8], decoding: Utf8Decoding) -> Option<(Cow<'_, str>, bool)> {
+    match std::str::from_utf8(raw) {
+        Ok(line) => Some((Cow::Borrowed(line), false)),
+        Err(_) if decoding == Utf8Decoding::Strict => None,
+        Err(_) => Some((String::from_utf8_lossy(raw), true)),
+    }
+}
+
+/// At most `max_bytes` of `line`, cut back to a character boundary; zero
+/// keeps the whole line
+fn truncate_to_char_boundary(line: &str, max_bytes: usize) -> &str {
+    if max_bytes == 0 || line.len() <= max_bytes {
+        return line;
+    }
+
+    let mut end = max_bytes;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Split a raw line read from a file into the log lines it holds. Strips the
+/// line ending, a UTF-8 BOM at the start of the file and NUL padding (left by
+/// truncation on some filesystems), and treats a lone `\r` as a separator.
+fn split_raw_line(raw: &str, at_file_start: bool) -> Vec<&str> {
+    let mut line = raw.strip_suffix('\n').unwrap_or(raw);
+    line = line.strip_suffix('\r').unwrap_or(line);
+
+    if at_file_start {
+        line = line.strip_prefix('\u{feff}').unwrap_or(line);
+    }
+
+    if !line.contains(['\r', '\0']) {
+        return vec![line];
+    }
+
+    line.split('\r')
+        .map(|piece| piece.trim_matches('\0'))
+        .filter(|piece| !piece.is_empty())
+        .collect()
+}
+
+/// What a single log line produced, counting each record of a split line
+#[derive(Debug, Default)]
+struct LineOutcome {
+    log: usize,
+    span: usize,
+    sampled_out: usize,
+    rate_limited: usize,
+    filtered: usize,
+}
+
+impl LineOutcome {
+    /// Combine the outcomes of records split from one line
+    fn merge(&mut self, other: LineOutcome) {
+        self.log += other.log;
+        self.span += other.span;
+        self.sampled_out += other.sampled_out;
+        self.rate_limited += other.rate_limited;
+        self.filtered += other.filtered;
+    }
+}
+
+/// Result of flushing the buffer
+#[derive(Debug, Default)]
+struct FlushSummary {
+    sent: usize,
+    failed: usize,
+    /// Batches dropped for waiting longer than `max_batch_send_age`
+    expired: usize,
+}
+
+/// Counts from a one-shot file replay
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub lines_read: usize,
+    pub logs_parsed: usize,
+    pub logs_sampled_out: usize,
+    pub logs_rate_limited: usize,
+    pub lines_filtered: usize,
+    pub spans_parsed: usize,
+    pub batches_sent: usize,
+    pub batches_failed: usize,
+    pub batches_expired: usize,
+}
+
+impl FlushSummary {
+    /// Count a send, handing back the error when it failed
+    fn record(&mut self, result: Result<PayloadSize>) -> Option<CollectorError> {
+        match result {
+            Ok(_) => {
+                self.sent += 1;
+                None
+            }
+            Err(e @ CollectorError::BatchExpired(_)) => {
+                warn!("Dropping stale batch: {}", e);
+                self.expired += 1;
+                Some(e)
+            }
+            Err(e) => {
+                error!("Failed to send batch: {}", e);
+                self.failed += 1;
+                Some(e)
+            }
         }
     }
 }
 
+impl ReplayReport {
+    fn add_line(&mut self, outcome: LineOutcome) {
+        self.logs_parsed += outcome.log;
+        self.spans_parsed += outcome.span;
+        self.logs_sampled_out += outcome.sampled_out;
+        self.logs_rate_limited += outcome.rate_limited;
+        self.lines_filtered += outcome.filtered;
+    }
+
+    fn add_flush(&mut self, summary: FlushSummary) {
+        self.batches_sent += summary.sent;
+        self.batches_failed += summary.failed;
+        self.batches_expired += summary.expired;
+    }
+}
+
+/// Stage of the collector's lifecycle reported by `/readyz`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessState {
+    Starting,
+    Ready,
+    Degraded,
+    ShuttingDown,
+}
+
+/// Readiness state with why the collector is not ready
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: ReadinessState,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+impl Readiness {
+    fn new(status: ReadinessState, reasons: Vec<String>) -> Self {
+        Self { status, reasons }
+    }
+
+    /// Whether traffic may depend on the collector. A degraded collector
+    /// still buffers, so only starting and shutting down count as not ready.
+    pub fn is_ready(&self) -> bool {
+        matches!(self.status, ReadinessState::Ready | ReadinessState::Degraded)
+    }
+}
+
+/// How recently a monitored log path produced lines
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSourceHealth {
+    pub path: String,
+    pub source: String,
+    /// Seconds since a line was last read, or since monitoring started
+    pub idle_seconds: u64,
+    /// Idle threshold for the path, `None` when it is not checked
+    pub max_idle_seconds: Option<u64>,
+    pub healthy: bool,
+}
+
 /// Collector statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CollectorStats {
     pub collector_id: String,
+    pub uptime_seconds: u64,
+    /// Starts before this one, counted in `state_path`; zero without it
+    pub restarts: u64,
     pub service_name: String,
     pub pod_name: String,
     pub namespace: String,
@@ -397,4 +2177,147 @@ pub struct CollectorStats {
     pub transport_success_rate: f64,
     pub transport_attempts: u64,
     pub avg_transport_duration_ms: u64,
+    pub bytes_sent_uncompressed: u64,
+    pub bytes_sent_compressed: u64,
+    pub compression_ratio: f64,
+    pub invalid_entries_dropped: u64,
+    pub invalid_id_spans_dropped: u64,
+    pub lines_filtered: u64,
+    pub traces_sampled_out: u64,
+    /// Logs dropped by per-level rate limits
+    pub logs_rate_limited: u64,
+    /// Spans tagged as missing their same-service parent
+    pub orphan_spans: u64,
+    pub batches_expired: u64,
+    /// Batches appended to `DEAD_LETTER_PATH`
+    pub batches_dead_lettered: u64,
+    /// Whether sending is paused
+    pub paused: bool,
+    /// Readiness reported by `/readyz`
+    pub readiness: ReadinessState,
+    /// Whether file reads are paused until the buffer drains
+    pub reading_throttled: bool,
+    /// Times file reads were paused by backpressure
+    pub read_throttles: u64,
+    /// Errors recorded since startup by category
+    pub errors_by_category: BTreeMap<String, u64>,
+    /// Freshness of each monitored log path
+    pub log_sources: Vec<LogSourceHealth>,
+    /// RED metrics per span operation; empty unless span metrics are enabled
+    pub span_metrics: Vec<OperationMetrics>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(last_position: u64, last_modified: Option<std::time::SystemTime>) -> FileState {
+        FileState {
+            path: "/var/log/app.log".to_string(),
+            source: "app.log".to_string(),
+            last_position,
+            last_modified,
+            inode: Some(42),
+            mtime_unsupported: false,
+            cri: CriDecoder::new(),
+            last_read_at: Instant::now(),
+            idle: false,
+            failing: false,
+        }
+    }
+
+    #[test]
+    fn test_monitor_backoff() {
+        use rand::SeedableRng;
+
+        let config = Config {
+            file_error_threshold: 3,
+            file_error_backoff: Duration::from_secs(10),
+            file_error_max_backoff: Duration::from_secs(60),
+            ..Config::default()
+        };
+        let mut backoff = MonitorBackoff::from_config(&config);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        assert_eq!(backoff.record_error(&mut rng), None);
+        assert_eq!(backoff.record_error(&mut rng), None);
+
+        // Delays double from the base, capped at the max, with up to 50% jitter
+        for full_delay in [10, 20, 40, 60, 60] {
+            let pause = backoff.record_error(&mut rng).unwrap();
+            let full_delay = Duration::from_secs(full_delay);
+            assert!(pause >= full_delay / 2 && pause <= full_delay, "{:?} vs {:?}", pause, full_delay);
+        }
+
+        assert_eq!(backoff.record_success(), 7);
+        assert_eq!(backoff.record_error(&mut rng), None);
+    }
+
+    #[test]
+    fn test_decode_line() {
+        assert_eq!(decode_line(b"INFO: ok\n", Utf8Decoding::Lossy), Some((Cow::Borrowed("INFO: ok\n"), false)));
+
+        let raw = b"INFO: caf\xe9 opened\n";
+        let (line, lossy) = decode_line(raw, Utf8Decoding::Lossy).unwrap();
+        assert_eq!(line, "INFO: caf\u{fffd} opened\n");
+        assert!(lossy);
+        assert_eq!(decode_line(raw, Utf8Decoding::Strict), None);
+    }
+
+    #[test]
+    fn test_truncate_to_char_boundary() {
+        assert_eq!(truncate_to_char_boundary("timeout", 0), "timeout");
+        assert_eq!(truncate_to_char_boundary("timeout", 4), "time");
+        // "é" is two bytes, so cutting inside it backs off to before it
+        assert_eq!(truncate_to_char_boundary("café au lait", 4), "caf");
+    }
+
+    #[test]
+    fn test_split_raw_line() {
+        assert_eq!(split_raw_line("INFO: plain\n", false), vec!["INFO: plain"]);
+        assert_eq!(split_raw_line("INFO: windows\r\n", false), vec!["INFO: windows"]);
+        assert_eq!(split_raw_line("INFO: no newline", false), vec!["INFO: no newline"]);
+        assert_eq!(split_raw_line("\n", false), vec![""]);
+
+        // BOM only at the start of the file
+        assert_eq!(split_raw_line("\u{feff}INFO: first\n", true), vec!["INFO: first"]);
+        assert_eq!(split_raw_line("\u{feff}INFO: first\n", false), vec!["\u{feff}INFO: first"]);
+
+        // Lone carriage returns separate lines
+        assert_eq!(split_raw_line("INFO: one\rINFO: two\r\n", false), vec!["INFO: one", "INFO: two"]);
+
+        // NUL padding is skipped
+        assert!(split_raw_line("\0\0\0\0\n", false).is_empty());
+        assert_eq!(split_raw_line("\0\0\0INFO: after padding\n", false), vec!["INFO: after padding"]);
+    }
+
+    #[test]
+    fn test_read_start_without_mtime() {
+        let state = state(100, None);
+
+        let appended = FileSnapshot { size: 150, modified: None, inode: Some(42) };
+        assert_eq!(state.read_start(&appended), Some(100));
+
+        let unchanged = FileSnapshot { size: 100, modified: None, inode: Some(42) };
+        assert_eq!(state.read_start(&unchanged), None);
+
+        let truncated = FileSnapshot { size: 10, modified: None, inode: Some(42) };
+        assert_eq!(state.read_start(&truncated), Some(0));
+
+        let replaced = FileSnapshot { size: 200, modified: None, inode: Some(7) };
+        assert_eq!(state.read_start(&replaced), Some(0));
+    }
+
+    #[test]
+    fn test_read_start_with_mtime() {
+        let then = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let now = then + Duration::from_secs(5);
+        let state = state(100, Some(then));
+
+        let unchanged = FileSnapshot { size: 100, modified: Some(then), inode: Some(42) };
+        assert_eq!(state.read_start(&unchanged), None);
+
+        let touched = FileSnapshot { size: 100, modified: Some(now), inode: Some(42) };
+        assert_eq!(state.read_start(&touched), Some(100));
+    }
 }
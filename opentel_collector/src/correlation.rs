@@ -0,0 +1,217 @@
+//! Short-lived join of logs and spans that share a trace id
+
+use crate::telemetry::{LogEntry, TraceSpan};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Holds logs that carry a `trace_id` but no `span_id` for a bounded window,
+/// attaching span context when a matching span is parsed. Logs that see no
+/// span within the window are released as-is.
+#[derive(Debug)]
+pub struct CorrelationBuffer {
+    window: Duration,
+    max_pending: usize,
+    pending: VecDeque<(Instant, LogEntry)>,
+    recent_spans: HashMap<String, (Instant, String)>,
+}
+
+impl CorrelationBuffer {
+    pub fn new(window: Duration, max_pending: usize) -> Self {
+        Self {
+            window,
+            max_pending: max_pending.max(1),
+            pending: VecDeque::new(),
+            recent_spans: HashMap::new(),
+        }
+    }
+
+    /// Accept a parsed log, returning the logs that are ready to buffer. The
+    /// log is held if it is waiting for span context, and the oldest held log
+    /// is released as-is when the buffer is full.
+    pub fn add_log(&mut self, mut log_entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        let trace_id = match (&log_entry.trace_id, &log_entry.span_id) {
+            (Some(trace_id), None) => trace_id.clone(),
+            _ => return vec![log_entry],
+        };
+
+        // A span for this trace was already seen
+        if let Some((seen, span_id)) = self.recent_spans.get(&trace_id)
+            && now.duration_since(*seen) <= self.window
+        {
+            log_entry.span_id = Some(span_id.clone());
+            return vec![log_entry];
+        }
+
+        let mut ready = Vec::new();
+        if self.pending.len() >= self.max_pending
+            && let Some((_, oldest)) = self.pending.pop_front()
+        {
+            ready.push(oldest);
+        }
+
+        self.pending.push_back((now, log_entry));
+        ready
+    }
+
+    /// Record a span and release any held logs from the same trace with its
+    /// span id attached
+    pub fn add_span(&mut self, span: &TraceSpan, now: Instant) -> Vec<LogEntry> {
+        if self.recent_spans.len() < self.max_pending || self.recent_spans.contains_key(&span.trace_id) {
+            self.recent_spans
+                .insert(span.trace_id.clone(), (now, span.span_id.clone()));
+        }
+
+        let (matched, waiting): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(_, log)| log.trace_id.as_deref() == Some(span.trace_id.as_str()));
+        self.pending = waiting;
+
+        matched
+            .into_iter()
+            .map(|(_, mut log)| {
+                log.span_id = Some(span.span_id.clone());
+                log
+            })
+            .collect()
+    }
+
+    /// Release held logs whose window has passed, and forget old spans
+    pub fn expire(&mut self, now: Instant) -> Vec<LogEntry> {
+        let window = self.window;
+        self.recent_spans
+            .retain(|_, (seen, _)| now.duration_since(*seen) <= window);
+
+        let mut expired = Vec::new();
+        while let Some((held_since, _)) = self.pending.front() {
+            if now.duration_since(*held_since) <= window {
+                break;
+            }
+            if let Some((_, log)) = self.pending.pop_front() {
+                expired.push(log);
+            }
+        }
+        expired
+    }
+
+    /// Release every held log, e.g. on shutdown
+    pub fn drain(&mut self) -> Vec<LogEntry> {
+        self.pending.drain(..).map(|(_, log)| log).collect()
+    }
+
+    /// Number of logs currently held
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Copies of the held logs, oldest first, e.g. for a checkpoint
+    pub fn snapshot(&self) -> Vec<LogEntry> {
+        self.pending.iter().map(|(_, log)| log.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::LogLevel;
+
+    fn log(trace_id: &str, message: &str) -> LogEntry {
+        LogEntry {
+            trace_id: Some(trace_id.to_string()),
+            ..LogEntry::new(
+                LogLevel::Info,
+                message.to_string(),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )
+        }
+    }
+
+    fn span(trace_id: &str, span_id: &str) -> TraceSpan {
+        TraceSpan::new(
+            trace_id.to_string(),
+            span_id.to_string(),
+            "db.query".to_string(),
+            "test-service".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_log_gains_context_from_late_span() {
+        let mut buffer = CorrelationBuffer::new(Duration::from_secs(2), 100);
+        let start = Instant::now();
+
+        assert!(buffer.add_log(log("trace-1", "waiting"), start).is_empty());
+        assert_eq!(buffer.pending(), 1);
+
+        let released = buffer.add_span(&span("trace-1", "span-1"), start + Duration::from_secs(1));
+
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].span_id.as_deref(), Some("span-1"));
+        assert_eq!(buffer.pending(), 0);
+    }
+
+    #[test]
+    fn test_log_gains_context_from_earlier_span() {
+        let mut buffer = CorrelationBuffer::new(Duration::from_secs(2), 100);
+        let start = Instant::now();
+
+        assert!(buffer.add_span(&span("trace-1", "span-1"), start).is_empty());
+        let ready = buffer.add_log(log("trace-1", "late"), start + Duration::from_secs(1));
+
+        assert_eq!(ready[0].span_id.as_deref(), Some("span-1"));
+    }
+
+    #[test]
+    fn test_orphans_released_after_window() {
+        let mut buffer = CorrelationBuffer::new(Duration::from_secs(2), 100);
+        let start = Instant::now();
+
+        buffer.add_log(log("trace-1", "orphan"), start);
+        buffer.add_log(log("trace-2", "younger"), start + Duration::from_secs(2));
+
+        let expired = buffer.expire(start + Duration::from_secs(3));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].message, "orphan");
+        assert_eq!(expired[0].span_id, None);
+        assert_eq!(buffer.pending(), 1);
+
+        // A span arriving after the window does not reach the released log
+        assert!(buffer.add_span(&span("trace-1", "span-1"), start + Duration::from_secs(3)).is_empty());
+    }
+
+    #[test]
+    fn test_bounded_pending_logs() {
+        let mut buffer = CorrelationBuffer::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+
+        assert!(buffer.add_log(log("trace-1", "one"), now).is_empty());
+        assert!(buffer.add_log(log("trace-2", "two"), now).is_empty());
+        let evicted = buffer.add_log(log("trace-3", "three"), now);
+
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].message, "one");
+        assert_eq!(buffer.pending(), 2);
+        assert_eq!(buffer.drain().len(), 2);
+    }
+
+    #[test]
+    fn test_logs_without_trace_or_with_span_pass_through() {
+        let mut buffer = CorrelationBuffer::new(Duration::from_secs(60), 10);
+        let now = Instant::now();
+
+        let untraced = LogEntry::new(
+            LogLevel::Info,
+            "plain".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        assert_eq!(buffer.add_log(untraced, now).len(), 1);
+
+        let mut with_span = log("trace-1", "has span");
+        with_span.span_id = Some("span-0".to_string());
+        assert_eq!(buffer.add_log(with_span, now)[0].span_id.as_deref(), Some("span-0"));
+        assert_eq!(buffer.pending(), 0);
+    }
+}
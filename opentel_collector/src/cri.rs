@@ -0,0 +1,201 @@
+//! Decoding of Kubernetes CRI container log files
+
+use crate::log_parser::within_skew;
+use crate::telemetry::{current_timestamp, LogEntry};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Upper bound on a reassembled line; longer runs of partial lines are
+/// released in pieces
+const MAX_CRI_LINE_BYTES: usize = 1024 * 1024;
+
+/// One logical line from a CRI log file, with partial lines reassembled
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriRecord {
+    /// Time the runtime received the line, in Unix seconds
+    pub timestamp: Option<u64>,
+    /// `stdout` or `stderr`; `None` for lines not in CRI format
+    pub stream: Option<String>,
+    pub message: String,
+}
+
+impl CriRecord {
+    /// Add the stream attribute and fall back to the runtime timestamp when
+    /// the application did not log a usable one of its own. The runtime
+    /// timestamp is held to the same `max_skew` as logged ones.
+    pub fn apply_to(&self, log_entry: &mut LogEntry, max_skew: Duration) {
+        if let Some(stream) = &self.stream {
+            log_entry.attributes.insert("stream".to_string(), stream.clone());
+        }
+
+        let logged_timestamp = log_entry
+            .attributes
+            .get("timestamp.source")
+            .is_some_and(|source| source == "log");
+
+        if let Some(timestamp) = self.timestamp
+            && !logged_timestamp
+            && within_skew(timestamp, current_timestamp(), max_skew)
+        {
+            log_entry.timestamp = timestamp;
+            log_entry.attributes.insert("timestamp.source".to_string(), "runtime".to_string());
+        }
+    }
+}
+
+/// Strips the `<timestamp> <stream> <tag> ` prefix from CRI log lines and
+/// joins partial (`P`) lines with the full (`F`) line that ends them. Partial
+/// lines are tracked per stream since stdout and stderr interleave.
+#[derive(Debug, Clone, Default)]
+pub struct CriDecoder {
+    partial: HashMap<String, CriRecord>,
+}
+
+impl CriDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode one line, returning a record once a logical line is complete.
+    /// Lines that are not in CRI format are passed through unchanged.
+    pub fn decode(&mut self, line: &str) -> Option<CriRecord> {
+        let Some((timestamp, stream, tag, message)) = split_cri_line(line) else {
+            return Some(CriRecord {
+                timestamp: None,
+                stream: None,
+                message: line.to_string(),
+            });
+        };
+
+        let partial = tag.split(':').next() == Some("P");
+        let record = match self.partial.remove(stream) {
+            Some(mut pending) => {
+                pending.message.push_str(message);
+                pending
+            }
+            None => CriRecord {
+                timestamp: chrono::DateTime::parse_from_rfc3339(timestamp)
+                    .ok()
+                    .map(|dt| dt.timestamp() as u64),
+                stream: Some(stream.to_string()),
+                message: message.to_string(),
+            },
+        };
+
+        if partial && record.message.len() < MAX_CRI_LINE_BYTES {
+            self.partial.insert(stream.to_string(), record);
+            None
+        } else {
+            Some(record)
+        }
+    }
+
+    /// Release partial lines that never saw their final piece, e.g. when the
+    /// file is rotated mid-line
+    pub fn flush(&mut self) -> Vec<CriRecord> {
+        self.partial.drain().map(|(_, record)| record).collect()
+    }
+}
+
+/// Split a CRI line into timestamp, stream, tag and message
+fn split_cri_line(line: &str) -> Option<(&str, &str, &str, &str)> {
+    let mut parts = line.splitn(4, ' ');
+    let timestamp = parts.next()?;
+    let stream = parts.next()?;
+    let tag = parts.next()?;
+    // A full line may carry an empty message
+    let message = parts.next().unwrap_or("");
+
+    if !matches!(stream, "stdout" | "stderr") || !matches!(tag.split(':').next(), Some("P" | "F")) {
+        return None;
+    }
+
+    Some((timestamp, stream, tag, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::LogLevel;
+
+    #[test]
+    fn test_full_line() {
+        let mut decoder = CriDecoder::new();
+        let record = decoder
+            .decode("2023-12-01T10:30:45.123456789Z stdout F INFO: request handled")
+            .unwrap();
+
+        assert_eq!(record.message, "INFO: request handled");
+        assert_eq!(record.stream.as_deref(), Some("stdout"));
+        assert_eq!(record.timestamp, Some(1701426645));
+    }
+
+    #[test]
+    fn test_partial_lines_reassembled() {
+        let mut decoder = CriDecoder::new();
+
+        assert_eq!(decoder.decode("2023-12-01T10:30:45.123Z stdout P {\"level\": \"INFO\", "), None);
+        assert_eq!(decoder.decode("2023-12-01T10:30:45.124Z stderr F ERROR: unrelated").unwrap().message, "ERROR: unrelated");
+        assert_eq!(decoder.decode("2023-12-01T10:30:45.125Z stdout P \"message\": "), None);
+
+        let record = decoder.decode("2023-12-01T10:30:45.126Z stdout F \"joined\"}").unwrap();
+        assert_eq!(record.message, r#"{"level": "INFO", "message": "joined"}"#);
+        assert_eq!(record.stream.as_deref(), Some("stdout"));
+        assert!(decoder.flush().is_empty());
+    }
+
+    #[test]
+    fn test_non_cri_lines_pass_through() {
+        let mut decoder = CriDecoder::new();
+        let record = decoder.decode("WARN: not from the runtime").unwrap();
+
+        assert_eq!(record.message, "WARN: not from the runtime");
+        assert_eq!(record.stream, None);
+        assert_eq!(record.timestamp, None);
+    }
+
+    #[test]
+    fn test_flush_unfinished_partial() {
+        let mut decoder = CriDecoder::new();
+        assert_eq!(decoder.decode("2023-12-01T10:30:45.123Z stdout P cut off"), None);
+
+        let flushed = decoder.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].message, "cut off");
+    }
+
+    #[test]
+    fn test_apply_runtime_timestamp() {
+        let record = CriRecord {
+            timestamp: Some(1701426645),
+            stream: Some("stderr".to_string()),
+            message: "oops".to_string(),
+        };
+
+        let mut log_entry = LogEntry::new(
+            LogLevel::Error,
+            "oops".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        )
+        .with_attribute("timestamp.source".to_string(), "ingest".to_string());
+        record.apply_to(&mut log_entry, Duration::ZERO);
+
+        assert_eq!(log_entry.timestamp, 1701426645);
+        assert_eq!(log_entry.attributes["stream"], "stderr");
+        assert_eq!(log_entry.attributes["timestamp.source"], "runtime");
+
+        // The application's own timestamp wins
+        log_entry.timestamp = 1700000000;
+        log_entry.attributes.insert("timestamp.source".to_string(), "log".to_string());
+        record.apply_to(&mut log_entry, Duration::ZERO);
+        assert_eq!(log_entry.timestamp, 1700000000);
+
+        // A runtime timestamp outside the skew limit is not used either
+        log_entry.attributes.insert("timestamp.source".to_string(), "ingest".to_string());
+        record.apply_to(&mut log_entry, Duration::from_secs(3600));
+        assert_eq!(log_entry.timestamp, 1700000000);
+        assert_eq!(log_entry.attributes["timestamp.source"], "ingest");
+    }
+}
@@ -0,0 +1,128 @@
+//! Dead-letter files: batches that could not be delivered, one JSON batch
+//! per line, kept for controlled replay
+
+use crate::errors::Result;
+use crate::files;
+use crate::telemetry::TelemetryBatch;
+use serde::Serialize;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// Result of replaying one batch from a dead-letter file
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchOutcome {
+    /// Line of the batch in the dead-letter file, counting from 1
+    pub line: usize,
+    /// Id of the batch; `None` when the line is not a valid batch
+    pub batch_id: Option<String>,
+    /// Why the batch was not delivered; `None` when the gateway accepted it
+    pub error: Option<String>,
+}
+
+impl BatchOutcome {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Per-batch results of replaying a dead-letter file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeadLetterReport {
+    pub batches: Vec<BatchOutcome>,
+    /// Whether the file was deleted because every batch was delivered
+    pub file_deleted: bool,
+}
+
+impl DeadLetterReport {
+    /// Batches accepted by the gateway
+    pub fn sent(&self) -> usize {
+        self.batches.iter().filter(|outcome| outcome.is_success()).count()
+    }
+
+    /// Batches that were rejected, failed every retry or could not be parsed
+    pub fn failed(&self) -> usize {
+        self.batches.len() - self.sent()
+    }
+}
+
+/// Append `batch` to the dead-letter file at `path`, creating it if needed.
+/// The line is synced before returning, so a batch is not lost to a crash
+/// once it has been dropped from memory.
+pub fn append(path: &Path, batch: &TelemetryBatch) -> Result<()> {
+    let mut line = serde_json::to_vec(batch)?;
+    line.push(b'\n');
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&line)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Non-blank lines of a dead-letter file with their line numbers
+pub fn read_lines(path: &Path) -> Result<Vec<(usize, String)>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index + 1, line.to_string()))
+        .collect())
+}
+
+/// Replace the dead-letter file at `path` with only `lines`, deleting it
+/// when none are left. An interrupted rewrite keeps every batch, see
+/// [`files::replace`].
+pub fn retain(path: &Path, lines: &[String]) -> Result<()> {
+    if lines.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    files::replace(path, content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_and_retain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("failed.ndjson");
+        std::fs::write(&path, "{\"a\":1}\n\n{\"b\":2}\n{\"c\":3}\n").unwrap();
+
+        let lines = read_lines(&path).unwrap();
+        let numbers: Vec<_> = lines.iter().map(|(number, _)| *number).collect();
+        assert_eq!(numbers, vec![1, 3, 4]);
+
+        retain(&path, &[lines[1].1.clone()]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"b\":2}\n");
+
+        retain(&path, &[]).unwrap();
+        assert!(!path.exists());
+        // Already gone is not an error
+        retain(&path, &[]).unwrap();
+    }
+
+    #[test]
+    fn test_append_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("failed.ndjson");
+        let batch = || TelemetryBatch::new(Vec::new(), Vec::new(), "collector-1".to_string(), "pod".to_string(), "ns".to_string());
+        let (first, second) = (batch(), batch());
+
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+
+        let ids: Vec<_> = read_lines(&path)
+            .unwrap()
+            .into_iter()
+            .map(|(_, line)| serde_json::from_str::<TelemetryBatch>(&line).unwrap().metadata.batch_id)
+            .collect();
+        assert_eq!(ids, vec![first.metadata.batch_id, second.metadata.batch_id]);
+    }
+}
@@ -27,6 +27,12 @@ pub enum CollectorError {
     /// Transport error
     Transport(String),
 
+    /// Batch was dropped because it waited too long to be sent
+    BatchExpired(String),
+
+    /// Gateway answered without confirming the batch in its delivery receipt
+    Unconfirmed(String),
+
     /// Generic error with message
     Other(String),
 }
@@ -41,11 +47,31 @@ impl fmt::Display for CollectorError {
             CollectorError::LogParse(msg) => write!(f, "Log parsing error: {}", msg),
             CollectorError::BufferOverflow => write!(f, "Buffer overflow"),
             CollectorError::Transport(msg) => write!(f, "Transport error: {}", msg),
+            CollectorError::BatchExpired(msg) => write!(f, "Batch expired: {}", msg),
+            CollectorError::Unconfirmed(msg) => write!(f, "Batch not confirmed: {}", msg),
             CollectorError::Other(msg) => write!(f, "Error: {}", msg),
         }
     }
 }
 
+impl CollectorError {
+    /// Short name of the error kind, used to count errors by category
+    pub fn category(&self) -> &'static str {
+        match self {
+            CollectorError::Io(_) => "io",
+            CollectorError::Http(_) => "http",
+            CollectorError::Json(_) => "json",
+            CollectorError::Config(_) => "config",
+            CollectorError::LogParse(_) => "log_parse",
+            CollectorError::BufferOverflow => "buffer_overflow",
+            CollectorError::Transport(_) => "transport",
+            CollectorError::BatchExpired(_) => "batch_expired",
+            CollectorError::Unconfirmed(_) => "unconfirmed",
+            CollectorError::Other(_) => "other",
+        }
+    }
+}
+
 impl std::error::Error for CollectorError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -0,0 +1,280 @@
+//! File transport writing batches to a local JSONL file, for air-gapped
+//! clusters, local debugging and tests
+
+use crate::config::Config;
+use crate::errors::{CollectorError, Result};
+use crate::telemetry::TelemetryBatch;
+use crate::transport::{PayloadSize, Transport};
+use async_trait::async_trait;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// The file being appended to
+#[derive(Debug)]
+struct ActiveFile {
+    file: File,
+    size: u64,
+    opened_at: Instant,
+}
+
+/// Appends each batch as one JSON line to a file. The file is rotated to
+/// `<path>.1`, `<path>.2`, ... once it would grow past `max_bytes` or has
+/// been open for `max_age`, and only the newest `max_files` rotated files
+/// are kept. A zero limit disables that rotation or cap.
+#[derive(Debug)]
+pub struct FileTransport {
+    path: PathBuf,
+    max_bytes: u64,
+    max_age: Duration,
+    max_files: usize,
+    active: Mutex<Option<ActiveFile>>,
+}
+
+impl FileTransport {
+    /// Create a file transport appending to `path`; the file is opened on
+    /// first use
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: 0,
+            max_age: Duration::ZERO,
+            max_files: 0,
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Create a file transport from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let path = config.file_sink_path.as_deref().ok_or_else(|| {
+            CollectorError::Config("file_sink_path is required for the file transport".to_string())
+        })?;
+
+        Ok(Self::new(path)
+            .with_rotation(config.file_sink_max_bytes, config.file_sink_max_age)
+            .with_max_files(config.file_sink_max_files))
+    }
+
+    /// Rotate the file once it would exceed `max_bytes` or has been open for
+    /// `max_age`
+    pub fn with_rotation(mut self, max_bytes: u64, max_age: Duration) -> Self {
+        self.max_bytes = max_bytes;
+        self.max_age = max_age;
+        self
+    }
+
+    /// Keep at most `max_files` rotated files, deleting the oldest
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Append one line, rotating first when it would break a limit. Writes
+    /// are small and local, so plain blocking I/O is used under the lock.
+    async fn append(&self, line: &[u8]) -> Result<()> {
+        let mut active = self.active.lock().await;
+
+        let due = active.as_ref().is_some_and(|current| {
+            let too_big = self.max_bytes > 0 && current.size > 0 && current.size + line.len() as u64 > self.max_bytes;
+            let too_old = !self.max_age.is_zero() && current.opened_at.elapsed() >= self.max_age;
+            too_big || too_old
+        });
+        if due {
+            *active = None;
+            self.rotate()?;
+        }
+
+        if active.is_none() {
+            *active = Some(self.open()?);
+        }
+        let current = active.as_mut().expect("file opened above");
+        current.file.write_all(line)?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn open(&self) -> Result<ActiveFile> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        Ok(ActiveFile {
+            size: file.metadata()?.len(),
+            file,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1`, move the current file to
+    /// `<path>.1` and delete rotated files past `max_files`
+    fn rotate(&self) -> Result<()> {
+        let mut rotated = self.rotated_files()?;
+        rotated.sort_unstable_by_key(|(index, _)| std::cmp::Reverse(*index));
+
+        for (index, path) in rotated {
+            if self.max_files > 0 && index >= self.max_files {
+                std::fs::remove_file(&path)?;
+            } else {
+                std::fs::rename(&path, self.rotated_path(index + 1))?;
+            }
+        }
+
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+        info!("Rotated file sink {}", self.path.display());
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        rotated_path(&self.path, index)
+    }
+
+    /// Existing rotated files with their index
+    fn rotated_files(&self) -> Result<Vec<(usize, PathBuf)>> {
+        let Some(file_name) = self.path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            return Ok(Vec::new());
+        };
+        let dir = match self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            Some(parent) => parent.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+
+        let mut rotated = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(index) = name
+                .strip_prefix(file_name.as_str())
+                .and_then(|suffix| suffix.strip_prefix('.'))
+                .and_then(|index| index.parse::<usize>().ok())
+            {
+                rotated.push((index, entry.path()));
+            }
+        }
+        Ok(rotated)
+    }
+}
+
+#[async_trait]
+impl Transport for FileTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let mut line = serde_json::to_vec(&batch)?;
+        line.push(b'\n');
+        self.append(&line).await?;
+        Ok(PayloadSize::uncompressed(line.len()))
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        let mut active = self.active.lock().await;
+        if active.is_some() {
+            return true;
+        }
+
+        match self.open() {
+            Ok(file) => {
+                *active = Some(file);
+                true
+            }
+            Err(e) => {
+                warn!("Cannot open file sink {}: {}", self.path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+/// Sends through a primary transport while also writing every batch to a
+/// file. Only the primary decides whether a send succeeded; a failed file
+/// write is logged and otherwise ignored.
+#[derive(Debug)]
+pub struct MirroredTransport<T> {
+    primary: T,
+    mirror: FileTransport,
+}
+
+impl<T: Transport> MirroredTransport<T> {
+    pub fn new(primary: T, mirror: FileTransport) -> Self {
+        Self { primary, mirror }
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for MirroredTransport<T> {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        if let Err(e) = self.mirror.send_batch(batch.clone()).await {
+            warn!("Failed to mirror batch {} to {}: {}", batch.metadata.batch_id, self.mirror.path.display(), e);
+        }
+        self.primary.send_batch(batch).await
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        self.mirror.test_connectivity().await;
+        self.primary.test_connectivity().await
+    }
+}
+
+/// Path of the `index`th newest rotated file, e.g. `telemetry.jsonl.1`
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(message: &str) -> TelemetryBatch {
+        let log = crate::telemetry::LogEntry::new(
+            crate::telemetry::LogLevel::Info,
+            message.to_string(),
+            "svc".to_string(),
+            "pod".to_string(),
+            "ns".to_string(),
+        );
+        TelemetryBatch::new(vec![log], Vec::new(), "collector".to_string(), "pod".to_string(), "ns".to_string())
+    }
+
+    fn messages(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<TelemetryBatch>(line).unwrap().logs[0].message.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_size_rotation_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.jsonl");
+        let line_len = serde_json::to_vec(&batch("m0")).unwrap().len() as u64 + 1;
+
+        // Two lines per file, two rotated files kept
+        let transport = FileTransport::new(&path).with_rotation(line_len * 2, Duration::ZERO).with_max_files(2);
+        for i in 0..7 {
+            transport.send_batch(batch(&format!("m{}", i))).await.unwrap();
+        }
+
+        assert_eq!(messages(&path), vec!["m6"]);
+        assert_eq!(messages(&rotated_path(&path, 1)), vec!["m4", "m5"]);
+        assert_eq!(messages(&rotated_path(&path, 2)), vec!["m2", "m3"]);
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[tokio::test]
+    async fn test_time_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.jsonl");
+        let transport = FileTransport::new(&path).with_rotation(0, Duration::from_millis(50));
+
+        transport.send_batch(batch("before")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        transport.send_batch(batch("after")).await.unwrap();
+
+        assert_eq!(messages(&rotated_path(&path, 1)), vec!["before"]);
+        assert_eq!(messages(&path), vec!["after"]);
+    }
+}
@@ -0,0 +1,95 @@
+//! Crash-safe writes of the collector's own files: checkpoints, offsets,
+//! state and dead letters
+
+use crate::errors::{CollectorError, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temporary files written at the same time in one process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Replace the file at `path` with `contents`. The contents are written to a
+/// uniquely named file next to it, synced, renamed over `path` and the
+/// directory synced, so after a crash or power loss the file holds either
+/// the old or the new contents in full.
+pub fn replace(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp_path = temp_path(path)?;
+    let written = write_synced(&temp_path, contents).and_then(|()| std::fs::rename(&temp_path, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    sync_dir(path)
+}
+
+/// Run blocking file I/O on the blocking thread pool, so async callers do
+/// not stall the runtime
+pub async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|e| Err(CollectorError::Io(std::io::Error::other(e))))
+}
+
+/// `.<name>.<pid>-<n>.tmp` next to `path`, unique to this write even for
+/// paths that share a stem
+fn temp_path(path: &Path) -> Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        CollectorError::Io(std::io::Error::other(format!("{} is not a file path", path.display())))
+    })?;
+    let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_name = format!(".{}.{}-{}.tmp", name.to_string_lossy(), std::process::id(), unique);
+    Ok(path.with_file_name(temp_name))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Sync the directory holding `path`, making a rename into it durable
+fn sync_dir(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_leaves_no_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("buffer.checkpoint");
+        let offsets = dir.path().join("buffer.offsets");
+
+        replace(&checkpoint, b"old").unwrap();
+        replace(&checkpoint, b"new").unwrap();
+        replace(&offsets, b"{}").unwrap();
+
+        assert_eq!(std::fs::read(&checkpoint).unwrap(), b"new");
+        assert_eq!(std::fs::read(&offsets).unwrap(), b"{}");
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["buffer.checkpoint", "buffer.offsets"]);
+    }
+
+    #[test]
+    fn test_temp_paths_unique() {
+        let path = Path::new("/var/lib/collector/buffer.checkpoint");
+        let first = temp_path(path).unwrap();
+        assert_eq!(first.parent(), path.parent());
+        assert_ne!(first, temp_path(path).unwrap());
+    }
+}
@@ -0,0 +1,96 @@
+//! Pattern-based filtering of raw log lines before parsing
+
+use crate::config::{Config, FilterAction};
+use crate::errors::{CollectorError, Result};
+use regex::Regex;
+
+/// Drops raw log lines matching configured patterns before they are parsed.
+/// Filters are checked in order and the first match decides; unmatched lines
+/// are kept unless keep filters are configured, which turns the filters into
+/// an allowlist.
+#[derive(Debug, Clone, Default)]
+pub struct LineFilter {
+    filters: Vec<(FilterAction, Regex)>,
+    allowlist: bool,
+}
+
+impl LineFilter {
+    /// Build a line filter from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let filters = config
+            .line_filters
+            .iter()
+            .map(|filter| {
+                let pattern = Regex::new(&filter.pattern).map_err(|e| {
+                    CollectorError::Config(format!("invalid line filter pattern '{}': {}", filter.pattern, e))
+                })?;
+                Ok((filter.action, pattern))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let allowlist = filters.iter().any(|(action, _)| *action == FilterAction::Keep);
+        Ok(Self { filters, allowlist })
+    }
+
+    /// Whether any filters are configured
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Decide whether a raw line should be parsed and shipped
+    pub fn should_keep(&self, line: &str) -> bool {
+        match self.filters.iter().find(|(_, pattern)| pattern.is_match(line)) {
+            Some((action, _)) => *action == FilterAction::Keep,
+            None => !self.allowlist,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LineFilterRule;
+
+    fn filter(rules: &[&str]) -> LineFilter {
+        let config = Config {
+            line_filters: rules.iter().map(|rule| rule.parse::<LineFilterRule>().unwrap()).collect(),
+            ..Config::default()
+        };
+        LineFilter::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_drop_filters() {
+        let filter = filter(&["drop:^DEBUG \\[netty\\]", "drop:healthz"]);
+
+        assert!(!filter.should_keep("DEBUG [netty] channel registered"));
+        assert!(!filter.should_keep(r#"{"path": "/healthz"}"#));
+        assert!(filter.should_keep("DEBUG [app] cart updated"));
+    }
+
+    #[test]
+    fn test_first_match_wins_and_keep_filters_allowlist() {
+        let filter = filter(&["drop:healthz", "keep:ERROR|WARN"]);
+
+        assert!(!filter.should_keep("ERROR healthz probe failed"));
+        assert!(filter.should_keep("ERROR payment declined"));
+        assert!(!filter.should_keep("INFO cart updated"));
+    }
+
+    #[test]
+    fn test_no_filters_keep_everything() {
+        let filter = filter(&[]);
+        assert!(filter.is_empty());
+        assert!(filter.should_keep("anything"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let config = Config {
+            line_filters: vec!["drop:(".parse().unwrap()],
+            ..Config::default()
+        };
+        assert!(matches!(LineFilter::from_config(&config), Err(CollectorError::Config(_))));
+        assert!("discard:x".parse::<LineFilterRule>().is_err());
+    }
+}
@@ -0,0 +1,651 @@
+//! OTLP/gRPC transport exporting batches to an OpenTelemetry collector
+//! (requires the `grpc` feature)
+
+use crate::config::Config;
+use crate::errors::{CollectorError, Result};
+use crate::otlp;
+use crate::telemetry::{LogEntry, MetricPoint, TelemetryBatch, TraceSpan};
+use crate::transport::{AttemptError, PayloadSize, RetryPolicy, Transport};
+use async_trait::async_trait;
+use prost::Message;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+/// gRPC method exporting logs
+pub const LOGS_EXPORT_PATH: &str = "/opentelemetry.proto.collector.logs.v1.LogsService/Export";
+/// gRPC method exporting traces
+pub const TRACES_EXPORT_PATH: &str = "/opentelemetry.proto.collector.trace.v1.TraceService/Export";
+/// gRPC method exporting metrics
+pub const METRICS_EXPORT_PATH: &str = "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export";
+
+/// The subset of the OTLP protobuf messages the collector sends, with the
+/// field numbers of `opentelemetry-proto`
+pub mod proto {
+    /// `opentelemetry.proto.common.v1.AnyValue` holding a string
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AnyValue {
+        #[prost(oneof = "any_value::Value", tags = "1")]
+        pub value: Option<any_value::Value>,
+    }
+
+    pub mod any_value {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(string, tag = "1")]
+            StringValue(String),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct KeyValue {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<AnyValue>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InstrumentationScope {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub version: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Resource {
+        #[prost(message, repeated, tag = "1")]
+        pub attributes: Vec<KeyValue>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportLogsServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_logs: Vec<ResourceLogs>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportLogsServiceResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceLogs {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_logs: Vec<ScopeLogs>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeLogs {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub log_records: Vec<LogRecord>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct LogRecord {
+        #[prost(fixed64, tag = "1")]
+        pub time_unix_nano: u64,
+        #[prost(int32, tag = "2")]
+        pub severity_number: i32,
+        #[prost(string, tag = "3")]
+        pub severity_text: String,
+        #[prost(message, optional, tag = "5")]
+        pub body: Option<AnyValue>,
+        #[prost(message, repeated, tag = "6")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(bytes = "vec", tag = "9")]
+        pub trace_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "10")]
+        pub span_id: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportTraceServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_spans: Vec<ResourceSpans>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportTraceServiceResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceSpans {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_spans: Vec<ScopeSpans>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeSpans {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub spans: Vec<Span>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Span {
+        #[prost(bytes = "vec", tag = "1")]
+        pub trace_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub span_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "4")]
+        pub parent_span_id: Vec<u8>,
+        #[prost(string, tag = "5")]
+        pub name: String,
+        #[prost(fixed64, tag = "7")]
+        pub start_time_unix_nano: u64,
+        #[prost(fixed64, tag = "8")]
+        pub end_time_unix_nano: u64,
+        #[prost(message, repeated, tag = "9")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(message, optional, tag = "15")]
+        pub status: Option<Status>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Status {
+        #[prost(string, tag = "2")]
+        pub message: String,
+        #[prost(int32, tag = "3")]
+        pub code: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_metrics: Vec<ResourceMetrics>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceMetrics {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_metrics: Vec<ScopeMetrics>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeMetrics {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub metrics: Vec<Metric>,
+    }
+
+    /// `Metric` carrying a gauge, the only kind the collector produces
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Metric {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(message, optional, tag = "5")]
+        pub gauge: Option<Gauge>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Gauge {
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<NumberDataPoint>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NumberDataPoint {
+        #[prost(fixed64, tag = "3")]
+        pub time_unix_nano: u64,
+        #[prost(oneof = "number_data_point::Value", tags = "4")]
+        pub value: Option<number_data_point::Value>,
+        #[prost(message, repeated, tag = "7")]
+        pub attributes: Vec<KeyValue>,
+    }
+
+    pub mod number_data_point {
+        /// Set even when zero, unlike a plain field
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(double, tag = "4")]
+            AsDouble(f64),
+        }
+    }
+}
+
+/// `ExportLogsServiceRequest` with one resource per service
+pub fn logs_request(batch: &TelemetryBatch) -> proto::ExportLogsServiceRequest {
+    let resource_logs = otlp::group_by_service(&batch.logs, |log| &log.service_name)
+        .into_iter()
+        .map(|(service_name, logs)| proto::ResourceLogs {
+            resource: Some(resource(batch, service_name)),
+            scope_logs: vec![proto::ScopeLogs {
+                scope: Some(scope()),
+                log_records: logs.into_iter().map(log_record).collect(),
+            }],
+        })
+        .collect();
+
+    proto::ExportLogsServiceRequest { resource_logs }
+}
+
+/// `ExportTraceServiceRequest` with one resource per service
+pub fn traces_request(batch: &TelemetryBatch) -> proto::ExportTraceServiceRequest {
+    let resource_spans = otlp::group_by_service(&batch.spans, |span| &span.service_name)
+        .into_iter()
+        .map(|(service_name, spans)| proto::ResourceSpans {
+            resource: Some(resource(batch, service_name)),
+            scope_spans: vec![proto::ScopeSpans {
+                scope: Some(scope()),
+                spans: spans.into_iter().map(span).collect(),
+            }],
+        })
+        .collect();
+
+    proto::ExportTraceServiceRequest { resource_spans }
+}
+
+/// `ExportMetricsServiceRequest` with every point as a gauge
+pub fn metrics_request(batch: &TelemetryBatch) -> proto::ExportMetricsServiceRequest {
+    let resource_metrics = otlp::group_by_service(&batch.metrics, |point| &point.service_name)
+        .into_iter()
+        .map(|(service_name, points)| proto::ResourceMetrics {
+            resource: Some(resource(batch, service_name)),
+            scope_metrics: vec![proto::ScopeMetrics {
+                scope: Some(scope()),
+                metrics: points.into_iter().map(gauge).collect(),
+            }],
+        })
+        .collect();
+
+    proto::ExportMetricsServiceRequest { resource_metrics }
+}
+
+fn log_record(log: &LogEntry) -> proto::LogRecord {
+    proto::LogRecord {
+        time_unix_nano: otlp::unix_nanos(log.timestamp),
+        severity_number: otlp::severity_number(log) as i32,
+        severity_text: log.level.to_string(),
+        body: Some(string_value(&log.message)),
+        attributes: attributes(&log.attributes),
+        trace_id: log.trace_id.as_deref().map(hex_bytes).unwrap_or_default(),
+        span_id: log.span_id.as_deref().map(hex_bytes).unwrap_or_default(),
+    }
+}
+
+fn span(span: &TraceSpan) -> proto::Span {
+    let (start, end) = otlp::span_times(span);
+
+    proto::Span {
+        trace_id: hex_bytes(&span.trace_id),
+        span_id: hex_bytes(&span.span_id),
+        parent_span_id: span.parent_span_id.as_deref().map(hex_bytes).unwrap_or_default(),
+        name: span.operation_name.clone(),
+        start_time_unix_nano: start,
+        end_time_unix_nano: end,
+        attributes: attributes(&span.tags),
+        status: Some(proto::Status {
+            message: span.status.to_string(),
+            code: otlp::status_code(&span.status),
+        }),
+    }
+}
+
+fn gauge(point: &MetricPoint) -> proto::Metric {
+    proto::Metric {
+        name: point.name.clone(),
+        gauge: Some(proto::Gauge {
+            data_points: vec![proto::NumberDataPoint {
+                time_unix_nano: otlp::unix_nanos(point.timestamp),
+                value: Some(proto::number_data_point::Value::AsDouble(point.value)),
+                attributes: attributes(&point.attributes),
+            }],
+        }),
+    }
+}
+
+fn resource(batch: &TelemetryBatch, service_name: &str) -> proto::Resource {
+    proto::Resource {
+        attributes: attributes(&otlp::resource_attributes(batch, service_name)),
+    }
+}
+
+fn scope() -> proto::InstrumentationScope {
+    proto::InstrumentationScope {
+        name: "opentel_collector".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+fn string_value(value: &str) -> proto::AnyValue {
+    proto::AnyValue {
+        value: Some(proto::any_value::Value::StringValue(value.to_string())),
+    }
+}
+
+/// OTLP `KeyValue` list, sorted by key so encoding is deterministic
+fn attributes(attributes: &HashMap<String, String>) -> Vec<proto::KeyValue> {
+    let sorted: BTreeMap<_, _> = attributes.iter().collect();
+    sorted
+        .into_iter()
+        .map(|(key, value)| proto::KeyValue {
+            key: key.clone(),
+            value: Some(string_value(value)),
+        })
+        .collect()
+}
+
+/// Bytes of a hex trace or span id; ids that are not hex are sent empty
+fn hex_bytes(id: &str) -> Vec<u8> {
+    if !id.len().is_multiple_of(2) {
+        return Vec::new();
+    }
+    (0..id.len())
+        .step_by(2)
+        .map(|i| id.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .unwrap_or_default()
+}
+
+/// Whether an export failing with `code` is worth retrying, following the
+/// OTLP specification; other codes mean the data will never be accepted
+pub fn is_retryable(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Cancelled
+            | Code::DeadlineExceeded
+            | Code::ResourceExhausted
+            | Code::Aborted
+            | Code::OutOfRange
+            | Code::Unavailable
+            | Code::DataLoss
+    )
+}
+
+/// Exports each batch to an OpenTelemetry collector's OTLP/gRPC services,
+/// one export call per signal with data. The channel connects on first use,
+/// over TLS for `https://` endpoints. Retryable status codes are retried
+/// like HTTP sends, and other codes fail the batch at once.
+#[derive(Debug)]
+pub struct GrpcTransport {
+    endpoint: Endpoint,
+    max_retries: u32,
+    retry_backoff_ms: u64,
+    max_batch_send_age: Duration,
+    channel: OnceLock<Channel>,
+}
+
+impl GrpcTransport {
+    /// Create a gRPC transport for a `http://host:port` or `https://host:port`
+    /// endpoint; nothing is connected until the first send. TLS endpoints
+    /// are verified against the webpki root certificates.
+    pub fn new(endpoint: &str, request_timeout: Duration, max_retries: u32, retry_backoff_ms: u64) -> Result<Self> {
+        let invalid = |e| CollectorError::Config(format!("invalid gRPC endpoint '{}': {}", endpoint, e));
+        let tls = endpoint.starts_with("https://");
+        let mut endpoint = Endpoint::from_shared(endpoint.to_string())
+            .map_err(invalid)?
+            .timeout(request_timeout)
+            .connect_timeout(request_timeout);
+        if tls {
+            endpoint = endpoint
+                .tls_config(ClientTlsConfig::new().with_webpki_roots())
+                .map_err(invalid)?;
+        }
+
+        Ok(Self {
+            endpoint,
+            max_retries,
+            retry_backoff_ms,
+            max_batch_send_age: Duration::ZERO,
+            channel: OnceLock::new(),
+        })
+    }
+
+    /// Create a gRPC transport from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let transport = Self::new(
+            &config.otlp_grpc_endpoint,
+            config.request_timeout,
+            config.max_retries,
+            config.retry_backoff_ms,
+        )?
+        .with_max_batch_send_age(config.max_batch_send_age);
+        let transport = match &config.otlp_grpc_ca_path {
+            Some(path) => transport.with_ca_certificate(Path::new(path))?,
+            None => transport,
+        };
+
+        Ok(if config.connect_timeout.is_zero() {
+            transport
+        } else {
+            transport.with_connect_timeout(config.connect_timeout)
+        })
+    }
+
+    /// Limit connecting to the collector separately from each export
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.endpoint = self.endpoint.connect_timeout(connect_timeout);
+        self
+    }
+
+    /// Trust the PEM CA certificate at `path` instead of the webpki roots,
+    /// e.g. for a collector with a certificate from a private CA
+    pub fn with_ca_certificate(mut self, path: &Path) -> Result<Self> {
+        let pem = std::fs::read(path)?;
+        let tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(pem));
+        self.endpoint = self
+            .endpoint
+            .tls_config(tls)
+            .map_err(|e| CollectorError::Config(format!("invalid gRPC CA certificate {}: {}", path.display(), e)))?;
+        Ok(self)
+    }
+
+    /// Stop retrying batches drained longer ago than `max_age`; zero retries
+    /// regardless of age
+    pub fn with_max_batch_send_age(mut self, max_age: Duration) -> Self {
+        self.max_batch_send_age = max_age;
+        self
+    }
+
+    /// The shared channel, created on first use so it is made inside the runtime
+    fn channel(&self) -> Channel {
+        self.channel.get_or_init(|| self.endpoint.connect_lazy()).clone()
+    }
+
+    /// Export a telemetry batch, one call per signal with data
+    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let mut sent = 0;
+
+        if !batch.logs.is_empty() {
+            sent += self
+                .export_with_retries::<_, proto::ExportLogsServiceResponse>(&batch, LOGS_EXPORT_PATH, logs_request(&batch))
+                .await?;
+        }
+        if !batch.spans.is_empty() {
+            sent += self
+                .export_with_retries::<_, proto::ExportTraceServiceResponse>(&batch, TRACES_EXPORT_PATH, traces_request(&batch))
+                .await?;
+        }
+        if !batch.metrics.is_empty() {
+            sent += self
+                .export_with_retries::<_, proto::ExportMetricsServiceResponse>(&batch, METRICS_EXPORT_PATH, metrics_request(&batch))
+                .await?;
+        }
+
+        Ok(PayloadSize::uncompressed(sent))
+    }
+
+    /// Export one request for a batch, retrying retryable failures with backoff
+    async fn export_with_retries<M, R>(&self, batch: &TelemetryBatch, path: &'static str, request: M) -> Result<usize>
+    where
+        M: Message + Clone + Send + Sync + 'static,
+        R: Message + Default + Send + Sync + 'static,
+    {
+        let size = request.encoded_len();
+        debug!(
+            "Exporting batch {} to {} ({} bytes)",
+            batch.metadata.batch_id, path, size
+        );
+
+        let retry_policy = RetryPolicy {
+            max_retries: self.max_retries,
+            retry_backoff_ms: self.retry_backoff_ms,
+            max_batch_send_age: self.max_batch_send_age,
+        };
+        let attempts = retry_policy
+            .run(batch, "export", || async {
+                self.export::<M, R>(path, request.clone()).await.map_err(|status| {
+                    let e = status_error(path, &status);
+                    if is_retryable(status.code()) {
+                        AttemptError::Retryable(e)
+                    } else {
+                        AttemptError::Fatal(e)
+                    }
+                })
+            })
+            .await?;
+
+        debug!(
+            "Exported batch {} to {} (attempt {})",
+            batch.metadata.batch_id, path, attempts
+        );
+        Ok(size)
+    }
+
+    /// Single unary export call
+    async fn export<M, R>(&self, path: &'static str, request: M) -> std::result::Result<(), Status>
+    where
+        M: Message + Send + Sync + 'static,
+        R: Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(self.channel());
+        grpc.ready()
+            .await
+            .map_err(|e| Status::unavailable(format!("channel not ready: {}", e)))?;
+
+        let codec = tonic_prost::ProstCodec::<M, R>::default();
+        grpc.unary(tonic::Request::new(request), PathAndQuery::from_static(path), codec)
+            .await
+            .map(|_| ())
+    }
+
+    /// Test connectivity to the collector
+    pub async fn test_connectivity(&self) -> bool {
+        let connect_timeout = Duration::from_secs(10);
+        match timeout(connect_timeout, self.endpoint.connect()).await {
+            Ok(Ok(_)) => {
+                info!("gRPC connectivity test successful: {}", self.endpoint.uri());
+                true
+            }
+            Ok(Err(e)) => {
+                warn!("gRPC connectivity test failed: {}", e);
+                false
+            }
+            Err(_) => {
+                warn!("gRPC connectivity test timed out");
+                false
+            }
+        }
+    }
+}
+
+fn status_error(path: &str, status: &Status) -> CollectorError {
+    CollectorError::Transport(format!(
+        "gRPC export to {} failed with {:?}: {}",
+        path,
+        status.code(),
+        status.message()
+    ))
+}
+
+#[async_trait]
+impl Transport for GrpcTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        GrpcTransport::send_batch(self, batch).await
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        GrpcTransport::test_connectivity(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{LogLevel, SpanStatus};
+
+    #[test]
+    fn test_hex_bytes() {
+        assert_eq!(hex_bytes("00f067aa0ba902b7"), vec![0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]);
+        assert!(hex_bytes("not-hex!").is_empty());
+        assert!(hex_bytes("abc").is_empty());
+    }
+
+    #[test]
+    fn test_requests_encode_batch() {
+        let log = LogEntry::new(
+            LogLevel::Error,
+            "payment failed".to_string(),
+            "checkout".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        )
+        .with_trace_context("4bf92f3577b34da6a3ce929d0e0e4736".to_string(), "00f067aa0ba902b7".to_string());
+        let span = TraceSpan::new(
+            "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            "00f067aa0ba902b7".to_string(),
+            "POST /pay".to_string(),
+            "checkout".to_string(),
+        )
+        .with_status(SpanStatus::Timeout);
+        let batch = TelemetryBatch::new(
+            vec![log],
+            vec![span],
+            "collector-1".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        );
+
+        let logs = logs_request(&batch);
+        let record = &logs.resource_logs[0].scope_logs[0].log_records[0];
+        assert_eq!(record.severity_number, 17);
+        assert_eq!(record.trace_id.len(), 16);
+        assert_eq!(record.span_id.len(), 8);
+        assert!(logs.resource_logs[0].resource.as_ref().unwrap().attributes.iter().any(|kv| kv.key == "service.name"));
+
+        let traces = traces_request(&batch);
+        let encoded = &traces.resource_spans[0].scope_spans[0].spans[0];
+        assert_eq!(encoded.name, "POST /pay");
+        assert_eq!(encoded.status.as_ref().unwrap().code, 2);
+        assert!(encoded.parent_span_id.is_empty());
+
+        // Round trip through the wire format
+        let decoded = proto::ExportTraceServiceRequest::decode(traces.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, traces);
+    }
+
+    #[test]
+    fn test_https_endpoint() {
+        let transport = GrpcTransport::new("https://otel-collector:4317", Duration::from_secs(1), 0, 1).unwrap();
+        assert_eq!(transport.endpoint.uri().scheme_str(), Some("https"));
+
+        let missing_ca = transport.with_ca_certificate(Path::new("/nonexistent/ca.pem"));
+        assert!(missing_ca.is_err());
+    }
+
+    #[test]
+    fn test_retryable_codes() {
+        assert!(is_retryable(Code::Unavailable));
+        assert!(is_retryable(Code::ResourceExhausted));
+        assert!(!is_retryable(Code::InvalidArgument));
+        assert!(!is_retryable(Code::Unauthenticated));
+    }
+}
@@ -5,13 +5,36 @@
 
 pub mod config;
 pub mod collector;
+pub mod correlation;
+pub mod cri;
 pub mod log_parser;
 pub mod telemetry;
 pub mod transport;
 pub mod buffer;
+pub mod checkpoint;
+pub mod dead_letter;
+pub mod file_sink;
+pub mod files;
 pub mod errors;
+pub mod podinfo;
+pub mod sampling;
+pub mod rate_limit;
+pub mod tail_sampling;
+pub mod orphan_spans;
+pub mod filter;
+pub mod otlp;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod span_metrics;
+pub mod recent;
+pub mod recent_errors;
+pub mod self_logs;
+pub mod state;
+pub mod stats_server;
 
 pub use config::Config;
-pub use collector::SidecarCollector;
+pub use collector::{SidecarCollector, ReplayReport};
 pub use telemetry::{LogEntry, TraceSpan, TelemetryBatch, BatchMetadata};
 pub use errors::{CollectorError, Result};
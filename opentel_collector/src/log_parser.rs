@@ -1,11 +1,20 @@
 //! Log parsing utilities for various log formats
 
-use crate::telemetry::{LogEntry, LogLevel, TraceSpan, SpanStatus, generate_trace_id, generate_span_id};
+use crate::config::{AttributeCollisionPolicy, Config, TraceIdPolicy};
+use crate::telemetry::{
+    LogEntry, LogLevel, TraceSpan, SpanStatus, generate_trace_id, generate_span_id,
+    normalize_id, derive_id, TRACE_ID_HEX_LEN, SPAN_ID_HEX_LEN,
+};
 use crate::errors::{CollectorError, Result};
+use chrono_tz::Tz;
 use regex::Regex;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::debug;
 
 /// Trait for parsing log lines into structured telemetry data
 pub trait LogParser: Send + Sync {
@@ -13,47 +22,231 @@ pub trait LogParser: Send + Sync {
     fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>>;
 }
 
+/// Options shared by all log parsers
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    pub trace_correlation: bool,
+    pub default_timezone: Tz,
+    pub max_timestamp_skew: Duration,
+    pub extract_key_values: bool,
+    pub key_value_delimiters: Vec<String>,
+    pub drop_empty_messages: bool,
+    pub max_attributes_per_entry: usize,
+    pub attribute_allowlist: Vec<String>,
+    pub trace_id_policy: TraceIdPolicy,
+    /// Spans discarded by the `drop` trace id policy
+    pub invalid_id_spans_dropped: Arc<AtomicU64>,
+    pub attribute_collision_policy: AttributeCollisionPolicy,
+    /// Field names for array-shaped JSON logs, by position
+    pub json_array_fields: Vec<String>,
+    /// Compiled operation name rewrites and their replacements, in order
+    pub operation_name_rules: Vec<(Regex, String)>,
+    pub max_operation_names: usize,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            trace_correlation: true,
+            default_timezone: Tz::UTC,
+            max_timestamp_skew: Duration::ZERO,
+            extract_key_values: false,
+            key_value_delimiters: vec!["=".to_string(), ":".to_string()],
+            drop_empty_messages: true,
+            max_attributes_per_entry: 0,
+            attribute_allowlist: Vec::new(),
+            trace_id_policy: TraceIdPolicy::Keep,
+            invalid_id_spans_dropped: Arc::new(AtomicU64::new(0)),
+            attribute_collision_policy: AttributeCollisionPolicy::PreferTopLevel,
+            json_array_fields: Vec::new(),
+            operation_name_rules: Vec::new(),
+            max_operation_names: 0,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Build parser options from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let default_timezone = config.default_timezone.parse::<Tz>().map_err(|e| {
+            CollectorError::Config(format!("invalid default_timezone '{}': {}", config.default_timezone, e))
+        })?;
+
+        let operation_name_rules = config
+            .operation_name_rules
+            .iter()
+            .map(|rule| {
+                let pattern = Regex::new(&rule.pattern).map_err(|e| {
+                    CollectorError::Config(format!("invalid operation name pattern '{}': {}", rule.pattern, e))
+                })?;
+                Ok((pattern, rule.replacement.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            trace_correlation: config.enable_trace_correlation,
+            default_timezone,
+            max_timestamp_skew: config.max_timestamp_skew,
+            extract_key_values: config.extract_key_values,
+            key_value_delimiters: config.key_value_delimiters.clone(),
+            drop_empty_messages: config.drop_empty_messages,
+            max_attributes_per_entry: config.max_attributes_per_entry,
+            attribute_allowlist: config.attribute_allowlist.clone(),
+            trace_id_policy: config.invalid_trace_id_policy,
+            invalid_id_spans_dropped: Arc::new(AtomicU64::new(0)),
+            attribute_collision_policy: config.attribute_collision_policy,
+            json_array_fields: config.json_array_fields.clone(),
+            operation_name_rules,
+            max_operation_names: config.max_operation_names,
+        })
+    }
+
+    /// Add a top-level JSON field to attributes that may already hold a value
+    /// from the `attributes` object, following the collision policy
+    fn insert_top_level_attribute(&self, attributes: &mut HashMap<String, String>, field: &str, value: &str) {
+        let collides = attributes.get(field).is_some_and(|nested| nested != value);
+
+        match self.attribute_collision_policy {
+            AttributeCollisionPolicy::PreferNested if collides => return,
+            AttributeCollisionPolicy::KeepBoth if collides => {
+                if let Some(nested) = attributes.remove(field) {
+                    attributes.insert(format!("attributes.{}", field), nested);
+                }
+            }
+            _ => {}
+        }
+
+        attributes.insert(field.to_string(), value.to_string());
+    }
+
+    /// Whether a parsed message should become a log entry under the empty-message policy
+    fn keeps_message(&self, message: &str) -> bool {
+        !self.drop_empty_messages || !is_blank_message(message)
+    }
+
+    /// Set the entry timestamp, falling back to ingest time when the log's own
+    /// timestamp is missing or implausibly far from now
+    fn apply_timestamp(&self, log_entry: &mut LogEntry, parsed: Option<u64>, raw: Option<&str>) {
+        let now = crate::telemetry::current_timestamp();
+
+        match parsed {
+            Some(timestamp) if within_skew(timestamp, now, self.max_timestamp_skew) => {
+                log_entry.timestamp = timestamp;
+                log_entry.attributes.insert("timestamp.source".to_string(), "log".to_string());
+            }
+            _ => {
+                log_entry.timestamp = now;
+                log_entry.attributes.insert("timestamp.source".to_string(), "ingest".to_string());
+                if let Some(raw) = raw {
+                    log_entry.attributes.insert("timestamp.original".to_string(), raw.to_string());
+                }
+            }
+        }
+    }
+}
+
 /// JSON log parser for structured logs
 pub struct JsonLogParser {
-    trace_correlation: bool,
+    config: ParserConfig,
 }
 
 impl JsonLogParser {
     pub fn new(trace_correlation: bool) -> Self {
-        Self { trace_correlation }
+        Self::with_config(ParserConfig {
+            trace_correlation,
+            ..ParserConfig::default()
+        })
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether array-shaped logs are mapped to fields
+    fn maps_arrays(&self) -> bool {
+        !self.config.json_array_fields.is_empty()
+    }
+
+    /// Parse a line as JSON, turning an array into an object using the
+    /// configured positional field names. Elements past the configured names
+    /// are ignored, and missing ones leave their fields unset.
+    fn parse_json(&self, line: &str) -> Result<Value> {
+        let json: Value = serde_json::from_str(line)?;
+
+        let Value::Array(elements) = json else {
+            return Ok(json);
+        };
+        if !self.maps_arrays() {
+            return Ok(Value::Array(elements));
+        }
+
+        let mut object = serde_json::Map::new();
+        for (field, element) in self.config.json_array_fields.iter().zip(elements) {
+            match (field.as_str(), element) {
+                ("_", _) => {}
+                ("*", Value::Object(fields)) => object.extend(fields),
+                ("*", _) => {}
+                (field, element) => {
+                    object.insert(field.to_string(), element);
+                }
+            }
+        }
+        Ok(Value::Object(object))
     }
 }
 
 impl LogParser for JsonLogParser {
     fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
-        let json: Value = serde_json::from_str(line)?;
+        let json = self.parse_json(line)?;
+
+        let raw_timestamp = ["timestamp", "@timestamp", "time"]
+            .iter()
+            .map(|field| &json[*field])
+            .find(|value| !value.is_null());
+        let timestamp = raw_timestamp.and_then(|value| match value {
+            Value::Number(number) => number.as_f64().and_then(epoch_seconds),
+            Value::String(text) => parse_timestamp(text.trim(), &self.config.default_timezone),
+            _ => None,
+        });
+
+        let level_text = json["level"]
+            .as_str()
+            .or_else(|| json["severity"].as_str())
+            .or_else(|| json["log_level"].as_str());
 
-        let timestamp = json["timestamp"]
+        let severity_number = json["severity_number"]
             .as_u64()
-            .or_else(|| json["@timestamp"].as_u64())
-            .or_else(|| json["time"].as_u64())
-            .unwrap_or_else(|| crate::telemetry::current_timestamp());
+            .or_else(|| json["severityNumber"].as_u64())
+            .and_then(|n| u8::try_from(n).ok());
 
-        let level = json["level"]
-            .as_str()
-            .or_else(|| json["severity"].as_str())
-            .or_else(|| json["log_level"].as_str())
-            .unwrap_or("INFO");
+        let level = level_text
+            .map(LogLevel::from)
+            .or_else(|| severity_number.and_then(LogLevel::from_severity_number))
+            .unwrap_or(LogLevel::Info);
 
-        let message = json["message"]
+        // Keep a finer-grained severity number only when it falls within the level's range
+        let severity_number = severity_number
+            .filter(|n| LogLevel::from_severity_number(*n).as_ref() == Some(&level))
+            .unwrap_or_else(|| level.severity_number());
+
+        // Valid JSON without a message is not a log record (it may still be a span)
+        let Some(message) = json["message"]
             .as_str()
             .or_else(|| json["msg"].as_str())
             .or_else(|| json["text"].as_str())
-            .unwrap_or("")
-            .to_string();
+        else {
+            return Ok(None);
+        };
 
-        if message.is_empty() {
+        if !self.config.keeps_message(message) {
             return Ok(None);
         }
+        let message = message.to_string();
 
         let mut log_entry = LogEntry {
-            timestamp,
-            level: LogLevel::from(level),
+            timestamp: 0,
+            level,
+            severity_number,
             message,
             service_name: service_name.to_string(),
             pod_name: pod_name.to_string(),
@@ -63,7 +256,13 @@ impl LogParser for JsonLogParser {
             attributes: HashMap::new(),
         };
 
-        if self.trace_correlation {
+        let raw_timestamp = raw_timestamp.map(|value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+        self.config.apply_timestamp(&mut log_entry, timestamp, raw_timestamp.as_deref());
+
+        if self.config.trace_correlation {
             if let Some(trace_id) = json["trace_id"]
                 .as_str()
                 .or_else(|| json["traceId"].as_str())
@@ -89,7 +288,7 @@ impl LogParser for JsonLogParser {
 
         for field in ["user_id", "request_id", "session_id", "correlation_id"] {
             if let Some(value) = json[field].as_str() {
-                log_entry.attributes.insert(field.to_string(), value.to_string());
+                self.config.insert_top_level_attribute(&mut log_entry.attributes, field, value);
             }
         }
 
@@ -97,7 +296,7 @@ impl LogParser for JsonLogParser {
     }
 
     fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
-        let json: Value = serde_json::from_str(line)?;
+        let json = self.parse_json(line)?;
 
         // Only parse if this looks like a span/trace log
         if !json.get("span_id").is_some() && !json.get("spanId").is_some() {
@@ -128,15 +327,18 @@ impl LogParser for JsonLogParser {
             .or_else(|| json["startTime"].as_u64())
             .unwrap_or_else(|| crate::telemetry::current_timestamp());
 
+        let duration_ms = json["duration_ms"]
+            .as_u64()
+            .or_else(|| json["duration"].as_u64());
+
         let end_time = json["end_time"]
             .as_u64()
             .or_else(|| json["endTime"].as_u64())
-            .unwrap_or(start_time);
+            .unwrap_or_else(|| start_time.saturating_add(duration_ms.unwrap_or(0) / 1000));
 
-        let duration_ms = json["duration_ms"]
-            .as_u64()
-            .or_else(|| json["duration"].as_u64())
-            .unwrap_or_else(|| end_time.saturating_sub(start_time) * 1000);
+        // Swapped times surface as an anomaly when the span is normalized
+        let duration_ms = duration_ms
+            .unwrap_or_else(|| end_time.abs_diff(start_time).saturating_mul(1000));
 
         let status = json["status"]
             .as_str()
@@ -167,14 +369,14 @@ impl LogParser for JsonLogParser {
             }
         }
 
-        Ok(Some(span))
+        Ok(Some(span.normalize_timing()))
     }
 }
 
 /// Regex-based log parser for unstructured logs
 pub struct RegexLogParser {
     patterns: Vec<LogPattern>,
-    trace_correlation: bool,
+    config: ParserConfig,
 }
 
 struct LogPattern {
@@ -188,16 +390,23 @@ struct LogPattern {
 
 impl RegexLogParser {
     pub fn new(trace_correlation: bool) -> Self {
+        Self::with_config(ParserConfig {
+            trace_correlation,
+            ..ParserConfig::default()
+        })
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
         Self {
             patterns: Self::default_patterns(),
-            trace_correlation,
+            config,
         }
     }
 
     pub fn with_custom_patterns(patterns: Vec<LogPattern>) -> Self {
         Self {
             patterns,
-            trace_correlation: true,
+            config: ParserConfig::default(),
         }
     }
 
@@ -272,17 +481,21 @@ impl LogParser for RegexLogParser {
                     continue;
                 }
 
-                let timestamp = if let Some(ts_group) = pattern.timestamp_group {
-                    captures.get(ts_group)
-                        .and_then(|m| parse_timestamp(m.as_str()))
-                        .unwrap_or_else(|| crate::telemetry::current_timestamp())
-                } else {
-                    crate::telemetry::current_timestamp()
-                };
+                if !self.config.keeps_message(&message) {
+                    return Ok(None);
+                }
+
+                let raw_timestamp = pattern.timestamp_group
+                    .and_then(|ts_group| captures.get(ts_group))
+                    .map(|m| m.as_str());
+                let timestamp = raw_timestamp
+                    .and_then(|ts| parse_timestamp(ts, &self.config.default_timezone));
 
+                let level = LogLevel::from(level);
                 let mut log_entry = LogEntry {
-                    timestamp,
-                    level: LogLevel::from(level),
+                    timestamp: 0,
+                    severity_number: level.severity_number(),
+                    level,
                     message,
                     service_name: service_name.to_string(),
                     pod_name: pod_name.to_string(),
@@ -292,8 +505,10 @@ impl LogParser for RegexLogParser {
                     attributes: HashMap::new(),
                 };
 
+                self.config.apply_timestamp(&mut log_entry, timestamp, raw_timestamp);
+
                 // Extract trace context if available and enabled
-                if self.trace_correlation {
+                if self.config.trace_correlation {
                     if let Some(trace_group) = pattern.trace_id_group {
                         if let Some(trace_id) = captures.get(trace_group) {
                             log_entry.trace_id = Some(trace_id.as_str().to_string());
@@ -311,9 +526,14 @@ impl LogParser for RegexLogParser {
             }
         }
 
-        Ok(Some(LogEntry {
-            timestamp: crate::telemetry::current_timestamp(),
+        if !self.config.keeps_message(line) {
+            return Ok(None);
+        }
+
+        let mut log_entry = LogEntry {
+            timestamp: 0,
             level: LogLevel::Info,
+            severity_number: LogLevel::Info.severity_number(),
             message: line.to_string(),
             service_name: service_name.to_string(),
             pod_name: pod_name.to_string(),
@@ -321,7 +541,10 @@ impl LogParser for RegexLogParser {
             trace_id: None,
             span_id: None,
             attributes: HashMap::new(),
-        }))
+        };
+        self.config.apply_timestamp(&mut log_entry, None, None);
+
+        Ok(Some(log_entry))
     }
 
     fn parse_span(&self, _line: &str, _service_name: &str) -> Result<Option<TraceSpan>> {
@@ -338,22 +561,36 @@ pub struct CompositeLogParser {
 
 impl CompositeLogParser {
     pub fn new(trace_correlation: bool) -> Self {
+        Self::with_config(ParserConfig {
+            trace_correlation,
+            ..ParserConfig::default()
+        })
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
         Self {
-            json_parser: JsonLogParser::new(trace_correlation),
-            regex_parser: RegexLogParser::new(trace_correlation),
+            json_parser: JsonLogParser::with_config(config.clone()),
+            regex_parser: RegexLogParser::with_config(config),
         }
     }
 }
 
+impl CompositeLogParser {
+    /// Whether a line may be JSON. Arrays only count when they are mapped,
+    /// since `[` also starts bracketed timestamps in text logs.
+    fn looks_like_json(&self, line: &str) -> bool {
+        let line = line.trim_start();
+        line.starts_with('{') || (line.starts_with('[') && self.json_parser.maps_arrays())
+    }
+}
+
 impl LogParser for CompositeLogParser {
     fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
         // Try JSON parsing first
-        if line.trim().starts_with('{') {
-            match self.json_parser.parse_log(line, service_name, pod_name, namespace) {
-                Ok(Some(log)) => return Ok(Some(log)),
-                Ok(None) => {},
-                Err(_) => {},
-            }
+        if self.looks_like_json(line)
+            && let Ok(log) = self.json_parser.parse_log(line, service_name, pod_name, namespace)
+        {
+            return Ok(log);
         }
 
         // Fall back to regex parsing
@@ -361,7 +598,7 @@ impl LogParser for CompositeLogParser {
     }
 
     fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
-        if line.trim().starts_with('{') {
+        if self.looks_like_json(line) {
             self.json_parser.parse_span(line, service_name)
         } else {
             Ok(None)
@@ -369,35 +606,530 @@ impl LogParser for CompositeLogParser {
     }
 }
 
-/// Parse various timestamp formats
-fn parse_timestamp(ts_str: &str) -> Option<u64> {
-    use chrono::{DateTime, NaiveDateTime};
+/// Parser for the text output of Rust's `tracing` fmt layer and of
+/// `env_logger`, keeping the module path as `code.namespace` and the fields
+/// `tracing` appends after the message as attributes. Other lines fall back
+/// to the regex patterns.
+pub struct TracingLogParser {
+    regex_parser: RegexLogParser,
+    config: ParserConfig,
+}
+
+impl TracingLogParser {
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            regex_parser: RegexLogParser::with_config(config.clone()),
+            config,
+        }
+    }
+
+    /// Timestamp, level, module and the rest of the line
+    fn split_line(line: &str) -> Option<(&str, &str, &str, &str)> {
+        static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            [
+                // tracing: 2023-12-01T10:30:45.123456Z  INFO my_app::api: Message field=value
+                Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+([\w:]+):\s?(.*)$").unwrap(),
+                // env_logger: [2023-12-01T10:30:45Z INFO  my_app::api] Message
+                Regex::new(r"^\[(\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+([\w:]+)\]\s?(.*)$").unwrap(),
+            ]
+        });
+
+        patterns.iter().find_map(|pattern| {
+            let captures = pattern.captures(line)?;
+            let group = |index| captures.get(index).map_or("", |m| m.as_str());
+            Some((group(1), group(2), group(3), group(4)))
+        })
+    }
+
+    /// Split `field=value` pairs off the end of a message, unquoting quoted
+    /// values. A message made only of fields is kept whole.
+    fn split_fields(rest: &str) -> (&str, HashMap<String, String>) {
+        static FIELD: OnceLock<Regex> = OnceLock::new();
+        let field = FIELD.get_or_init(|| {
+            Regex::new(r#"(?:^|\s)([A-Za-z_][\w.]*)=("(?:[^"\\]|\\.)*"|[^\s"]+)$"#).unwrap()
+        });
+
+        let mut message = rest.trim_end();
+        let mut fields = HashMap::new();
+        while let Some(captures) = field.captures(message) {
+            let value = &captures[2];
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => value.to_string(),
+            };
+            fields.entry(captures[1].to_string()).or_insert(value);
+            message = message[..captures.get(0).unwrap().start()].trim_end();
+        }
+
+        if message.is_empty() {
+            (rest.trim_end(), fields)
+        } else {
+            (message, fields)
+        }
+    }
+}
+
+impl LogParser for TracingLogParser {
+    fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
+        let plain = strip_ansi(line);
+        let Some((raw_timestamp, level, module, rest)) = Self::split_line(&plain) else {
+            return self.regex_parser.parse_log(line, service_name, pod_name, namespace);
+        };
+
+        let (message, fields) = Self::split_fields(rest);
+        if !self.config.keeps_message(message) {
+            return Ok(None);
+        }
+
+        let level = LogLevel::from(level);
+        let mut log_entry = LogEntry {
+            timestamp: 0,
+            severity_number: level.severity_number(),
+            level,
+            message: message.to_string(),
+            service_name: service_name.to_string(),
+            pod_name: pod_name.to_string(),
+            namespace: namespace.to_string(),
+            trace_id: None,
+            span_id: None,
+            attributes: fields,
+        };
+        log_entry.attributes.insert("code.namespace".to_string(), module.to_string());
+
+        let timestamp = parse_timestamp(raw_timestamp, &self.config.default_timezone);
+        self.config.apply_timestamp(&mut log_entry, timestamp, Some(raw_timestamp));
+
+        if self.config.trace_correlation {
+            log_entry.trace_id = log_entry.attributes.get("trace_id").cloned();
+            log_entry.span_id = log_entry.attributes.get("span_id").cloned();
+        }
+
+        Ok(Some(log_entry))
+    }
+
+    fn parse_span(&self, _line: &str, _service_name: &str) -> Result<Option<TraceSpan>> {
+        Ok(None)
+    }
+}
+
+/// Remove ANSI color codes, which `tracing` writes when it thinks it is on a
+/// terminal
+fn strip_ansi(line: &str) -> Cow<'_, str> {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap()).replace_all(line, "")
+}
+
+/// Upper bound on how much of a message is scanned for key-value pairs
+const MAX_KEY_VALUE_SCAN_BYTES: usize = 4096;
+
+/// Upper bound on the number of key-value pairs extracted from one message
+const MAX_KEY_VALUE_PAIRS: usize = 32;
+
+/// Parser decorator that mines `key=value` / `key: value` pairs embedded in
+/// free-text messages and adds them as attributes, leaving the message intact
+pub struct KeyValueParser {
+    inner: Box<dyn LogParser>,
+    pattern: Regex,
+}
+
+impl KeyValueParser {
+    pub fn new(inner: Box<dyn LogParser>, delimiters: &[String]) -> Self {
+        let delimiters: Vec<String> = delimiters
+            .iter()
+            .filter(|d| !d.is_empty())
+            .map(|d| regex::escape(d))
+            .collect();
+        let delimiters = if delimiters.is_empty() {
+            "=".to_string()
+        } else {
+            delimiters.join("|")
+        };
+
+        let pattern = Regex::new(&format!(
+            r#"(?:^|[\s,;(\[{{])([A-Za-z_][\w.\-]{{0,63}})(?:{})\s*("[^"]{{0,256}}"|[^\s"]{{1,256}})"#,
+            delimiters
+        )).expect("escaped delimiters always form a valid pattern");
+
+        Self { inner, pattern }
+    }
+
+    /// Extract key-value pairs from a message
+    fn extract(&self, message: &str) -> Vec<(String, String)> {
+        let mut end = message.len().min(MAX_KEY_VALUE_SCAN_BYTES);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let scanned = &message[..end];
+        self.pattern
+            .captures_iter(scanned)
+            .filter_map(|captures| {
+                let key = captures.get(1)?;
+                // `http://host` is a URL, not the key `http`
+                if scanned[key.end()..].starts_with("://") {
+                    return None;
+                }
+                let key = key.as_str();
+                let value = captures.get(2)?.as_str();
+                let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(quoted) => quoted,
+                    None => value.trim_end_matches([',', ';', '.', ')', ']', '}']),
+                };
+
+                if value.is_empty() {
+                    None
+                } else {
+                    Some((key.to_string(), value.to_string()))
+                }
+            })
+            .take(MAX_KEY_VALUE_PAIRS)
+            .collect()
+    }
+}
+
+impl LogParser for KeyValueParser {
+    fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
+        let mut log_entry = match self.inner.parse_log(line, service_name, pod_name, namespace)? {
+            Some(log_entry) => log_entry,
+            None => return Ok(None),
+        };
+
+        // Attributes set by the primary parser take precedence
+        for (key, value) in self.extract(&log_entry.message) {
+            log_entry.attributes.entry(key).or_insert(value);
+        }
+
+        Ok(Some(log_entry))
+    }
+
+    fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
+        self.inner.parse_span(line, service_name)
+    }
+}
+
+/// Attributes the parsers add about the entry itself, exempt from the
+/// attribute limit
+const COLLECTOR_ATTRIBUTES: &[&str] = &[
+    "timestamp.source",
+    "timestamp.original",
+    "trace_id.malformed",
+    "span_id.malformed",
+];
+
+/// Parser decorator that caps how many attributes a log entry carries,
+/// recording how many were removed in `attributes.dropped_count`. The
+/// collector's own attributes, such as `timestamp.source`, are always kept
+/// and do not count toward the limit.
+pub struct AttributeLimitParser {
+    inner: Box<dyn LogParser>,
+    max_attributes: usize,
+    allowlist: Vec<String>,
+}
+
+impl AttributeLimitParser {
+    pub fn new(inner: Box<dyn LogParser>, max_attributes: usize, allowlist: Vec<String>) -> Self {
+        Self {
+            inner,
+            max_attributes,
+            allowlist,
+        }
+    }
+
+    /// Keep allowlisted attributes first, then the rest in name order so the
+    /// same line always keeps the same attributes
+    fn cap(&self, attributes: &mut HashMap<String, String>) {
+        let mut keys: Vec<String> = attributes
+            .keys()
+            .filter(|key| !COLLECTOR_ATTRIBUTES.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        if keys.len() <= self.max_attributes {
+            return;
+        }
+
+        keys.sort_by_key(|key| {
+            let rank = self.allowlist.iter().position(|allowed| allowed == key);
+            (rank.is_none(), rank, key.clone())
+        });
+
+        let dropped = keys.split_off(self.max_attributes);
+        for key in &dropped {
+            attributes.remove(key);
+        }
+        attributes.insert("attributes.dropped_count".to_string(), dropped.len().to_string());
+    }
+}
+
+impl LogParser for AttributeLimitParser {
+    fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
+        let mut log_entry = self.inner.parse_log(line, service_name, pod_name, namespace)?;
+        if let Some(log_entry) = &mut log_entry {
+            self.cap(&mut log_entry.attributes);
+        }
+        Ok(log_entry)
+    }
+
+    fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
+        self.inner.parse_span(line, service_name)
+    }
+}
+
+/// Parser decorator that lowercases trace and span ids, left-pads 64-bit
+/// trace ids (B3, Jaeger) to 128 bits and applies the malformed id policy,
+/// keeping the original value in a `<field>.malformed` attribute (or span
+/// tag). Empty ids are left for validation to report.
+pub struct TraceIdParser {
+    inner: Box<dyn LogParser>,
+    policy: TraceIdPolicy,
+    spans_dropped: Arc<AtomicU64>,
+}
+
+impl TraceIdParser {
+    pub fn new(inner: Box<dyn LogParser>, policy: TraceIdPolicy, spans_dropped: Arc<AtomicU64>) -> Self {
+        Self { inner, policy, spans_dropped }
+    }
+
+    fn normalize(
+        &self,
+        field: &str,
+        id: &mut Option<String>,
+        hex_len: usize,
+        attributes: &mut HashMap<String, String>,
+    ) {
+        let Some(original) = id.as_deref().filter(|id| !id.is_empty()) else {
+            return;
+        };
 
-    // Try different timestamp formats
+        if let Some(normalized) = normalize_id(original, hex_len) {
+            *id = Some(normalized);
+            return;
+        }
+
+        // 64-bit trace ids are valid once padded to the W3C width
+        if hex_len == TRACE_ID_HEX_LEN
+            && let Some(short) = normalize_id(original, SPAN_ID_HEX_LEN)
+        {
+            *id = Some(format!("{:0>width$}", short, width = TRACE_ID_HEX_LEN));
+            return;
+        }
+
+        let replacement = match self.policy {
+            TraceIdPolicy::Keep => Some(original.to_string()),
+            TraceIdPolicy::Drop => None,
+            TraceIdPolicy::Regenerate => Some(derive_id(original, hex_len)),
+        };
+        attributes.insert(format!("{}.malformed", field), original.to_string());
+        *id = replacement;
+    }
+}
+
+impl LogParser for TraceIdParser {
+    fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
+        let mut log_entry = self.inner.parse_log(line, service_name, pod_name, namespace)?;
+        if let Some(log_entry) = &mut log_entry {
+            self.normalize("trace_id", &mut log_entry.trace_id, TRACE_ID_HEX_LEN, &mut log_entry.attributes);
+            self.normalize("span_id", &mut log_entry.span_id, SPAN_ID_HEX_LEN, &mut log_entry.attributes);
+        }
+        Ok(log_entry)
+    }
+
+    fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
+        let Some(mut span) = self.inner.parse_span(line, service_name)? else {
+            return Ok(None);
+        };
+
+        let mut trace_id = Some(std::mem::take(&mut span.trace_id));
+        let mut span_id = Some(std::mem::take(&mut span.span_id));
+        self.normalize("trace_id", &mut trace_id, TRACE_ID_HEX_LEN, &mut span.tags);
+        self.normalize("span_id", &mut span_id, SPAN_ID_HEX_LEN, &mut span.tags);
+        self.normalize("parent_span_id", &mut span.parent_span_id, SPAN_ID_HEX_LEN, &mut span.tags);
+
+        // A span cannot be sent without its own ids
+        match (trace_id, span_id) {
+            (Some(trace_id), Some(span_id)) => {
+                span.trace_id = trace_id;
+                span.span_id = span_id;
+                Ok(Some(span))
+            }
+            _ => {
+                debug!("Dropping span with malformed ids from {}", service_name);
+                self.spans_dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Operation name reported once the distinct name limit is reached
+pub const OTHER_OPERATION_NAME: &str = "other";
+
+/// Parser decorator that rewrites span operation names with the first
+/// matching rule and caps how many distinct names are reported, keeping the
+/// original name in the `operation_name.original` tag when it changes
+pub struct OperationNameParser {
+    inner: Box<dyn LogParser>,
+    rules: Vec<(Regex, String)>,
+    max_names: usize,
+    seen: Mutex<HashSet<String>>,
+}
+
+impl OperationNameParser {
+    pub fn new(inner: Box<dyn LogParser>, rules: Vec<(Regex, String)>, max_names: usize) -> Self {
+        Self {
+            inner,
+            rules,
+            max_names,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn normalize(&self, operation_name: &str) -> String {
+        let normalized = self
+            .rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(operation_name))
+            .map(|(pattern, replacement)| pattern.replace_all(operation_name, replacement.as_str()).into_owned())
+            .unwrap_or_else(|| operation_name.to_string());
+
+        if self.max_names == 0 {
+            return normalized;
+        }
+
+        let mut seen = self.seen.lock().unwrap();
+        if seen.contains(&normalized) {
+            normalized
+        } else if seen.len() < self.max_names {
+            seen.insert(normalized.clone());
+            normalized
+        } else {
+            OTHER_OPERATION_NAME.to_string()
+        }
+    }
+}
+
+impl LogParser for OperationNameParser {
+    fn parse_log(&self, line: &str, service_name: &str, pod_name: &str, namespace: &str) -> Result<Option<LogEntry>> {
+        self.inner.parse_log(line, service_name, pod_name, namespace)
+    }
+
+    fn parse_span(&self, line: &str, service_name: &str) -> Result<Option<TraceSpan>> {
+        let mut span = self.inner.parse_span(line, service_name)?;
+        if let Some(span) = &mut span {
+            let normalized = self.normalize(&span.operation_name);
+            if normalized != span.operation_name {
+                let original = std::mem::replace(&mut span.operation_name, normalized);
+                span.tags.insert("operation_name.original".to_string(), original);
+            }
+        }
+        Ok(span)
+    }
+}
+
+/// Split a line holding several JSON objects with no delimiter, as in
+/// `{...}{...}`, into one slice per object. At most `max_objects` slices are
+/// returned: the rest of a longer line, or an object cut short, is kept as
+/// the last slice. `None` when the line is not concatenated objects.
+pub fn split_json_objects(line: &str, max_objects: usize) -> Option<Vec<&str>> {
+    let line = line.trim();
+    if !line.starts_with('{') || max_objects < 2 {
+        return None;
+    }
+
+    let mut objects = Vec::new();
+    let mut stream = serde_json::Deserializer::from_str(line).into_iter::<serde::de::IgnoredAny>();
+    let mut start = 0;
+    while objects.len() + 1 < max_objects && stream.next().is_some_and(|value| value.is_ok()) {
+        let end = stream.byte_offset();
+        let object = line[start..end].trim();
+        if !object.starts_with('{') {
+            return None;
+        }
+        objects.push(object);
+        start = end;
+    }
+
+    // Text after the objects means this is not a concatenation
+    let rest = line[start..].trim();
+    if !rest.is_empty() {
+        if !rest.starts_with('{') {
+            return None;
+        }
+        objects.push(rest);
+    }
+
+    (objects.len() > 1).then_some(objects)
+}
+
+/// A message with nothing but whitespace or control characters
+pub fn is_blank_message(message: &str) -> bool {
+    message.chars().all(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Parse various timestamp formats, interpreting timestamps without an
+/// offset in `default_timezone`
+/// Whether a timestamp is within `max_skew` of `now`; a zero `max_skew`
+/// accepts every timestamp
+pub fn within_skew(timestamp: u64, now: u64, max_skew: Duration) -> bool {
+    max_skew.is_zero() || timestamp.abs_diff(now) <= max_skew.as_secs()
+}
+
+fn parse_timestamp(ts_str: &str, default_timezone: &Tz) -> Option<u64> {
+    use chrono::{DateTime, NaiveDateTime, TimeZone};
+
+    // Timestamps carrying their own offset are unambiguous
+    if let Ok(dt) = DateTime::parse_from_rfc3339(ts_str) {
+        return Some(dt.timestamp() as u64);
+    }
+    if let Ok(dt) = DateTime::parse_from_str(ts_str, "%d/%b/%Y:%H:%M:%S %z") {
+        return Some(dt.timestamp() as u64); // Apache log format
+    }
+
+    // Try different timestamp formats without an offset
     let formats = [
-        "%Y-%m-%dT%H:%M:%S%.fZ",      // ISO 8601 with timezone
-        "%Y-%m-%dT%H:%M:%SZ",         // ISO 8601 simple
+        "%Y-%m-%dT%H:%M:%S%.f",       // ISO 8601 without timezone
         "%Y-%m-%d %H:%M:%S%.f",       // SQL timestamp with fractional
         "%Y-%m-%d %H:%M:%S",          // SQL timestamp
         "%Y/%m/%d %H:%M:%S",          // Alternative format
-        "%d/%b/%Y:%H:%M:%S %z",       // Apache log format
     ];
 
     for format in &formats {
-        if let Ok(dt) = DateTime::parse_from_str(ts_str, format) {
-            return Some(dt.timestamp() as u64);
-        }
-        if let Ok(dt) = NaiveDateTime::parse_from_str(ts_str, format) {
-            return Some(dt.timestamp() as u64);
+        if let Ok(naive) = NaiveDateTime::parse_from_str(ts_str, format) {
+            // Nonexistent local times (DST gaps) take the offset in effect
+            // just before the transition, an hour earlier
+            let hour = chrono::TimeDelta::hours(1);
+            let timestamp = default_timezone
+                .from_local_datetime(&naive)
+                .earliest()
+                .or_else(|| default_timezone.from_local_datetime(&(naive - hour)).earliest().map(|dt| dt + hour))
+                .map(|dt| dt.timestamp())
+                .unwrap_or_else(|| naive.and_utc().timestamp());
+            return Some(timestamp as u64);
         }
     }
 
-    // Try parsing as Unix timestamp
-    if let Ok(timestamp) = ts_str.parse::<u64>() {
-        return Some(timestamp);
+    // Try parsing as a Unix timestamp, possibly fractional or in finer units
+    ts_str.parse::<f64>().ok().and_then(epoch_seconds)
+}
+
+/// Whole seconds from a Unix timestamp in seconds, milliseconds, microseconds
+/// or nanoseconds, told apart by magnitude: a value in seconds stays below
+/// 1e11 until the year 5138. Fractions are truncated.
+fn epoch_seconds(value: f64) -> Option<u64> {
+    if !value.is_finite() || value < 0.0 {
+        return None;
     }
 
-    None
+    let seconds = if value >= 1e17 {
+        value / 1e9
+    } else if value >= 1e14 {
+        value / 1e6
+    } else if value >= 1e11 {
+        value / 1e3
+    } else {
+        value
+    };
+    Some(seconds as u64)
 }
 
 /// Factory for creating log parsers
@@ -408,11 +1140,52 @@ impl LogParserFactory {
         format: &str,
         trace_correlation: bool,
     ) -> Box<dyn LogParser> {
-        match format.to_lowercase().as_str() {
-            "json" => Box::new(JsonLogParser::new(trace_correlation)),
-            "regex" => Box::new(RegexLogParser::new(trace_correlation)),
-            "composite" | "auto" => Box::new(CompositeLogParser::new(trace_correlation)),
-            _ => Box::new(CompositeLogParser::new(trace_correlation)), // Default
+        Self::create_parser_with_config(format, ParserConfig {
+            trace_correlation,
+            ..ParserConfig::default()
+        })
+    }
+
+    pub fn create_parser_with_config(
+        format: &str,
+        config: ParserConfig,
+    ) -> Box<dyn LogParser> {
+        let extract_key_values = config.extract_key_values;
+        let delimiters = config.key_value_delimiters.clone();
+        let max_attributes = config.max_attributes_per_entry;
+        let allowlist = config.attribute_allowlist.clone();
+        let trace_id_policy = config.trace_id_policy;
+        let invalid_id_spans_dropped = Arc::clone(&config.invalid_id_spans_dropped);
+        let operation_name_rules = config.operation_name_rules.clone();
+        let max_operation_names = config.max_operation_names;
+
+        let parser: Box<dyn LogParser> = match format.to_lowercase().as_str() {
+            "json" => Box::new(JsonLogParser::with_config(config)),
+            "regex" => Box::new(RegexLogParser::with_config(config)),
+            "composite" | "auto" => Box::new(CompositeLogParser::with_config(config)),
+            "tracing" => Box::new(TracingLogParser::with_config(config)),
+            _ => Box::new(CompositeLogParser::with_config(config)), // Default
+        };
+
+        let parser: Box<dyn LogParser> = if extract_key_values {
+            Box::new(KeyValueParser::new(parser, &delimiters))
+        } else {
+            parser
+        };
+
+        let parser: Box<dyn LogParser> = Box::new(TraceIdParser::new(parser, trace_id_policy, invalid_id_spans_dropped));
+
+        let parser: Box<dyn LogParser> = if !operation_name_rules.is_empty() || max_operation_names > 0 {
+            Box::new(OperationNameParser::new(parser, operation_name_rules, max_operation_names))
+        } else {
+            parser
+        };
+
+        // Applied last so attributes from every parser count toward the limit
+        if max_attributes > 0 {
+            Box::new(AttributeLimitParser::new(parser, max_attributes, allowlist))
+        } else {
+            parser
         }
     }
 }
@@ -421,6 +1194,78 @@ impl LogParserFactory {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_drop_empty_messages() {
+        let text_lines = ["", "   ", "\t \t", "\u{7}\u{1b}", " \u{0} \t", "ERROR:   \u{7}"];
+        let json_lines = [r#"{"level": "INFO", "message": " \t "}"#, r#"{"message": "\u0007"}"#];
+
+        let regex_parser = RegexLogParser::new(true);
+        for line in text_lines {
+            assert!(regex_parser.parse_log(line, "svc", "pod", "ns").unwrap().is_none(), "{:?}", line);
+        }
+
+        let json_parser = JsonLogParser::new(true);
+        for line in json_lines {
+            assert!(json_parser.parse_log(line, "svc", "pod", "ns").unwrap().is_none(), "{:?}", line);
+        }
+
+        let composite_parsers = [
+            LogParserFactory::create_parser("composite", true),
+            LogParserFactory::create_parser_with_config("composite", ParserConfig {
+                extract_key_values: true,
+                ..ParserConfig::default()
+            }),
+        ];
+        for parser in &composite_parsers {
+            for line in text_lines.iter().chain(&json_lines) {
+                assert!(parser.parse_log(line, "svc", "pod", "ns").unwrap().is_none(), "{:?}", line);
+            }
+        }
+
+        let keep_all = ParserConfig {
+            drop_empty_messages: false,
+            ..ParserConfig::default()
+        };
+        let parser = CompositeLogParser::with_config(keep_all);
+
+        let entry = parser.parse_log("\t \u{7}", "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(entry.message, "\t \u{7}");
+
+        let entry = parser.parse_log(r#"{"level": "INFO", "message": ""}"#, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(entry.message, "");
+    }
+
+    #[test]
+    fn test_json_without_message_is_not_wrapped() {
+        let parser = CompositeLogParser::new(true);
+        let line = r#"{"span_id": "abc123", "operation": "db.query"}"#;
+
+        assert!(parser.parse_log(line, "svc", "pod", "ns").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_json_severity_number() {
+        let parser = JsonLogParser::new(true);
+        let parse = |line: &str| parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+
+        let entry = parse(r#"{"level": "ERROR", "message": "disk full", "severity_number": 19}"#);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.severity_number, 19);
+
+        // Numbers outside the level's range fall back to the canonical number
+        let entry = parse(r#"{"level": "INFO", "message": "hello", "severity_number": 19}"#);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.severity_number, 9);
+
+        // The number alone determines the level
+        let entry = parse(r#"{"message": "bye", "severityNumber": 22}"#);
+        assert_eq!(entry.level, LogLevel::Fatal);
+        assert_eq!(entry.severity_number, 22);
+
+        let entry = parse(r#"{"level": "WARN", "message": "slow"}"#);
+        assert_eq!(entry.severity_number, 13);
+    }
+
     #[test]
     fn test_json_log_parsing() {
         let parser = JsonLogParser::new(true);
@@ -436,6 +1281,35 @@ mod tests {
         assert_eq!(log_entry.span_id, Some("def456".to_string()));
     }
 
+    #[test]
+    fn test_json_array_logs() {
+        let config = ParserConfig {
+            json_array_fields: vec!["level".to_string(), "message".to_string(), "*".to_string()],
+            ..ParserConfig::default()
+        };
+        let parser = LogParserFactory::create_parser_with_config("composite", config);
+
+        let line = r#"["ERROR", "payment failed", {"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "attributes": {"order_id": "o-1"}}]"#;
+        let log_entry = parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.level, LogLevel::Error);
+        assert_eq!(log_entry.message, "payment failed");
+        assert_eq!(log_entry.trace_id.as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(log_entry.attributes["order_id"], "o-1");
+
+        // Shorter arrays leave the missing fields unset
+        let log_entry = parser.parse_log(r#"["WARN", "disk almost full"]"#, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!((log_entry.level, log_entry.message.as_str()), (LogLevel::Warn, "disk almost full"));
+        assert!(parser.parse_log(r#"["ERROR"]"#, "svc", "pod", "ns").unwrap().is_none());
+
+        // Bracketed timestamps in text logs still reach the regex parser
+        let log_entry = parser.parse_log("[2023-12-01T10:30:45Z] ERROR: boom", "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.message, "boom");
+
+        // Arrays are not logs unless mapped
+        let unmapped = JsonLogParser::new(true);
+        assert!(unmapped.parse_log(r#"["ERROR", "payment failed"]"#, "svc", "pod", "ns").unwrap().is_none());
+    }
+
     #[test]
     fn test_regex_log_parsing() {
         let parser = RegexLogParser::new(false);
@@ -477,10 +1351,104 @@ mod tests {
 
     #[test]
     fn test_timestamp_parsing() {
-        assert!(parse_timestamp("2025-01-01T10:30:45Z").is_some());
-        assert!(parse_timestamp("2025-01-01 10:30:45").is_some());
-        assert!(parse_timestamp("1701234567").is_some());
-        assert!(parse_timestamp("invalid").is_none());
+        assert!(parse_timestamp("2025-01-01T10:30:45Z", &Tz::UTC).is_some());
+        assert!(parse_timestamp("2025-01-01 10:30:45", &Tz::UTC).is_some());
+        assert!(parse_timestamp("1701234567", &Tz::UTC).is_some());
+        assert!(parse_timestamp("invalid", &Tz::UTC).is_none());
+    }
+
+    #[test]
+    fn test_json_numeric_timestamps() {
+        let parser = JsonLogParser::with_config(ParserConfig {
+            max_timestamp_skew: Duration::ZERO,
+            ..ParserConfig::default()
+        });
+        let timestamp_of = |timestamp: &str| {
+            let line = format!(r#"{{"timestamp": {}, "message": "m"}}"#, timestamp);
+            let entry = parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap();
+            assert_eq!(entry.attributes["timestamp.source"], "log", "{}", timestamp);
+            entry.timestamp
+        };
+
+        assert_eq!(timestamp_of("1701234567"), 1701234567);
+        assert_eq!(timestamp_of("1701234567.923"), 1701234567);
+        assert_eq!(timestamp_of(r#""1701234567""#), 1701234567);
+        assert_eq!(timestamp_of(r#"" 1701234567.5 ""#), 1701234567);
+        assert_eq!(timestamp_of("1701234567123"), 1701234567);
+        assert_eq!(timestamp_of(r#""1701234567123""#), 1701234567);
+        assert_eq!(timestamp_of("1701234567123.4"), 1701234567);
+        assert_eq!(timestamp_of("1701234567123456"), 1701234567);
+        assert_eq!(timestamp_of("1701234567123456789"), 1701234567);
+        assert_eq!(timestamp_of(r#""2023-11-29T05:09:27Z""#), 1701234567);
+
+        for invalid in ["-5", r#""soon""#, "true"] {
+            let line = format!(r#"{{"timestamp": {}, "message": "m"}}"#, invalid);
+            let entry = parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap();
+            assert_eq!(entry.attributes["timestamp.source"], "ingest", "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_default_timezone() {
+        let vancouver = Tz::America__Vancouver;
+
+        // Offset-less timestamps are interpreted in the default timezone (PST/PDT)
+        assert_eq!(parse_timestamp("2023-12-01 10:30:45", &vancouver), Some(1701455445));
+        assert_eq!(parse_timestamp("2023-07-01 10:30:45", &vancouver), Some(1688232645));
+        assert_eq!(parse_timestamp("2023-12-01 10:30:45", &Tz::UTC), Some(1701426645));
+
+        // Times skipped by the spring-forward gap keep the pre-gap PST offset
+        assert_eq!(parse_timestamp("2024-03-10 02:30:00", &vancouver), Some(1710066600));
+        assert_eq!(parse_timestamp("2024-03-10 01:59:59", &vancouver), Some(1710064799));
+
+        // Explicit offsets are not affected by the default timezone
+        assert_eq!(parse_timestamp("2023-12-01T18:30:45Z", &vancouver), Some(1701455445));
+        assert_eq!(parse_timestamp("2023-12-01T10:30:45-08:00", &vancouver), Some(1701455445));
+    }
+
+    #[test]
+    fn test_timestamp_source_attribute() {
+        let config = ParserConfig {
+            trace_correlation: false,
+            default_timezone: Tz::America__Vancouver,
+            max_timestamp_skew: Duration::ZERO,
+            ..ParserConfig::default()
+        };
+        let parser = RegexLogParser::with_config(config);
+
+        let log_entry = parser
+            .parse_log("2023/12/01 10:30:45 [error] Connection timeout", "svc", "pod", "ns")
+            .unwrap()
+            .unwrap();
+        assert_eq!(log_entry.timestamp, 1701455445);
+        assert_eq!(log_entry.attributes["timestamp.source"], "log");
+
+        let log_entry = parser.parse_log("ERROR: No timestamp", "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.attributes["timestamp.source"], "ingest");
+    }
+
+    #[test]
+    fn test_old_timestamps_kept_by_default() {
+        let parser = JsonLogParser::new(false);
+        let log_line = r#"{"timestamp": "2015-03-01T08:00:00Z", "level": "INFO", "message": "From the archive"}"#;
+
+        let log_entry = parser.parse_log(log_line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.timestamp, 1425196800);
+        assert_eq!(log_entry.attributes["timestamp.source"], "log");
+    }
+
+    #[test]
+    fn test_implausible_timestamp_uses_ingest_time() {
+        let parser = JsonLogParser::with_config(ParserConfig {
+            max_timestamp_skew: Duration::from_secs(3600),
+            ..ParserConfig::default()
+        });
+        let log_line = r#"{"timestamp": 4102444800, "level": "INFO", "message": "From 2100"}"#;
+
+        let log_entry = parser.parse_log(log_line, "svc", "pod", "ns").unwrap().unwrap();
+        assert!(log_entry.timestamp < 4102444800);
+        assert_eq!(log_entry.attributes["timestamp.source"], "ingest");
+        assert_eq!(log_entry.attributes["timestamp.original"], "4102444800");
     }
 
     #[test]
@@ -498,4 +1466,315 @@ mod tests {
         assert_eq!(span.duration_ms, 150);
         assert_eq!(span.status, SpanStatus::Ok);
     }
+
+    #[test]
+    fn test_span_with_swapped_times() {
+        let parser = JsonLogParser::new(true);
+        let span_line = r#"{"trace_id": "abc123", "span_id": "def456", "start_time": 1701426650, "end_time": 1701426645}"#;
+
+        let span = parser.parse_span(span_line, "test-service").unwrap().unwrap();
+
+        assert_eq!(span.start_time, 1701426645);
+        assert_eq!(span.end_time, 1701426650);
+        assert_eq!(span.duration_ms, 5000);
+        assert_eq!(span.tags["span.timing_anomaly"], "end_before_start");
+    }
+
+    #[test]
+    fn test_attribute_limit() {
+        let config = ParserConfig {
+            max_attributes_per_entry: 3,
+            attribute_allowlist: vec!["request_id".to_string()],
+            ..ParserConfig::default()
+        };
+        let parser = LogParserFactory::create_parser_with_config("json", config);
+
+        let fields: Vec<String> = (0..10).map(|i| format!(r#""field_{}": "v""#, i)).collect();
+        let line = format!(
+            r#"{{"level": "INFO", "message": "wide", "attributes": {{{}, "request_id": "req-1"}}}}"#,
+            fields.join(", ")
+        );
+        let log_entry = parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap();
+
+        assert_eq!(log_entry.attributes.len(), 5);
+        assert_eq!(log_entry.attributes["request_id"], "req-1");
+        assert_eq!(log_entry.attributes["timestamp.source"], "ingest");
+        assert!(log_entry.attributes.contains_key("field_0"));
+        assert!(log_entry.attributes.contains_key("field_1"));
+        assert!(!log_entry.attributes.contains_key("field_2"));
+        assert_eq!(log_entry.attributes["attributes.dropped_count"], "8");
+
+        // Entries within the limit are untouched
+        let narrow = parser
+            .parse_log(r#"{"level": "INFO", "message": "narrow", "attributes": {"a": "1"}}"#, "svc", "pod", "ns")
+            .unwrap()
+            .unwrap();
+        assert!(!narrow.attributes.contains_key("attributes.dropped_count"));
+    }
+
+    fn trace_id_parser(policy: TraceIdPolicy) -> Box<dyn LogParser> {
+        LogParserFactory::create_parser_with_config("json", ParserConfig {
+            trace_id_policy: policy,
+            ..ParserConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_malformed_ids_kept_by_default() {
+        let parser = LogParserFactory::create_parser("json", true);
+
+        let line = r#"{"message": "bad ids", "trace_id": "trace-1", "span_id": "span-1"}"#;
+        let log_entry = parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.trace_id.as_deref(), Some("trace-1"));
+        assert_eq!(log_entry.attributes["trace_id.malformed"], "trace-1");
+
+        let span = parser.parse_span(line, "svc").unwrap().unwrap();
+        assert_eq!(span.trace_id, "trace-1");
+        assert_eq!(span.span_id, "span-1");
+        assert_eq!(span.tags["span_id.malformed"], "span-1");
+    }
+
+    #[test]
+    fn test_64_bit_trace_ids_padded() {
+        for policy in [TraceIdPolicy::Keep, TraceIdPolicy::Drop, TraceIdPolicy::Regenerate] {
+            let parser = trace_id_parser(policy);
+            let line = r#"{"trace_id": "A3CE929D0E0E4736", "span_id": "00f067aa0ba902b7", "operation": "db.query"}"#;
+
+            let span = parser.parse_span(line, "svc").unwrap().unwrap();
+            assert_eq!(span.trace_id, "0000000000000000a3ce929d0e0e4736");
+            assert!(!span.tags.contains_key("trace_id.malformed"));
+        }
+    }
+
+    #[test]
+    fn test_uppercase_ids_lowercased() {
+        for policy in [TraceIdPolicy::Keep, TraceIdPolicy::Drop, TraceIdPolicy::Regenerate] {
+            let parser = trace_id_parser(policy);
+            let line = r#"{"message": "upper", "trace_id": "4BF92F3577B34DA6A3CE929D0E0E4736", "span_id": "00F067AA0BA902B7"}"#;
+
+            let log_entry = parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+            assert_eq!(log_entry.trace_id.as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+            assert_eq!(log_entry.span_id.as_deref(), Some("00f067aa0ba902b7"));
+            assert!(!log_entry.attributes.contains_key("trace_id.malformed"));
+
+            let span = parser.parse_span(line, "svc").unwrap().unwrap();
+            assert_eq!(span.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+            assert_eq!(span.span_id, "00f067aa0ba902b7");
+        }
+    }
+
+    #[test]
+    fn test_malformed_ids_dropped() {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let parser = LogParserFactory::create_parser_with_config("json", ParserConfig {
+            trace_id_policy: TraceIdPolicy::Drop,
+            invalid_id_spans_dropped: Arc::clone(&dropped),
+            ..ParserConfig::default()
+        });
+
+        // Too short, then not hex
+        let line = r#"{"message": "bad ids", "trace_id": "abc123", "span_id": "zzzzzzzzzzzzzzzz"}"#;
+        let log_entry = parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.message, "bad ids");
+        assert_eq!(log_entry.trace_id, None);
+        assert_eq!(log_entry.span_id, None);
+        assert_eq!(log_entry.attributes["trace_id.malformed"], "abc123");
+        assert_eq!(log_entry.attributes["span_id.malformed"], "zzzzzzzzzzzzzzzz");
+
+        assert!(parser.parse_span(line, "svc").unwrap().is_none());
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+
+        // A bad parent only detaches the span from it
+        let child = r#"{"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "parent_span_id": "0000000000000000"}"#;
+        let span = parser.parse_span(child, "svc").unwrap().unwrap();
+        assert_eq!(span.parent_span_id, None);
+        assert_eq!(span.tags["parent_span_id.malformed"], "0000000000000000");
+    }
+
+    #[test]
+    fn test_malformed_ids_regenerated() {
+        let parser = trace_id_parser(TraceIdPolicy::Regenerate);
+
+        // Too short, then not hex
+        let line = r#"{"message": "bad ids", "trace_id": "abc123", "span_id": "zzzzzzzzzzzzzzzz"}"#;
+        let log_entry = parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap();
+        let trace_id = log_entry.trace_id.clone().unwrap();
+        let span_id = log_entry.span_id.clone().unwrap();
+        assert_eq!(normalize_id(&trace_id, TRACE_ID_HEX_LEN), Some(trace_id.clone()));
+        assert_eq!(normalize_id(&span_id, SPAN_ID_HEX_LEN), Some(span_id.clone()));
+        assert_eq!(log_entry.attributes["trace_id.malformed"], "abc123");
+        assert_eq!(log_entry.attributes["span_id.malformed"], "zzzzzzzzzzzzzzzz");
+
+        // The span from the same line keeps matching its log
+        let span = parser.parse_span(line, "svc").unwrap().unwrap();
+        assert_eq!(span.trace_id, trace_id);
+        assert_eq!(span.span_id, span_id);
+        assert_eq!(span.tags["trace_id.malformed"], "abc123");
+    }
+
+    #[test]
+    fn test_operation_name_normalization() {
+        let rules = [
+            (r"/[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}", "/{uuid}"),
+            (r"/\d+(/|$)", "/{id}$1"),
+        ];
+        let parser = LogParserFactory::create_parser_with_config("json", ParserConfig {
+            operation_name_rules: rules
+                .iter()
+                .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement.to_string()))
+                .collect(),
+            max_operation_names: 4,
+            ..ParserConfig::default()
+        });
+        let operation = |name: &str| {
+            let line = format!(
+                r#"{{"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "operation": "{}", "duration_ms": 5}}"#,
+                name
+            );
+            parser.parse_span(&line, "svc").unwrap().unwrap()
+        };
+
+        let span = operation("GET /users/12345");
+        assert_eq!(span.operation_name, "GET /users/{id}");
+        assert_eq!(span.tags["operation_name.original"], "GET /users/12345");
+        assert_eq!(operation("GET /users/678/orders").operation_name, "GET /users/{id}/orders");
+        assert_eq!(
+            operation("GET /orders/9b2f6a1c-3d4e-4f50-8a6b-7c8d9e0f1a2b").operation_name,
+            "GET /orders/{uuid}"
+        );
+
+        // Unmatched names pass through untouched
+        let span = operation("db.query");
+        assert_eq!(span.operation_name, "db.query");
+        assert!(!span.tags.contains_key("operation_name.original"));
+
+        // Past the limit new names are bucketed, known ones still reported
+        assert_eq!(operation("cache.get").operation_name, OTHER_OPERATION_NAME);
+        assert_eq!(operation("GET /users/42").operation_name, "GET /users/{id}");
+    }
+
+    #[test]
+    fn test_attribute_collision_policies() {
+        let line = r#"{"message": "checkout", "user_id": "top", "request_id": "req-1", "attributes": {"user_id": "nested", "request_id": "req-1", "cart": "c-9"}}"#;
+        let attributes_with = |policy| {
+            let parser = JsonLogParser::with_config(ParserConfig {
+                attribute_collision_policy: policy,
+                ..ParserConfig::default()
+            });
+            parser.parse_log(line, "svc", "pod", "ns").unwrap().unwrap().attributes
+        };
+
+        let top_level = attributes_with(AttributeCollisionPolicy::PreferTopLevel);
+        assert_eq!(top_level["user_id"], "top");
+        assert!(!top_level.contains_key("attributes.user_id"));
+
+        let nested = attributes_with(AttributeCollisionPolicy::PreferNested);
+        assert_eq!(nested["user_id"], "nested");
+        assert!(!nested.contains_key("attributes.user_id"));
+
+        let both = attributes_with(AttributeCollisionPolicy::KeepBoth);
+        assert_eq!(both["user_id"], "top");
+        assert_eq!(both["attributes.user_id"], "nested");
+        // Equal values are not a collision
+        assert_eq!(both["request_id"], "req-1");
+        assert!(!both.contains_key("attributes.request_id"));
+        assert_eq!(both["cart"], "c-9");
+    }
+
+    #[test]
+    fn test_key_value_extraction() {
+        let config = ParserConfig {
+            extract_key_values: true,
+            ..ParserConfig::default()
+        };
+        let parser = LogParserFactory::create_parser_with_config("composite", config);
+        let log_line = r#"INFO: GET /api took 34ms status=200 for user: alice (region="ca central", retry=1)."#;
+
+        let log_entry = parser.parse_log(log_line, "svc", "pod", "ns").unwrap().unwrap();
+
+        assert_eq!(log_entry.message, r#"GET /api took 34ms status=200 for user: alice (region="ca central", retry=1)."#);
+        assert_eq!(log_entry.attributes["status"], "200");
+        assert_eq!(log_entry.attributes["user"], "alice");
+        assert_eq!(log_entry.attributes["region"], "ca central");
+        assert_eq!(log_entry.attributes["retry"], "1");
+        assert!(!log_entry.attributes.contains_key("GET"));
+
+        let log_line = "INFO: calling https://api.example.com/v1/orders?id=7 upstream: http://cache:6379 attempt=2";
+        let log_entry = parser.parse_log(log_line, "svc", "pod", "ns").unwrap().unwrap();
+        assert!(!log_entry.attributes.contains_key("https"));
+        assert!(!log_entry.attributes.contains_key("http"));
+        assert!(!log_entry.attributes.contains_key("id"));
+        assert_eq!(log_entry.attributes["upstream"], "http://cache:6379");
+        assert_eq!(log_entry.attributes["attempt"], "2");
+    }
+
+    #[test]
+    fn test_key_value_custom_delimiters_and_limits() {
+        let parser = KeyValueParser::new(Box::new(RegexLogParser::new(false)), &["=>".to_string()]);
+
+        let log_entry = parser.parse_log("WARN: cache=>miss key=ignored", "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.attributes["cache"], "miss");
+        assert!(!log_entry.attributes.contains_key("key"));
+
+        let noisy: String = (0..1000).map(|i| format!("k{}=v{} ", i, i)).collect();
+        let log_entry = parser.parse_log(&noisy.replace('=', "=>"), "svc", "pod", "ns").unwrap().unwrap();
+        let extracted = log_entry.attributes.keys().filter(|k| k.starts_with('k')).count();
+        assert_eq!(extracted, MAX_KEY_VALUE_PAIRS);
+    }
+
+    #[test]
+    fn test_tracing_format() {
+        let parser = LogParserFactory::create_parser_with_config("tracing", ParserConfig::default());
+        let now = chrono::Utc::now();
+        let line = format!(
+            "{}  WARN checkout::payments: card declined order_id=42 reason=\"insufficient funds\" trace_id=4bf92f3577b34da6a3ce929d0e0e4736",
+            now.format("%Y-%m-%dT%H:%M:%S%.6fZ")
+        );
+
+        let log_entry = parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.level, LogLevel::Warn);
+        assert_eq!(log_entry.message, "card declined");
+        assert_eq!(log_entry.attributes["code.namespace"], "checkout::payments");
+        assert_eq!(log_entry.attributes["order_id"], "42");
+        assert_eq!(log_entry.attributes["reason"], "insufficient funds");
+        assert_eq!(log_entry.trace_id.as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(log_entry.timestamp, now.timestamp() as u64);
+
+        // env_logger, with ANSI colors
+        let line = format!("[{} \x1b[31mERROR\x1b[0m checkout] payment service unavailable", now.format("%Y-%m-%dT%H:%M:%SZ"));
+        let log_entry = parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!(log_entry.level, LogLevel::Error);
+        assert_eq!(log_entry.message, "payment service unavailable");
+        assert_eq!(log_entry.attributes["code.namespace"], "checkout");
+
+        // A message of only fields is kept whole; other lines use the regex patterns
+        let line = format!("{} INFO checkout: user_id=7", now.format("%Y-%m-%dT%H:%M:%SZ"));
+        assert_eq!(parser.parse_log(&line, "svc", "pod", "ns").unwrap().unwrap().message, "user_id=7");
+        let log_entry = parser.parse_log("ERROR: thread 'main' panicked", "svc", "pod", "ns").unwrap().unwrap();
+        assert_eq!((log_entry.level, log_entry.message.as_str()), (LogLevel::Error, "thread 'main' panicked"));
+    }
+
+    #[test]
+    fn test_split_json_objects() {
+        let line = r#"{"message": "a {b}"}{"message": "c"} {"message": "d"}"#;
+        assert_eq!(
+            split_json_objects(line, 16),
+            Some(vec![r#"{"message": "a {b}"}"#, r#"{"message": "c"}"#, r#"{"message": "d"}"#])
+        );
+
+        // Past the cap the rest stays together
+        assert_eq!(
+            split_json_objects(line, 2),
+            Some(vec![r#"{"message": "a {b}"}"#, r#"{"message": "c"} {"message": "d"}"#])
+        );
+
+        // An object cut short is kept as the last one
+        assert_eq!(split_json_objects(r#"{"a":1}{"b":"#, 16), Some(vec![r#"{"a":1}"#, r#"{"b":"#]));
+
+        // Single objects, trailing text and other values are left alone
+        assert_eq!(split_json_objects(r#"{"message": "one"}"#, 16), None);
+        assert_eq!(split_json_objects(r#"{"a":1} done"#, 16), None);
+        assert_eq!(split_json_objects(r#"{"a":1} 42"#, 16), None);
+        assert_eq!(split_json_objects("INFO: {} {}", 16), None);
+    }
 }
@@ -1,18 +1,147 @@
 //! OpenTelemetry Sidecar Collector Binary
 
+use clap::{Parser, Subcommand};
+use opentel_collector::self_logs::{self, SelfLogLayer};
 use opentel_collector::{Config, SidecarCollector, Result};
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{info, error};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, Registry};
+
+/// OpenTelemetry-compatible sidecar collector
+///
+/// Configuration is layered: command-line flags override environment
+/// variables, which override the config file, which overrides defaults.
+#[derive(Debug, Parser)]
+#[command(name = "opentel_collector", version, about)]
+struct Cli {
+    /// Path to a JSON configuration file
+    #[arg(long, global = true)]
+    config_file: Option<PathBuf>,
+
+    /// Name of the service being monitored
+    #[arg(long, global = true)]
+    service_name: Option<String>,
+
+    /// Kubernetes pod name
+    #[arg(long, global = true)]
+    pod_name: Option<String>,
+
+    /// Kubernetes namespace
+    #[arg(long, global = true)]
+    namespace: Option<String>,
+
+    /// URL of the telemetry gateway
+    #[arg(long, global = true)]
+    gateway_url: Option<String>,
+
+    /// Comma-separated log file paths
+    #[arg(long, global = true, value_delimiter = ',')]
+    log_paths: Option<Vec<String>>,
+
+    /// Number of entries per batch
+    #[arg(long, global = true)]
+    batch_size: Option<usize>,
+
+    /// Forced flush interval in seconds
+    #[arg(long, global = true)]
+    flush_interval_seconds: Option<u64>,
+
+    /// Parse and buffer logs but log batches instead of sending them
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Load and validate the configuration, then exit
+    ValidateConfig,
+
+    /// Print the effective configuration as JSON with credentials masked, then exit
+    PrintConfig,
+
+    /// Ingest an existing log file once, send it to the gateway, then exit
+    ReplayFile {
+        /// Log file to replay
+        path: PathBuf,
+    },
+
+    /// Resend the batches in a dead-letter file and print the result of each, then exit
+    ReplayDeadLetter {
+        /// Dead-letter file with one JSON batch per line
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Remove delivered batches from the file, deleting it once all are delivered
+        #[arg(long)]
+        delete_on_success: bool,
+    },
+}
+
+impl Cli {
+    /// Apply command-line overrides on top of the loaded configuration
+    fn apply(&self, config: &mut Config) {
+        if let Some(service_name) = &self.service_name {
+            config.service_name = service_name.clone();
+        }
+
+        if let Some(pod_name) = &self.pod_name {
+            config.pod_name = pod_name.clone();
+        }
+
+        if let Some(namespace) = &self.namespace {
+            config.namespace = namespace.clone();
+        }
+
+        if let Some(gateway_url) = &self.gateway_url {
+            config.gateway_url = gateway_url.clone();
+        }
+
+        if let Some(log_paths) = &self.log_paths {
+            config.log_paths = log_paths.iter().map(|s| s.trim().to_string()).collect();
+        }
+
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+
+        if let Some(seconds) = self.flush_interval_seconds {
+            config.flush_interval = Duration::from_secs(seconds);
+        }
+
+        if self.dry_run {
+            config.dry_run = true;
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    initialize_tracing();
+    let cli = Cli::parse();
 
-    info!("Starting OpenTelemetry Sidecar Collector v{}", env!("CARGO_PKG_VERSION"));
+    // Initialize tracing
+    let self_log_handle = initialize_tracing();
 
     // Load configuration
-    let config = Config::from_env();
+    let mut config = match Config::load(cli.config_file.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    cli.apply(&mut config);
+
+    // Printed before validation so misconfiguration can be inspected
+    if let Some(Command::PrintConfig) = cli.command {
+        println!("{}", config.to_redacted_json()?);
+        return Ok(());
+    }
+
+    info!("Starting OpenTelemetry Sidecar Collector v{}", env!("CARGO_PKG_VERSION"));
 
     // Validate configuration
     if let Err(e) = config.validate() {
@@ -20,16 +149,60 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    if let Some(Command::ValidateConfig) = cli.command {
+        info!("Configuration is valid");
+        return Ok(());
+    }
+
+    if let Some(Command::ReplayFile { path }) = &cli.command {
+        let collector = SidecarCollector::new(config)?;
+        let report = collector.replay_file(&path.to_string_lossy()).await?;
+
+        if report.batches_failed > 0 || report.batches_expired > 0 {
+            error!(
+                "{} batches failed to send and {} expired during replay",
+                report.batches_failed, report.batches_expired
+            );
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    if let Some(Command::ReplayDeadLetter { file, delete_on_success }) = &cli.command {
+        let collector = SidecarCollector::new(config)?;
+        let report = collector
+            .replay_dead_letter(&file.to_string_lossy(), *delete_on_success)
+            .await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+
+        if report.failed() > 0 {
+            error!("{} batches were not delivered and remain in {}", report.failed(), file.display());
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
     info!(
         "Collector configuration - Service: {}, Pod: {}, Namespace: {}, Gateway: {}",
         config.service_name,
         config.pod_name,
         config.namespace,
-        config.gateway_url
+        config.redacted().gateway_url
     );
 
     // Create and start collector
-    let collector = SidecarCollector::new(config)?;
+    let forward_self_logs = config.forward_self_logs;
+    let mut collector = SidecarCollector::new(config)?;
+    if forward_self_logs {
+        let (self_log_layer, self_log_receiver) = self_logs::channel();
+        if let Err(e) = self_log_handle.reload(Some(self_log_layer)) {
+            error!("Failed to enable self log forwarding: {}", e);
+            std::process::exit(1);
+        }
+        collector = collector.with_self_logs(self_log_receiver);
+    }
 
     if let Err(e) = collector.start().await {
         error!("Collector failed: {}", e);
@@ -39,8 +212,10 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Initialize structured logging
-fn initialize_tracing() {
+/// Initialize structured logging, returning the handle that installs the
+/// self log layer once the configuration asks for the collector's own
+/// events to be forwarded; until then no layer sees them
+fn initialize_tracing() -> reload::Handle<Option<SelfLogLayer>, Registry> {
     let log_level = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
 
     let fmt_layer = tracing_subscriber::fmt::layer()
@@ -55,8 +230,13 @@ fn initialize_tracing() {
         .or_else(|_| tracing_subscriber::EnvFilter::try_new(&log_level))
         .unwrap();
 
+    let (self_log_layer, self_log_handle) = reload::Layer::new(None);
+
     tracing_subscriber::registry()
+        .with(self_log_layer)
         .with(filter_layer)
         .with(fmt_layer)
         .init();
+
+    self_log_handle
 }
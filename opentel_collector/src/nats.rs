@@ -0,0 +1,186 @@
+//! NATS transport publishing batches to a subject (requires the `nats` feature)
+
+use crate::config::{redact_url, Config};
+use crate::errors::{CollectorError, Result};
+use crate::telemetry::TelemetryBatch;
+use crate::transport::{AttemptError, PayloadSize, RetryPolicy, Transport};
+use async_nats::HeaderMap;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+/// Publishes each batch as JSON to a NATS subject, with `Collector-Id` and
+/// `Batch-Id` headers. The connection is made on first use so a server that
+/// is down at startup only delays delivery, and failed publishes are retried
+/// like HTTP sends.
+#[derive(Debug)]
+pub struct NatsTransport {
+    url: String,
+    subject: String,
+    timeout: Duration,
+    connect_timeout: Duration,
+    max_retries: u32,
+    retry_backoff_ms: u64,
+    max_batch_send_age: Duration,
+    client: OnceCell<async_nats::Client>,
+}
+
+impl NatsTransport {
+    /// Create a NATS transport; nothing is connected until the first send
+    pub fn new(url: String, subject: String, publish_timeout: Duration, max_retries: u32, retry_backoff_ms: u64) -> Self {
+        Self {
+            url,
+            subject,
+            timeout: publish_timeout,
+            connect_timeout: publish_timeout,
+            max_retries,
+            retry_backoff_ms,
+            max_batch_send_age: Duration::ZERO,
+            client: OnceCell::new(),
+        }
+    }
+
+    /// Create a NATS transport from the collector configuration
+    pub fn from_config(config: &Config) -> Self {
+        let transport = Self::new(
+            config.nats_url.clone(),
+            config.nats_subject.clone(),
+            config.request_timeout,
+            config.max_retries,
+            config.retry_backoff_ms,
+        )
+        .with_max_batch_send_age(config.max_batch_send_age);
+
+        if config.connect_timeout.is_zero() {
+            transport
+        } else {
+            transport.with_connect_timeout(config.connect_timeout)
+        }
+    }
+
+    /// Limit connecting to the server separately from publishing
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Stop retrying batches drained longer ago than `max_age`; zero retries
+    /// regardless of age
+    pub fn with_max_batch_send_age(mut self, max_age: Duration) -> Self {
+        self.max_batch_send_age = max_age;
+        self
+    }
+
+    /// The connected client, connecting first if needed
+    async fn client(&self) -> Result<&async_nats::Client> {
+        self.client
+            .get_or_try_init(|| async {
+                let client = async_nats::ConnectOptions::new()
+                    .name(format!("opentel_collector/{}", env!("CARGO_PKG_VERSION")))
+                    .connection_timeout(self.connect_timeout)
+                    .connect(self.url.as_str())
+                    .await
+                    .map_err(|e| {
+                        CollectorError::Transport(format!("Failed to connect to NATS at {}: {}", redact_url(&self.url), e))
+                    })?;
+
+                info!("Connected to NATS at {}", redact_url(&self.url));
+                Ok(client)
+            })
+            .await
+    }
+
+    /// Publish a telemetry batch to the configured subject
+    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let payload = Bytes::from(serde_json::to_vec(&batch)?);
+
+        debug!(
+            "Publishing batch {} with {} logs and {} spans to {}",
+            batch.metadata.batch_id,
+            batch.logs.len(),
+            batch.spans.len(),
+            self.subject
+        );
+
+        let retry_policy = RetryPolicy {
+            max_retries: self.max_retries,
+            retry_backoff_ms: self.retry_backoff_ms,
+            max_batch_send_age: self.max_batch_send_age,
+        };
+        let attempts = retry_policy
+            .run(&batch, "publish", || async {
+                self.publish_attempt(&batch, payload.clone())
+                    .await
+                    .map_err(AttemptError::Retryable)
+            })
+            .await?;
+
+        debug!(
+            "Published batch {} (attempt {}, {} bytes)",
+            batch.metadata.batch_id,
+            attempts,
+            payload.len()
+        );
+        Ok(PayloadSize::uncompressed(payload.len()))
+    }
+
+    /// Single publish, flushed so a lost connection is reported rather than
+    /// the batch waiting in the client's outgoing queue
+    async fn publish_attempt(&self, batch: &TelemetryBatch, payload: Bytes) -> Result<()> {
+        let client = self.client().await?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Collector-Id", batch.metadata.collector_id.as_str());
+        headers.insert("Batch-Id", batch.metadata.batch_id.as_str());
+
+        client
+            .publish_with_headers(self.subject.clone(), headers, payload)
+            .await
+            .map_err(|e| CollectorError::Transport(format!("NATS publish failed: {}", e)))?;
+
+        timeout(self.timeout, client.flush())
+            .await
+            .map_err(|_| CollectorError::Transport("NATS flush timeout".to_string()))?
+            .map_err(|e| CollectorError::Transport(format!("NATS flush failed: {}", e)))
+    }
+
+    /// Test connectivity to the NATS server
+    pub async fn test_connectivity(&self) -> bool {
+        let result = match self.client().await {
+            Ok(client) => timeout(self.timeout, client.flush())
+                .await
+                .map_err(|_| "flush timeout".to_string())
+                .and_then(|flushed| flushed.map_err(|e| e.to_string())),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(()) => {
+                info!(
+                    "NATS connectivity test successful: {} on {}",
+                    redact_url(&self.url),
+                    self.subject
+                );
+                true
+            }
+            Err(e) => {
+                warn!("NATS connectivity test failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for NatsTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        NatsTransport::send_batch(self, batch).await
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        NatsTransport::test_connectivity(self).await
+    }
+}
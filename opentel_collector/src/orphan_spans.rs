@@ -0,0 +1,243 @@
+//! Detection of spans whose parent within the same service never arrived
+
+use crate::telemetry::TraceSpan;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Tag set to `true` on spans whose same-service parent was never seen
+pub const ORPHAN_TAG: &str = "span.orphan";
+
+/// Tag holding the span kind, compared against the entry kinds
+pub const SPAN_KIND_TAG: &str = "span.kind";
+
+/// Span ids seen for a trace and the spans still waiting for their parent
+#[derive(Debug, Default)]
+struct TrackedTrace {
+    span_ids: HashSet<String>,
+    waiting: Vec<TraceSpan>,
+    /// Whether a root span or a span of an entry kind was seen, i.e. where
+    /// the trace entered this service
+    has_entry: bool,
+}
+
+/// Tracks span ids per `trace_id` for a bounded window to find spans whose
+/// parent is missing. A span whose parent has not been seen yet is held
+/// until the parent arrives or the window passes. Parents of root spans and
+/// of spans whose `span.kind` is an entry kind (`server`, `consumer`) live
+/// in another service and are not waited for.
+///
+/// When the window passes, the spans still waiting are tagged as orphans,
+/// except that a trace without a recognised entry span treats its earliest
+/// waiting span as the entry, since spans without a kind give no other way
+/// to tell a cross-service parent from a lost one. When more than
+/// `max_traces` traces are tracked the oldest is decided early.
+#[derive(Debug)]
+pub struct OrphanDetector {
+    window: Duration,
+    max_traces: usize,
+    entry_kinds: Vec<String>,
+    order: VecDeque<(Instant, String)>,
+    traces: HashMap<String, TrackedTrace>,
+}
+
+impl OrphanDetector {
+    pub fn new(window: Duration, max_traces: usize, entry_kinds: &[String]) -> Self {
+        Self {
+            window,
+            max_traces: max_traces.max(1),
+            entry_kinds: entry_kinds.iter().map(|kind| kind.to_lowercase()).collect(),
+            order: VecDeque::new(),
+            traces: HashMap::new(),
+        }
+    }
+
+    /// Track a span, returning the spans ready to buffer: the span itself
+    /// unless it waits for its parent, spans that were waiting for it, and
+    /// spans of traces decided early to stay within `max_traces`
+    pub fn add_span(&mut self, span: TraceSpan, now: Instant) -> Vec<TraceSpan> {
+        let is_entry = self.is_entry(&span);
+        if !self.traces.contains_key(&span.trace_id) {
+            self.order.push_back((now, span.trace_id.clone()));
+        }
+        let trace = self.traces.entry(span.trace_id.clone()).or_default();
+        trace.span_ids.insert(span.span_id.clone());
+        trace.has_entry |= is_entry;
+
+        // Release children that were waiting for this span
+        let (mut ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut trace.waiting)
+            .into_iter()
+            .partition(|waiting| waiting.parent_span_id.as_deref() == Some(span.span_id.as_str()));
+        trace.waiting = waiting;
+
+        let parent_seen = span
+            .parent_span_id
+            .as_ref()
+            .is_none_or(|parent| trace.span_ids.contains(parent));
+        if is_entry || parent_seen {
+            ready.push(span);
+        } else {
+            trace.waiting.push(span);
+        }
+
+        while self.order.len() > self.max_traces {
+            self.decide_oldest(&mut ready);
+        }
+        ready
+    }
+
+    /// Decide the traces whose window has passed
+    pub fn expire(&mut self, now: Instant) -> Vec<TraceSpan> {
+        let mut decided = Vec::new();
+        while let Some((first_seen, _)) = self.order.front() {
+            if now.duration_since(*first_seen) <= self.window {
+                break;
+            }
+            self.decide_oldest(&mut decided);
+        }
+        decided
+    }
+
+    /// Decide every tracked trace, e.g. on shutdown
+    pub fn drain(&mut self) -> Vec<TraceSpan> {
+        let mut decided = Vec::new();
+        while !self.order.is_empty() {
+            self.decide_oldest(&mut decided);
+        }
+        decided
+    }
+
+    /// Number of spans waiting for their parent
+    pub fn pending(&self) -> usize {
+        self.traces.values().map(|trace| trace.waiting.len()).sum()
+    }
+
+    /// Copies of the spans waiting for their parent, oldest trace first,
+    /// e.g. for a checkpoint
+    pub fn snapshot(&self) -> Vec<TraceSpan> {
+        self.order
+            .iter()
+            .filter_map(|(_, trace_id)| self.traces.get(trace_id))
+            .flat_map(|trace| trace.waiting.iter().cloned())
+            .collect()
+    }
+
+    fn is_entry(&self, span: &TraceSpan) -> bool {
+        span.parent_span_id.is_none()
+            || span
+                .tags
+                .get(SPAN_KIND_TAG)
+                .is_some_and(|kind| self.entry_kinds.contains(&kind.to_lowercase()))
+    }
+
+    fn decide_oldest(&mut self, decided: &mut Vec<TraceSpan>) {
+        let Some((_, trace_id)) = self.order.pop_front() else {
+            return;
+        };
+        let Some(mut trace) = self.traces.remove(&trace_id) else {
+            return;
+        };
+
+        if !trace.has_entry
+            && let Some(entry) = trace
+                .waiting
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, span)| span.start_time)
+                .map(|(index, _)| index)
+        {
+            decided.push(trace.waiting.remove(entry));
+        }
+
+        for mut span in trace.waiting {
+            span.tags.insert(ORPHAN_TAG.to_string(), "true".to_string());
+            decided.push(span);
+        }
+    }
+}
+
+/// Whether a span was tagged as an orphan
+pub fn is_orphan(span: &TraceSpan) -> bool {
+    span.tags.get(ORPHAN_TAG).is_some_and(|value| value == "true")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(span_id: &str, parent: Option<&str>, start_time: u64) -> TraceSpan {
+        let mut span = TraceSpan::new(
+            "trace-1".to_string(),
+            span_id.to_string(),
+            "db.query".to_string(),
+            "test-service".to_string(),
+        );
+        span.parent_span_id = parent.map(str::to_string);
+        span.start_time = start_time;
+        span
+    }
+
+    fn detector() -> OrphanDetector {
+        OrphanDetector::new(Duration::from_secs(2), 100, &["server".to_string(), "consumer".to_string()])
+    }
+
+    fn ids(spans: &[TraceSpan]) -> Vec<&str> {
+        spans.iter().map(|span| span.span_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_child_waits_for_parent() {
+        let mut detector = detector();
+        let start = Instant::now();
+
+        // Children usually finish, and are logged, before their parent
+        assert!(detector.add_span(span("child", Some("parent"), 20), start).is_empty());
+        let released = detector.add_span(span("parent", None, 10), start);
+
+        assert_eq!(ids(&released), vec!["child", "parent"]);
+        assert!(released.iter().all(|span| !is_orphan(span)));
+        assert_eq!(detector.pending(), 0);
+    }
+
+    #[test]
+    fn test_missing_same_service_parent_flagged() {
+        let mut detector = detector();
+        let start = Instant::now();
+
+        let entry = span("entry", Some("remote"), 10).with_tag(SPAN_KIND_TAG.to_string(), "SERVER".to_string());
+        assert_eq!(ids(&detector.add_span(entry, start)), vec!["entry"]);
+        assert!(detector.add_span(span("lost-child", Some("lost"), 30), start).is_empty());
+
+        assert!(detector.expire(start + Duration::from_secs(1)).is_empty());
+        let decided = detector.expire(start + Duration::from_secs(3));
+        assert_eq!(ids(&decided), vec!["lost-child"]);
+        assert!(is_orphan(&decided[0]));
+    }
+
+    #[test]
+    fn test_earliest_span_without_kind_is_entry() {
+        let mut detector = detector();
+        let start = Instant::now();
+
+        detector.add_span(span("lost-child", Some("lost"), 30), start);
+        detector.add_span(span("entry", Some("remote"), 10), start);
+
+        let decided = detector.drain();
+        let orphans: Vec<_> = decided.iter().filter(|span| is_orphan(span)).collect();
+        assert_eq!(decided.len(), 2);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].span_id, "lost-child");
+    }
+
+    #[test]
+    fn test_bounded_traces_decides_oldest() {
+        let mut detector = OrphanDetector::new(Duration::from_secs(60), 1, &[]);
+        let now = Instant::now();
+
+        detector.add_span(span("a", Some("remote"), 10), now);
+        let mut other = span("b", Some("remote"), 10);
+        other.trace_id = "trace-2".to_string();
+
+        assert_eq!(ids(&detector.add_span(other, now)), vec!["a"]);
+        assert_eq!(detector.pending(), 1);
+    }
+}
@@ -0,0 +1,287 @@
+//! OTLP/HTTP JSON encoding of telemetry batches
+
+use crate::telemetry::{LogEntry, MetricPoint, SpanStatus, TelemetryBatch, TraceSpan};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+
+/// OTLP/HTTP path for log export requests
+pub const LOGS_PATH: &str = "/v1/logs";
+/// OTLP/HTTP path for trace export requests
+pub const TRACES_PATH: &str = "/v1/traces";
+/// OTLP/HTTP path for metric export requests
+pub const METRICS_PATH: &str = "/v1/metrics";
+
+pub(crate) const NANOS_PER_SECOND: u64 = 1_000_000_000;
+pub(crate) const NANOS_PER_MILLI: u64 = 1_000_000;
+
+/// Unix nanoseconds of a Unix timestamp in seconds. A timestamp too far in
+/// the future for a `u64` of nanoseconds, e.g. one parsed from a corrupt
+/// line, saturates rather than overflowing.
+pub(crate) fn unix_nanos(seconds: u64) -> u64 {
+    seconds.saturating_mul(NANOS_PER_SECOND)
+}
+
+/// Export requests for every signal in the batch that has data, paired with
+/// the OTLP/HTTP path each is posted to
+pub fn export_requests(batch: &TelemetryBatch) -> Vec<(&'static str, Value)> {
+    let mut requests = Vec::new();
+    if !batch.logs.is_empty() {
+        requests.push((LOGS_PATH, logs_request(batch)));
+    }
+    if !batch.spans.is_empty() {
+        requests.push((TRACES_PATH, traces_request(batch)));
+    }
+    if !batch.metrics.is_empty() {
+        requests.push((METRICS_PATH, metrics_request(batch)));
+    }
+    requests
+}
+
+/// `ExportLogsServiceRequest` with one resource per service
+pub fn logs_request(batch: &TelemetryBatch) -> Value {
+    let resource_logs: Vec<Value> = group_by_service(&batch.logs, |log| &log.service_name)
+        .into_iter()
+        .map(|(service_name, logs)| {
+            json!({
+                "resource": resource(batch, service_name),
+                "scopeLogs": [{
+                    "scope": scope(),
+                    "logRecords": logs.into_iter().map(log_record).collect::<Vec<_>>(),
+                }],
+            })
+        })
+        .collect();
+
+    json!({ "resourceLogs": resource_logs })
+}
+
+/// `ExportTraceServiceRequest` with one resource per service
+pub fn traces_request(batch: &TelemetryBatch) -> Value {
+    let resource_spans: Vec<Value> = group_by_service(&batch.spans, |span| &span.service_name)
+        .into_iter()
+        .map(|(service_name, spans)| {
+            json!({
+                "resource": resource(batch, service_name),
+                "scopeSpans": [{
+                    "scope": scope(),
+                    "spans": spans.into_iter().map(span).collect::<Vec<_>>(),
+                }],
+            })
+        })
+        .collect();
+
+    json!({ "resourceSpans": resource_spans })
+}
+
+/// `ExportMetricsServiceRequest` with every point as a gauge
+pub fn metrics_request(batch: &TelemetryBatch) -> Value {
+    let resource_metrics: Vec<Value> = group_by_service(&batch.metrics, |point| &point.service_name)
+        .into_iter()
+        .map(|(service_name, points)| {
+            json!({
+                "resource": resource(batch, service_name),
+                "scopeMetrics": [{
+                    "scope": scope(),
+                    "metrics": points.into_iter().map(gauge).collect::<Vec<_>>(),
+                }],
+            })
+        })
+        .collect();
+
+    json!({ "resourceMetrics": resource_metrics })
+}
+
+fn log_record(log: &LogEntry) -> Value {
+    let mut record = json!({
+        "timeUnixNano": unix_nanos(log.timestamp).to_string(),
+        "severityNumber": severity_number(log),
+        "severityText": log.level.to_string(),
+        "body": { "stringValue": log.message },
+        "attributes": attributes(&log.attributes),
+    });
+    if let Some(trace_id) = &log.trace_id {
+        record["traceId"] = json!(trace_id);
+    }
+    if let Some(span_id) = &log.span_id {
+        record["spanId"] = json!(span_id);
+    }
+    record
+}
+
+/// OTLP severity number of a log, from its level unless it carried one
+pub(crate) fn severity_number(log: &LogEntry) -> u8 {
+    if log.severity_number == 0 {
+        log.level.severity_number()
+    } else {
+        log.severity_number
+    }
+}
+
+/// OTLP start and end of a span in Unix nanoseconds
+pub(crate) fn span_times(span: &TraceSpan) -> (u64, u64) {
+    // Start and end are whole seconds; the duration keeps millisecond precision
+    let start = unix_nanos(span.start_time);
+    (start, start.saturating_add(span.duration_ms.saturating_mul(NANOS_PER_MILLI)))
+}
+
+/// OTLP status code of a span: STATUS_CODE_UNSET = 0, STATUS_CODE_OK = 1,
+/// STATUS_CODE_ERROR = 2
+pub(crate) fn status_code(status: &SpanStatus) -> i32 {
+    match status {
+        SpanStatus::Ok => 1,
+        SpanStatus::Error | SpanStatus::Timeout => 2,
+        SpanStatus::Cancelled => 0,
+    }
+}
+
+fn span(span: &TraceSpan) -> Value {
+    let (start, end) = span_times(span);
+    let code = status_code(&span.status);
+
+    let mut encoded = json!({
+        "traceId": span.trace_id,
+        "spanId": span.span_id,
+        "name": span.operation_name,
+        "startTimeUnixNano": start.to_string(),
+        "endTimeUnixNano": end.to_string(),
+        "attributes": attributes(&span.tags),
+        "status": { "code": code, "message": span.status.to_string() },
+    });
+    if let Some(parent_span_id) = &span.parent_span_id {
+        encoded["parentSpanId"] = json!(parent_span_id);
+    }
+    encoded
+}
+
+fn gauge(point: &MetricPoint) -> Value {
+    json!({
+        "name": point.name,
+        "gauge": {
+            "dataPoints": [{
+                "timeUnixNano": unix_nanos(point.timestamp).to_string(),
+                "asDouble": point.value,
+                "attributes": attributes(&point.attributes),
+            }],
+        },
+    })
+}
+
+fn resource(batch: &TelemetryBatch, service_name: &str) -> Value {
+    json!({ "attributes": attributes(&resource_attributes(batch, service_name)) })
+}
+
+/// Batch resource attributes plus the service and source pod
+pub(crate) fn resource_attributes(batch: &TelemetryBatch, service_name: &str) -> HashMap<String, String> {
+    let mut resource_attributes = batch.metadata.resource_attributes.clone();
+    resource_attributes.insert("service.name".to_string(), service_name.to_string());
+    resource_attributes.insert("k8s.pod.name".to_string(), batch.metadata.source_pod.clone());
+    resource_attributes.insert("k8s.namespace.name".to_string(), batch.metadata.source_namespace.clone());
+    resource_attributes
+}
+
+fn scope() -> Value {
+    json!({ "name": "opentel_collector", "version": env!("CARGO_PKG_VERSION") })
+}
+
+/// OTLP `KeyValue` list, sorted by key so encoding is deterministic
+fn attributes(attributes: &HashMap<String, String>) -> Vec<Value> {
+    let sorted: BTreeMap<_, _> = attributes.iter().collect();
+    sorted
+        .into_iter()
+        .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+        .collect()
+}
+
+/// Items grouped by service name, in service name order
+pub(crate) fn group_by_service<T>(items: &[T], service_name: impl Fn(&T) -> &String) -> BTreeMap<&str, Vec<&T>> {
+    let mut groups: BTreeMap<&str, Vec<&T>> = BTreeMap::new();
+    for item in items {
+        groups.entry(service_name(item).as_str()).or_default().push(item);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::LogLevel;
+
+    fn batch() -> TelemetryBatch {
+        let log = LogEntry::new(
+            LogLevel::Error,
+            "payment failed".to_string(),
+            "checkout".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        )
+        .with_trace_context("4bf92f3577b34da6a3ce929d0e0e4736".to_string(), "00f067aa0ba902b7".to_string())
+        .with_attribute("order_id".to_string(), "o-1".to_string());
+
+        let mut span = TraceSpan::new(
+            "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            "00f067aa0ba902b7".to_string(),
+            "POST /pay".to_string(),
+            "checkout".to_string(),
+        )
+        .with_status(SpanStatus::Timeout)
+        .set_duration_ms(1500);
+        span.start_time = 1_700_000_000;
+
+        TelemetryBatch::new(
+            vec![log],
+            vec![span],
+            "collector-1".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_export_requests_per_signal() {
+        let paths: Vec<_> = export_requests(&batch()).into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, vec![LOGS_PATH, TRACES_PATH]);
+    }
+
+    #[test]
+    fn test_logs_request() {
+        let request = logs_request(&batch());
+        let resource_logs = &request["resourceLogs"][0];
+
+        let resource = &resource_logs["resource"]["attributes"];
+        assert!(resource.as_array().unwrap().contains(&json!({
+            "key": "service.name", "value": { "stringValue": "checkout" }
+        })));
+
+        let record = &resource_logs["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["severityNumber"], 17);
+        assert_eq!(record["severityText"], "ERROR");
+        assert_eq!(record["body"]["stringValue"], "payment failed");
+        assert_eq!(record["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(record["attributes"][0]["key"], "order_id");
+    }
+
+    #[test]
+    fn test_traces_request() {
+        let request = traces_request(&batch());
+        let span = &request["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+
+        assert_eq!(span["name"], "POST /pay");
+        assert_eq!(span["startTimeUnixNano"], "1700000000000000000");
+        assert_eq!(span["endTimeUnixNano"], "1700000001500000000");
+        assert_eq!(span["status"]["code"], 2);
+        assert!(span.get("parentSpanId").is_none());
+    }
+
+    #[test]
+    fn test_far_future_timestamps_saturate() {
+        let mut batch = batch();
+        batch.logs[0].timestamp = u64::MAX / 1000;
+        batch.spans[0].start_time = u64::MAX;
+
+        let record = &logs_request(&batch)["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["timeUnixNano"], u64::MAX.to_string());
+        let span = &traces_request(&batch)["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["startTimeUnixNano"], u64::MAX.to_string());
+        assert_eq!(span["endTimeUnixNano"], u64::MAX.to_string());
+    }
+}
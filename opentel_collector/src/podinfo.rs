@@ -0,0 +1,168 @@
+//! Kubernetes downward API metadata loading
+//!
+//! The downward API can project pod labels and annotations into files such as
+//! `/etc/podinfo/labels`, one `key="value"` pair per line. These are merged into
+//! batch-level resource attributes so telemetry can be tagged with ownership and
+//! version labels without threading individual environment variables.
+//!
+//! Host and process attributes following the OpenTelemetry semantic
+//! conventions can be added as well, for correlating telemetry with node-level
+//! issues.
+
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Load and merge resource attributes from a set of downward API files.
+///
+/// Missing files are skipped, and later files take precedence on key collisions.
+pub fn load_resource_attributes(paths: &[String]) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+
+    for path in paths {
+        if !Path::new(path).exists() {
+            debug!("Pod info file {} not present, skipping", path);
+            continue;
+        }
+
+        match std::fs::read_to_string(path) {
+            Ok(content) => attributes.extend(parse_downward_api(&content)),
+            Err(e) => warn!("Failed to read pod info file {}: {}", path, e),
+        }
+    }
+
+    attributes
+}
+
+/// Attributes describing the host and the collector process: `host.name`,
+/// `process.pid`, `process.creation.time` (now, as RFC 3339) and
+/// `process.runtime.name`.
+///
+/// Inside a pod the kernel hostname is the pod name, so `host.name` prefers
+/// the node name from `NODE_NAME`, which the downward API can set from
+/// `spec.nodeName`.
+pub fn host_attributes() -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+
+    if let Some(host_name) = node_name().or_else(kernel_hostname) {
+        attributes.insert("host.name".to_string(), host_name);
+    }
+    attributes.insert("process.pid".to_string(), std::process::id().to_string());
+    attributes.insert(
+        "process.creation.time".to_string(),
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    attributes.insert("process.runtime.name".to_string(), "rust".to_string());
+
+    attributes
+}
+
+fn node_name() -> Option<String> {
+    std::env::var("NODE_NAME").ok().filter(|name| !name.trim().is_empty())
+}
+
+fn kernel_hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Parse the contents of a downward API labels/annotations file
+pub fn parse_downward_api(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() {
+                return None;
+            }
+
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+
+            Some((key.to_string(), unquote(value.trim())))
+        })
+        .collect()
+}
+
+/// Strip surrounding quotes and resolve the escapes the downward API emits
+fn unquote(value: &str) -> String {
+    let inner = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return value.to_string(),
+    };
+
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_labels() {
+        let content = "app=\"nginx\"\napp.kubernetes.io/version=\"1.2.3\"\nteam=\"sre\"\n";
+        let attributes = parse_downward_api(content);
+
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes["app"], "nginx");
+        assert_eq!(attributes["app.kubernetes.io/version"], "1.2.3");
+        assert_eq!(attributes["team"], "sre");
+    }
+
+    #[test]
+    fn test_parse_escaped_values() {
+        let content = r#"description="line one\nline \"two\"""#;
+        let attributes = parse_downward_api(content);
+
+        assert_eq!(attributes["description"], "line one\nline \"two\"");
+    }
+
+    #[test]
+    fn test_host_attributes() {
+        let attributes = host_attributes();
+
+        assert_eq!(attributes["process.pid"], std::process::id().to_string());
+        assert_eq!(attributes["process.runtime.name"], "rust");
+        assert!(chrono::DateTime::parse_from_rfc3339(&attributes["process.creation.time"]).is_ok());
+    }
+
+    #[test]
+    fn test_missing_file_is_ignored() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "owner=\"platform\"").unwrap();
+
+        let paths = vec![
+            "/nonexistent/podinfo/labels".to_string(),
+            file.path().to_string_lossy().to_string(),
+        ];
+        let attributes = load_resource_attributes(&paths);
+
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes["owner"], "platform");
+    }
+}
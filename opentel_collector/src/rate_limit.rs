@@ -0,0 +1,121 @@
+//! Per-level rate limiting of parsed log entries
+
+use crate::config::Config;
+use crate::telemetry::LogLevel;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Refills at `rate` tokens per second up to one second's worth
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            refilled_at: now,
+        }
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Token buckets keyed by log level, so a flood at one level cannot crowd
+/// the others out of the buffer. Levels without a limit are never limited.
+#[derive(Debug, Clone, Default)]
+pub struct LevelRateLimiter {
+    buckets: HashMap<LogLevel, TokenBucket>,
+}
+
+impl LevelRateLimiter {
+    /// Build a limiter from the collector configuration
+    pub fn from_config(config: &Config) -> Self {
+        let now = Instant::now();
+        let buckets = config
+            .level_rate_limits
+            .iter()
+            .map(|(level, rate)| (LogLevel::from(level.as_str()), TokenBucket::new(*rate, now)))
+            .collect();
+
+        Self { buckets }
+    }
+
+    /// Whether any level is limited
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Take a token for an entry at `level`, returning whether it may be kept
+    pub fn allow(&mut self, level: &LogLevel, now: Instant) -> bool {
+        match self.buckets.get_mut(level) {
+            Some(bucket) => bucket.try_take(now),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter(limits: &[(&str, f64)]) -> LevelRateLimiter {
+        let config = Config {
+            level_rate_limits: limits.iter().map(|(level, rate)| (level.to_string(), *rate)).collect(),
+            ..Config::default()
+        };
+        LevelRateLimiter::from_config(&config)
+    }
+
+    #[test]
+    fn test_limits_only_configured_levels() {
+        let mut limiter = limiter(&[("error", 3.0)]);
+        let now = Instant::now();
+
+        let kept = (0..10).filter(|_| limiter.allow(&LogLevel::Error, now)).count();
+        assert_eq!(kept, 3);
+        assert!((0..100).all(|_| limiter.allow(&LogLevel::Info, now)));
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let mut limiter = limiter(&[("info", 10.0)]);
+        let start = Instant::now();
+
+        assert_eq!((0..20).filter(|_| limiter.allow(&LogLevel::Info, start)).count(), 10);
+        assert!(!limiter.allow(&LogLevel::Info, start));
+
+        // Half a second refills half the burst
+        let later = start + Duration::from_millis(500);
+        assert_eq!((0..20).filter(|_| limiter.allow(&LogLevel::Info, later)).count(), 5);
+    }
+
+    #[test]
+    fn test_fractional_rate_allows_one() {
+        let mut limiter = limiter(&[("debug", 0.5)]);
+        let start = Instant::now();
+
+        assert!(limiter.allow(&LogLevel::Debug, start));
+        assert!(!limiter.allow(&LogLevel::Debug, start + Duration::from_secs(1)));
+        assert!(limiter.allow(&LogLevel::Debug, start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,135 @@
+//! Bounded in-memory index of recently parsed log entries, for local debugging
+
+use crate::telemetry::{LogEntry, LogLevel};
+use std::collections::VecDeque;
+
+/// Filters for querying recent log entries
+#[derive(Debug, Clone, Default)]
+pub struct RecentQuery {
+    /// Only entries at or above this level
+    pub level: Option<LogLevel>,
+    /// Only entries whose message contains this text, ignoring case
+    pub contains: Option<String>,
+    /// Maximum number of entries returned
+    pub limit: usize,
+}
+
+/// Ring of the most recently parsed log entries, independent of the send
+/// buffer. Once full, recording an entry evicts the oldest one.
+#[derive(Debug)]
+pub struct RecentLogs {
+    capacity: usize,
+    entries: VecDeque<LogEntry>,
+}
+
+impl RecentLogs {
+    /// Create a ring holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record a parsed entry, evicting the oldest one when full
+    pub fn record(&mut self, entry: &LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.clone());
+    }
+
+    /// Number of entries held
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no entries are held
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries matching the query, newest first
+    pub fn query(&self, query: &RecentQuery) -> Vec<LogEntry> {
+        let min_severity = query.level.as_ref().map(LogLevel::severity_number);
+        let contains = query.contains.as_ref().map(|text| text.to_lowercase());
+
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| min_severity.is_none_or(|min| entry.level.severity_number() >= min))
+            .filter(|entry| {
+                contains
+                    .as_ref()
+                    .is_none_or(|text| entry.message.to_lowercase().contains(text.as_str()))
+            })
+            .take(query.limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: LogLevel, message: &str) -> LogEntry {
+        LogEntry::new(
+            level,
+            message.to_string(),
+            "checkout".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        )
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ring_evicts_oldest() {
+        let mut recent = RecentLogs::new(3);
+        for i in 0..5 {
+            recent.record(&entry(LogLevel::Info, &format!("line {}", i)));
+        }
+
+        assert_eq!(recent.len(), 3);
+        let all = recent.query(&RecentQuery { limit: 10, ..RecentQuery::default() });
+        assert_eq!(messages(&all), vec!["line 4", "line 3", "line 2"]);
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let mut recent = RecentLogs::new(10);
+        recent.record(&entry(LogLevel::Error, "upstream Timeout after 5s"));
+        recent.record(&entry(LogLevel::Info, "request timeout retried"));
+        recent.record(&entry(LogLevel::Warn, "slow query"));
+        recent.record(&entry(LogLevel::Fatal, "connection timeout, exiting"));
+        recent.record(&entry(LogLevel::Error, "payment declined"));
+
+        let errors = recent.query(&RecentQuery {
+            level: Some(LogLevel::Error),
+            contains: Some("timeout".to_string()),
+            limit: 50,
+        });
+        assert_eq!(messages(&errors), vec!["connection timeout, exiting", "upstream Timeout after 5s"]);
+
+        let limited = recent.query(&RecentQuery {
+            level: Some(LogLevel::Warn),
+            limit: 2,
+            ..RecentQuery::default()
+        });
+        assert_eq!(messages(&limited), vec!["payment declined", "connection timeout, exiting"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_records_nothing() {
+        let mut recent = RecentLogs::new(0);
+        recent.record(&entry(LogLevel::Error, "dropped"));
+        assert!(recent.is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! Attribute-based sampling of parsed log entries
+
+use crate::config::Config;
+use crate::errors::{CollectorError, Result};
+use crate::telemetry::LogEntry;
+use rand::Rng;
+use regex::Regex;
+
+/// Drops a configured fraction of log entries based on their attributes
+#[derive(Debug, Clone, Default)]
+pub struct LogSampler {
+    rules: Vec<CompiledSamplingRule>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledSamplingRule {
+    attribute: String,
+    pattern: Regex,
+    sample_ratio: f64,
+}
+
+impl LogSampler {
+    /// Build a sampler from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let rules = config
+            .sampling_rules
+            .iter()
+            .map(|rule| {
+                let pattern = Regex::new(&rule.pattern).map_err(|e| {
+                    CollectorError::Config(format!("invalid sampling pattern '{}': {}", rule.pattern, e))
+                })?;
+
+                Ok(CompiledSamplingRule {
+                    attribute: rule.attribute.clone(),
+                    pattern,
+                    sample_ratio: rule.sample_ratio.clamp(0.0, 1.0),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Whether any sampling rules are configured
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Decide whether to keep a log entry
+    pub fn should_keep(&self, log_entry: &LogEntry) -> bool {
+        if self.rules.is_empty() {
+            return true;
+        }
+        self.should_keep_with(log_entry, &mut rand::rng())
+    }
+
+    /// Decide whether to keep a log entry using the given random source.
+    /// The first matching rule decides; unmatched entries are always kept.
+    pub fn should_keep_with<R: Rng + ?Sized>(&self, log_entry: &LogEntry, rng: &mut R) -> bool {
+        let matched = self.rules.iter().find(|rule| {
+            log_entry
+                .attributes
+                .get(&rule.attribute)
+                .is_some_and(|value| rule.pattern.is_match(value))
+        });
+
+        match matched {
+            Some(rule) => rng.random::<f64>() < rule.sample_ratio,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SamplingRule;
+    use crate::telemetry::LogLevel;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn sampler(rules: Vec<SamplingRule>) -> LogSampler {
+        let config = Config {
+            sampling_rules: rules,
+            ..Config::default()
+        };
+        LogSampler::from_config(&config).unwrap()
+    }
+
+    fn entry_with(attribute: &str, value: &str) -> LogEntry {
+        LogEntry::new(
+            LogLevel::Info,
+            "GET request".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        )
+        .with_attribute(attribute.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_sampling_matching_entries() {
+        let sampler = sampler(vec![SamplingRule {
+            attribute: "path".to_string(),
+            pattern: "^/healthz$".to_string(),
+            sample_ratio: 0.1,
+        }]);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let healthz = entry_with("path", "/healthz");
+        let kept = (0..1000)
+            .filter(|_| sampler.should_keep_with(&healthz, &mut rng))
+            .count();
+        assert!((60..=140).contains(&kept), "kept {} of 1000", kept);
+
+        let other = entry_with("path", "/api/orders");
+        assert!((0..1000).all(|_| sampler.should_keep_with(&other, &mut rng)));
+
+        let unrelated = entry_with("method", "GET");
+        assert!(sampler.should_keep_with(&unrelated, &mut rng));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let sampler = sampler(vec![
+            SamplingRule {
+                attribute: "path".to_string(),
+                pattern: "^/healthz".to_string(),
+                sample_ratio: 1.0,
+            },
+            SamplingRule {
+                attribute: "path".to_string(),
+                pattern: ".*".to_string(),
+                sample_ratio: 0.0,
+            },
+        ]);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert!(sampler.should_keep_with(&entry_with("path", "/healthz"), &mut rng));
+        assert!(!sampler.should_keep_with(&entry_with("path", "/api"), &mut rng));
+    }
+
+    #[test]
+    fn test_invalid_sampling_pattern() {
+        let config = Config {
+            sampling_rules: vec![SamplingRule {
+                attribute: "path".to_string(),
+                pattern: "(unclosed".to_string(),
+                sample_ratio: 0.5,
+            }],
+            ..Config::default()
+        };
+
+        assert!(LogSampler::from_config(&config).is_err());
+        assert!(config.validate().is_err());
+    }
+}
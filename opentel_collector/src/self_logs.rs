@@ -0,0 +1,158 @@
+//! Forwarding of the collector's own `tracing` events through the telemetry
+//! pipeline, so sidecar logs are queryable next to application logs
+
+use crate::telemetry::{LogEntry, LogLevel};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Events held for the collector before newer ones are dropped
+pub const SELF_LOG_CAPACITY: usize = 1024;
+
+/// Target prefix of the collector's own events. Events of its dependencies,
+/// such as hyper or reqwest logging from tasks they spawn while a batch is
+/// sent, are never forwarded.
+const COLLECTOR_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+tokio::task_local! {
+    static SUPPRESSED: ();
+}
+
+/// Run `future` without forwarding the events it emits. The collector sends
+/// and buffers forwarded entries under this, since an event logged while
+/// sending would otherwise need sending in turn, e.g. a send failure logging
+/// an error that fails to send.
+pub async fn suppressed<F: Future>(future: F) -> F::Output {
+    SUPPRESSED.scope((), future).await
+}
+
+/// Create a layer forwarding events together with the receiving end the
+/// collector drains on each flush tick. Events are dropped while nothing receives them.
+pub fn channel() -> (SelfLogLayer, SelfLogReceiver) {
+    let (sender, receiver) = mpsc::channel(SELF_LOG_CAPACITY);
+    (SelfLogLayer { sender }, SelfLogReceiver { receiver })
+}
+
+/// `tracing` layer turning each of the collector's own events into a
+/// [`LogEntry`] tagged `source=collector`. Service, pod and namespace are
+/// filled in by the collector.
+#[derive(Debug)]
+pub struct SelfLogLayer {
+    sender: mpsc::Sender<LogEntry>,
+}
+
+impl<S: Subscriber> Layer<S> for SelfLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !is_collector_target(metadata.target()) || SUPPRESSED.try_with(|_| ()).is_ok() {
+            return;
+        }
+
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+
+        let level = match *metadata.level() {
+            Level::TRACE => LogLevel::Trace,
+            Level::DEBUG => LogLevel::Debug,
+            Level::INFO => LogLevel::Info,
+            Level::WARN => LogLevel::Warn,
+            Level::ERROR => LogLevel::Error,
+        };
+        let entry = LogEntry::new(level, visitor.message, String::new(), String::new(), String::new())
+            .with_attributes(visitor.fields)
+            .with_attribute("source".to_string(), "collector".to_string())
+            .with_attribute("telemetry.self".to_string(), "true".to_string())
+            .with_attribute("code.namespace".to_string(), metadata.target().to_string());
+
+        // Full or closed: dropping is the only option that cannot block or recurse
+        let _ = self.sender.try_send(entry);
+    }
+}
+
+fn is_collector_target(target: &str) -> bool {
+    target
+        .strip_prefix(COLLECTOR_TARGET)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Receiving end of [`channel`]
+#[derive(Debug)]
+pub struct SelfLogReceiver {
+    receiver: mpsc::Receiver<LogEntry>,
+}
+
+impl SelfLogReceiver {
+    /// Take a forwarded event if one is waiting
+    pub fn try_recv(&mut self) -> Option<LogEntry> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Collects an event's message and its other fields as attributes
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: HashMap<String, String>,
+}
+
+impl Visit for EventVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.fields.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test]
+    async fn test_events_forwarded_unless_suppressed() {
+        let (layer, mut receiver) = channel();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        tracing::warn!(path = "/var/log/app.log", "File {} disappeared", "app.log");
+        suppressed(async { tracing::error!("Failed to send batch") }).await;
+        tracing::info!("after");
+
+        let entry = receiver.try_recv().unwrap();
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message, "File app.log disappeared");
+        assert_eq!(entry.attributes.get("path").map(String::as_str), Some("/var/log/app.log"));
+        assert_eq!(entry.attributes.get("source").map(String::as_str), Some("collector"));
+
+        // The suppressed error never arrives
+        assert_eq!(receiver.try_recv().unwrap().message, "after");
+        assert!(receiver.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dependency_events_not_forwarded() {
+        let (layer, mut receiver) = channel();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        // e.g. logged by a connection task hyper spawned, outside `suppressed`
+        tracing::debug!(target: "hyper_util::client::legacy", "connection error");
+        tracing::warn!(target: "opentel_collector_extras", "lookalike crate");
+        tracing::warn!(target: "opentel_collector::transport", "retrying");
+
+        assert_eq!(receiver.try_recv().unwrap().message, "retrying");
+        assert!(receiver.try_recv().is_none());
+    }
+}
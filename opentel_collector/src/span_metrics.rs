@@ -0,0 +1,214 @@
+//! Rate, error and duration (RED) metrics derived from parsed spans
+
+use crate::telemetry::{MetricPoint, SpanStatus, TraceSpan};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Running request, error and latency aggregates for one operation
+#[derive(Debug, Clone, Default)]
+struct OperationStats {
+    requests: u64,
+    errors: u64,
+    duration_sum_ms: u64,
+    duration_min_ms: u64,
+    duration_max_ms: u64,
+    /// Recency stamp for least-recently-used eviction
+    last_seen: u64,
+}
+
+/// Current RED metrics for one operation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OperationMetrics {
+    pub operation: String,
+    pub requests: u64,
+    pub errors: u64,
+    /// Errors as a percentage of requests
+    pub error_rate: f64,
+    pub avg_duration_ms: u64,
+    pub min_duration_ms: u64,
+    pub max_duration_ms: u64,
+}
+
+/// Aggregates spans per `operation_name` into cumulative RED metrics. The
+/// number of tracked operations is bounded; the least recently seen
+/// operation is evicted to make room for a new one.
+#[derive(Debug)]
+pub struct SpanMetrics {
+    interval: Duration,
+    max_operations: usize,
+    operations: HashMap<String, OperationStats>,
+    clock: u64,
+    last_emitted: Option<Instant>,
+}
+
+impl SpanMetrics {
+    pub fn new(interval: Duration, max_operations: usize) -> Self {
+        Self {
+            interval,
+            max_operations: max_operations.max(1),
+            operations: HashMap::new(),
+            clock: 0,
+            last_emitted: None,
+        }
+    }
+
+    /// Count a span toward its operation's metrics
+    pub fn record(&mut self, span: &TraceSpan) {
+        self.clock += 1;
+
+        if !self.operations.contains_key(&span.operation_name)
+            && self.operations.len() >= self.max_operations
+        {
+            self.evict_least_recent();
+        }
+
+        let stats = self.operations.entry(span.operation_name.clone()).or_default();
+        stats.duration_min_ms = if stats.requests == 0 {
+            span.duration_ms
+        } else {
+            stats.duration_min_ms.min(span.duration_ms)
+        };
+        stats.duration_max_ms = stats.duration_max_ms.max(span.duration_ms);
+        stats.duration_sum_ms = stats.duration_sum_ms.saturating_add(span.duration_ms);
+        stats.requests += 1;
+        if matches!(span.status, SpanStatus::Error | SpanStatus::Timeout) {
+            stats.errors += 1;
+        }
+        stats.last_seen = self.clock;
+    }
+
+    fn evict_least_recent(&mut self) {
+        let oldest = self
+            .operations
+            .iter()
+            .min_by_key(|(_, stats)| stats.last_seen)
+            .map(|(operation, _)| operation.clone());
+
+        if let Some(operation) = oldest {
+            self.operations.remove(&operation);
+        }
+    }
+
+    /// Current metrics for every tracked operation, sorted by name
+    pub fn snapshot(&self) -> Vec<OperationMetrics> {
+        let mut snapshot: Vec<_> = self
+            .operations
+            .iter()
+            .map(|(operation, stats)| OperationMetrics {
+                operation: operation.clone(),
+                requests: stats.requests,
+                errors: stats.errors,
+                error_rate: stats.errors as f64 / stats.requests as f64 * 100.0,
+                avg_duration_ms: stats.duration_sum_ms / stats.requests,
+                min_duration_ms: stats.duration_min_ms,
+                max_duration_ms: stats.duration_max_ms,
+            })
+            .collect();
+        snapshot.sort_by(|a, b| a.operation.cmp(&b.operation));
+        snapshot
+    }
+
+    /// Metric points for every operation if the emit interval has passed
+    /// since the last call that returned any
+    pub fn due_points(&mut self, service_name: &str, now: Instant) -> Vec<MetricPoint> {
+        if self.operations.is_empty()
+            || self.last_emitted.is_some_and(|last| now.duration_since(last) < self.interval)
+        {
+            return Vec::new();
+        }
+
+        self.last_emitted = Some(now);
+        self.points(service_name)
+    }
+
+    /// Metric points describing the current snapshot
+    pub fn points(&self, service_name: &str) -> Vec<MetricPoint> {
+        self.snapshot()
+            .into_iter()
+            .flat_map(|metrics| {
+                [
+                    ("span.requests", metrics.requests as f64),
+                    ("span.errors", metrics.errors as f64),
+                    ("span.error_rate", metrics.error_rate),
+                    ("span.duration_ms.avg", metrics.avg_duration_ms as f64),
+                    ("span.duration_ms.max", metrics.max_duration_ms as f64),
+                ]
+                .map(|(name, value)| {
+                    MetricPoint::new(name.to_string(), value, service_name.to_string())
+                        .with_attribute("operation".to_string(), metrics.operation.clone())
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(operation: &str, status: SpanStatus, duration_ms: u64) -> TraceSpan {
+        TraceSpan::new(
+            "trace-1".to_string(),
+            "span-1".to_string(),
+            operation.to_string(),
+            "test-service".to_string(),
+        )
+        .with_status(status)
+        .set_duration_ms(duration_ms)
+    }
+
+    #[test]
+    fn test_error_rate_per_operation() {
+        let mut metrics = SpanMetrics::new(Duration::from_secs(60), 100);
+        for _ in 0..3 {
+            metrics.record(&span("GET /orders", SpanStatus::Ok, 100));
+        }
+        metrics.record(&span("GET /orders", SpanStatus::Error, 500));
+        metrics.record(&span("db.query", SpanStatus::Timeout, 2000));
+        metrics.record(&span("db.query", SpanStatus::Cancelled, 20));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 2);
+
+        let orders = &snapshot[0];
+        assert_eq!(orders.operation, "GET /orders");
+        assert_eq!((orders.requests, orders.errors), (4, 1));
+        assert_eq!(orders.error_rate, 25.0);
+        assert_eq!(orders.avg_duration_ms, 200);
+        assert_eq!((orders.min_duration_ms, orders.max_duration_ms), (100, 500));
+
+        let query = &snapshot[1];
+        assert_eq!((query.requests, query.errors), (2, 1));
+        assert_eq!(query.error_rate, 50.0);
+    }
+
+    #[test]
+    fn test_least_recent_operation_evicted() {
+        let mut metrics = SpanMetrics::new(Duration::from_secs(60), 2);
+        metrics.record(&span("a", SpanStatus::Ok, 1));
+        metrics.record(&span("b", SpanStatus::Ok, 1));
+        metrics.record(&span("a", SpanStatus::Ok, 1));
+        metrics.record(&span("c", SpanStatus::Ok, 1));
+
+        let operations: Vec<_> = metrics.snapshot().into_iter().map(|m| m.operation).collect();
+        assert_eq!(operations, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_points_emitted_once_per_interval() {
+        let mut metrics = SpanMetrics::new(Duration::from_secs(60), 100);
+        let start = Instant::now();
+        assert!(metrics.due_points("test-service", start).is_empty());
+
+        metrics.record(&span("GET /orders", SpanStatus::Error, 100));
+        let points = metrics.due_points("test-service", start);
+        assert_eq!(points.len(), 5);
+        let error_rate = points.iter().find(|point| point.name == "span.error_rate").unwrap();
+        assert_eq!(error_rate.value, 100.0);
+        assert_eq!(error_rate.attributes["operation"], "GET /orders");
+
+        assert!(metrics.due_points("test-service", start + Duration::from_secs(30)).is_empty());
+        assert_eq!(metrics.due_points("test-service", start + Duration::from_secs(60)).len(), 5);
+    }
+}
@@ -0,0 +1,138 @@
+//! Collector state kept on disk across restarts
+
+use crate::errors::Result;
+use crate::files;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Small state file a restarted collector picks up from its previous run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectorState {
+    /// Times the collector has started, including this run
+    pub starts: u64,
+    /// Unix timestamp of the latest start
+    #[serde(default)]
+    pub last_started_at: Option<u64>,
+    /// Batch sequence number a restarted collector continues after: the
+    /// last one sent when the previous run shut down cleanly, or the end of
+    /// the range it had reserved when it crashed
+    #[serde(default)]
+    pub batch_sequence: u64,
+}
+
+impl CollectorState {
+    /// Read the state at `path`, or a fresh state when there is none
+    pub fn load(path: &Path) -> Result<Self> {
+        load(path)
+    }
+
+    /// Replace the state at `path`, keeping the previous state if a crash
+    /// interrupts the write
+    pub fn store(&self, path: &Path) -> Result<()> {
+        store(path, self)
+    }
+
+    /// Count a start in the state at `path`, returning the updated state
+    pub fn record_start(path: &Path) -> Result<Self> {
+        let mut state = Self::load(path)?;
+        state.starts += 1;
+        state.last_started_at = Some(crate::telemetry::current_timestamp());
+        state.store(path)?;
+        Ok(state)
+    }
+
+    /// Set the batch sequence number in the state at `path`
+    pub fn record_batch_sequence(path: &Path, sequence: u64) -> Result<()> {
+        let mut state = Self::load(path)?;
+        state.batch_sequence = sequence;
+        state.store(path)
+    }
+
+    /// Starts before the current one
+    pub fn restarts(&self) -> u64 {
+        self.starts.saturating_sub(1)
+    }
+}
+
+/// Where the previous run stopped reading a tailed file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOffset {
+    /// Inode of the file that was read, to tell whether it was rotated since
+    pub inode: Option<u64>,
+    /// Byte offset of the first unread line
+    pub position: u64,
+}
+
+/// Read offsets of tailed files by path, so a restarted collector resumes
+/// where the previous run stopped
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileOffsets {
+    pub files: HashMap<String, FileOffset>,
+}
+
+impl FileOffsets {
+    /// Read the offsets at `path`, or none when there is no file
+    pub fn load(path: &Path) -> Result<Self> {
+        load(path)
+    }
+
+    /// Replace the offsets at `path`, keeping the previous ones if a crash
+    /// interrupts the write
+    pub fn store(&self, path: &Path) -> Result<()> {
+        store(path, self)
+    }
+}
+
+fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace the file at `path` with `value`, creating its directory if
+/// needed, see [`files::replace`]
+fn store<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    files::replace(path, &serde_json::to_vec(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_start_counts_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("collector.state");
+        assert_eq!(CollectorState::load(&path).unwrap(), CollectorState::default());
+
+        assert_eq!(CollectorState::record_start(&path).unwrap().restarts(), 0);
+        assert_eq!(CollectorState::record_start(&path).unwrap().restarts(), 1);
+
+        let state = CollectorState::load(&path).unwrap();
+        assert_eq!(state.starts, 2);
+        assert!(state.last_started_at.is_some());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_batch_sequence_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("collector.state");
+
+        CollectorState::record_start(&path).unwrap();
+        CollectorState::record_batch_sequence(&path, 42).unwrap();
+
+        let state = CollectorState::record_start(&path).unwrap();
+        assert_eq!(state.batch_sequence, 42);
+        assert_eq!(state.restarts(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+//! Local HTTP server exposing collector statistics for debugging
+
+use crate::collector::SidecarCollector;
+use crate::errors::Result;
+use crate::recent::RecentQuery;
+use crate::telemetry::LogLevel;
+use axum::extract::{Query, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// Entries returned by `/recent` when no limit is given
+const DEFAULT_RECENT_LIMIT: usize = 100;
+
+/// Routes served by the stats server:
+///
+/// - `GET /healthz`: 503 while a log path is idle past its threshold and
+///   `log_freshness_health` is set, otherwise 200
+/// - `GET /readyz`: 200 once the collector is ready or degraded, 503 while
+///   it is starting or shutting down
+/// - `GET /stats`: collector statistics as JSON
+/// - `GET /recent?level=error&contains=timeout&limit=50`: recently parsed
+///   log entries, newest first, when the recent log index is enabled
+/// - `GET /errors`: recent collector errors, newest first
+/// - `POST /pause` and `POST /resume`: stop and restart sending; they
+///   require `Authorization: Bearer <stats_control_token>` and return 404
+///   when no token is configured
+pub fn router(collector: Arc<SidecarCollector>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/stats", get(stats))
+        .route("/recent", get(recent))
+        .route("/errors", get(errors))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .with_state(collector)
+}
+
+/// Serve the stats routes on `listener` until the task is dropped
+pub async fn serve(listener: TcpListener, collector: Arc<SidecarCollector>) -> Result<()> {
+    info!("Stats server listening on {}", listener.local_addr()?);
+    axum::serve(listener, router(collector)).await?;
+    Ok(())
+}
+
+async fn healthz(State(collector): State<Arc<SidecarCollector>>) -> Response {
+    let stale = collector.unhealthy_log_sources().await;
+    if stale.is_empty() {
+        return Json(json!({ "status": "healthy" })).into_response();
+    }
+
+    let body = json!({ "status": "unhealthy", "stale_log_sources": stale });
+    (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+}
+
+async fn readyz(State(collector): State<Arc<SidecarCollector>>) -> Response {
+    let readiness = collector.readiness().await;
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+async fn stats(State(collector): State<Arc<SidecarCollector>>) -> Response {
+    Json(collector.stats().await).into_response()
+}
+
+async fn errors(State(collector): State<Arc<SidecarCollector>>) -> Response {
+    Json(collector.recent_errors().await).into_response()
+}
+
+async fn pause(State(collector): State<Arc<SidecarCollector>>, headers: HeaderMap) -> Response {
+    if let Some(rejection) = reject_control(&collector, &headers) {
+        return rejection;
+    }
+    collector.pause();
+    Json(json!({ "paused": true })).into_response()
+}
+
+async fn resume(State(collector): State<Arc<SidecarCollector>>, headers: HeaderMap) -> Response {
+    if let Some(rejection) = reject_control(&collector, &headers) {
+        return rejection;
+    }
+    collector.resume();
+    Json(json!({ "paused": false })).into_response()
+}
+
+/// Response refusing a request that changes collector behaviour, unless it
+/// carries the configured bearer token
+fn reject_control(collector: &SidecarCollector, headers: &HeaderMap) -> Option<Response> {
+    let Some(expected) = collector.stats_control_token() else {
+        let body = json!({ "error": "pause and resume are disabled, set STATS_CONTROL_TOKEN to enable them" });
+        return Some((StatusCode::NOT_FOUND, Json(body)).into_response());
+    };
+
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some((StatusCode::UNAUTHORIZED, Json(json!({ "error": "invalid or missing bearer token" }))).into_response()),
+    }
+}
+
+/// Compare tokens without returning early on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Query string accepted by `/recent`
+#[derive(Debug, Deserialize)]
+struct RecentParams {
+    level: Option<String>,
+    contains: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn recent(
+    State(collector): State<Arc<SidecarCollector>>,
+    Query(params): Query<RecentParams>,
+) -> Response {
+    let query = RecentQuery {
+        level: params.level.as_deref().map(LogLevel::from),
+        contains: params.contains.filter(|text| !text.is_empty()),
+        limit: params.limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+    };
+
+    match collector.recent_logs(&query).await {
+        Some(entries) => Json(entries).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            "recent log index is disabled; set RECENT_LOGS_CAPACITY to enable it",
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,279 @@
+//! Tail-based sampling that keeps every trace containing an error
+
+use crate::telemetry::{LogEntry, LogLevel, SpanStatus, TraceSpan};
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Members of a trace held until it is decided
+#[derive(Debug, Default)]
+struct HeldTrace {
+    logs: Vec<LogEntry>,
+    spans: Vec<TraceSpan>,
+    has_error: bool,
+}
+
+impl HeldTrace {
+    fn len(&self) -> usize {
+        self.logs.len() + self.spans.len()
+    }
+}
+
+/// Entries from decided traces
+#[derive(Debug, Default)]
+pub struct SampledTraces {
+    /// Logs from kept traces
+    pub logs: Vec<LogEntry>,
+    /// Spans from kept traces
+    pub spans: Vec<TraceSpan>,
+    /// Traces dropped by the sample ratio
+    pub dropped: usize,
+}
+
+impl SampledTraces {
+    fn keep(&mut self, trace: HeldTrace) {
+        self.logs.extend(trace.logs);
+        self.spans.extend(trace.spans);
+    }
+}
+
+/// Holds spans and logs per `trace_id` for a bounded window, then keeps the
+/// whole trace if any member is an error span or an error log, and otherwise
+/// keeps it with the configured ratio. When more than `max_pending` entries
+/// are held the oldest trace is decided early. Members arriving after their
+/// trace was decided start a new one and are decided on their own.
+#[derive(Debug)]
+pub struct TailSampler {
+    window: Duration,
+    sample_ratio: f64,
+    max_pending: usize,
+    pending: usize,
+    order: VecDeque<(Instant, String)>,
+    traces: HashMap<String, HeldTrace>,
+}
+
+impl TailSampler {
+    pub fn new(window: Duration, sample_ratio: f64, max_pending: usize) -> Self {
+        Self {
+            window,
+            sample_ratio: sample_ratio.clamp(0.0, 1.0),
+            max_pending: max_pending.max(1),
+            pending: 0,
+            order: VecDeque::new(),
+            traces: HashMap::new(),
+        }
+    }
+
+    /// Hold a log with its trace, returning any traces decided early to
+    /// stay within `max_pending`. Logs without a trace id pass through.
+    pub fn add_log(&mut self, log_entry: LogEntry, now: Instant) -> SampledTraces {
+        let Some(trace_id) = log_entry.trace_id.clone() else {
+            return SampledTraces {
+                logs: vec![log_entry],
+                ..SampledTraces::default()
+            };
+        };
+
+        let trace = self.held(trace_id, now);
+        trace.has_error |= log_entry.level.severity_number() >= LogLevel::Error.severity_number();
+        trace.logs.push(log_entry);
+        self.pending += 1;
+        self.evict(&mut rand::rng())
+    }
+
+    /// Hold a span with its trace, returning any traces decided early to
+    /// stay within `max_pending`
+    pub fn add_span(&mut self, span: TraceSpan, now: Instant) -> SampledTraces {
+        let trace = self.held(span.trace_id.clone(), now);
+        trace.has_error |= span.status == SpanStatus::Error;
+        trace.spans.push(span);
+        self.pending += 1;
+        self.evict(&mut rand::rng())
+    }
+
+    /// Decide the traces whose window has passed
+    pub fn expire(&mut self, now: Instant) -> SampledTraces {
+        self.expire_with(now, &mut rand::rng())
+    }
+
+    /// Decide the traces whose window has passed using the given random source
+    pub fn expire_with<R: Rng + ?Sized>(&mut self, now: Instant, rng: &mut R) -> SampledTraces {
+        let mut sampled = SampledTraces::default();
+        while let Some((first_seen, _)) = self.order.front() {
+            if now.duration_since(*first_seen) <= self.window {
+                break;
+            }
+            self.decide_oldest(&mut sampled, rng);
+        }
+        sampled
+    }
+
+    /// Decide every held trace, e.g. on shutdown
+    pub fn drain(&mut self) -> SampledTraces {
+        let mut sampled = SampledTraces::default();
+        let rng = &mut rand::rng();
+        while !self.order.is_empty() {
+            self.decide_oldest(&mut sampled, rng);
+        }
+        sampled
+    }
+
+    /// Number of logs and spans currently held
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Copies of the held logs and spans, oldest trace first, e.g. for a
+    /// checkpoint
+    pub fn snapshot(&self) -> (Vec<LogEntry>, Vec<TraceSpan>) {
+        let (mut logs, mut spans) = (Vec::new(), Vec::new());
+        for trace in self.order.iter().filter_map(|(_, trace_id)| self.traces.get(trace_id)) {
+            logs.extend(trace.logs.iter().cloned());
+            spans.extend(trace.spans.iter().cloned());
+        }
+        (logs, spans)
+    }
+
+    fn held(&mut self, trace_id: String, now: Instant) -> &mut HeldTrace {
+        if !self.traces.contains_key(&trace_id) {
+            self.order.push_back((now, trace_id.clone()));
+        }
+        self.traces.entry(trace_id).or_default()
+    }
+
+    fn evict<R: Rng + ?Sized>(&mut self, rng: &mut R) -> SampledTraces {
+        let mut sampled = SampledTraces::default();
+        while self.pending > self.max_pending && !self.order.is_empty() {
+            self.decide_oldest(&mut sampled, rng);
+        }
+        sampled
+    }
+
+    fn decide_oldest<R: Rng + ?Sized>(&mut self, sampled: &mut SampledTraces, rng: &mut R) {
+        let Some((_, trace_id)) = self.order.pop_front() else {
+            return;
+        };
+        let Some(trace) = self.traces.remove(&trace_id) else {
+            return;
+        };
+
+        self.pending -= trace.len();
+        if trace.has_error || rng.random::<f64>() < self.sample_ratio {
+            sampled.keep(trace);
+        } else {
+            sampled.dropped += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn span(trace_id: &str, span_id: &str, status: SpanStatus) -> TraceSpan {
+        TraceSpan::new(
+            trace_id.to_string(),
+            span_id.to_string(),
+            "db.query".to_string(),
+            "test-service".to_string(),
+        )
+        .with_status(status)
+    }
+
+    fn log(trace_id: &str, level: LogLevel) -> LogEntry {
+        LogEntry {
+            trace_id: Some(trace_id.to_string()),
+            ..LogEntry::new(
+                level,
+                "checkout".to_string(),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )
+        }
+    }
+
+    fn span_ids(sampled: &SampledTraces) -> Vec<&str> {
+        sampled.spans.iter().map(|span| span.span_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_error_span_keeps_whole_trace() {
+        let mut sampler = TailSampler::new(Duration::from_secs(2), 0.0, 100);
+        let start = Instant::now();
+
+        sampler.add_span(span("trace-1", "a", SpanStatus::Ok), start);
+        sampler.add_log(log("trace-1", LogLevel::Info), start);
+        sampler.add_span(span("trace-1", "b", SpanStatus::Error), start);
+        sampler.add_span(span("trace-1", "c", SpanStatus::Ok), start);
+        sampler.add_span(span("trace-2", "d", SpanStatus::Ok), start);
+        assert_eq!(sampler.pending(), 5);
+
+        // Nothing is decided within the window
+        assert!(sampler.expire(start + Duration::from_secs(1)).spans.is_empty());
+
+        let sampled = sampler.expire(start + Duration::from_secs(3));
+        assert_eq!(span_ids(&sampled), vec!["a", "b", "c"]);
+        assert_eq!(sampled.logs.len(), 1);
+        assert_eq!(sampled.dropped, 1);
+        assert_eq!(sampler.pending(), 0);
+    }
+
+    #[test]
+    fn test_error_log_keeps_trace() {
+        let mut sampler = TailSampler::new(Duration::from_secs(2), 0.0, 100);
+        let start = Instant::now();
+
+        sampler.add_span(span("trace-1", "a", SpanStatus::Ok), start);
+        sampler.add_log(log("trace-1", LogLevel::Error), start);
+
+        let sampled = sampler.drain();
+        assert_eq!(span_ids(&sampled), vec!["a"]);
+        assert_eq!(sampled.dropped, 0);
+    }
+
+    #[test]
+    fn test_error_free_traces_sampled_by_ratio() {
+        let mut sampler = TailSampler::new(Duration::from_secs(1), 0.25, 10_000);
+        let start = Instant::now();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for i in 0..1000 {
+            sampler.add_span(span(&format!("trace-{}", i), "a", SpanStatus::Ok), start);
+        }
+
+        let sampled = sampler.expire_with(start + Duration::from_secs(2), &mut rng);
+        assert!((200..=300).contains(&sampled.spans.len()), "kept {} of 1000", sampled.spans.len());
+        assert_eq!(sampled.spans.len() + sampled.dropped, 1000);
+    }
+
+    #[test]
+    fn test_bounded_pending_decides_oldest_trace() {
+        let mut sampler = TailSampler::new(Duration::from_secs(60), 1.0, 2);
+        let now = Instant::now();
+
+        assert!(sampler.add_span(span("trace-1", "a", SpanStatus::Ok), now).spans.is_empty());
+        assert!(sampler.add_span(span("trace-2", "b", SpanStatus::Ok), now).spans.is_empty());
+        let early = sampler.add_span(span("trace-3", "c", SpanStatus::Ok), now);
+
+        assert_eq!(span_ids(&early), vec!["a"]);
+        assert_eq!(sampler.pending(), 2);
+    }
+
+    #[test]
+    fn test_untraced_logs_pass_through() {
+        let mut sampler = TailSampler::new(Duration::from_secs(60), 0.0, 10);
+        let untraced = LogEntry::new(
+            LogLevel::Info,
+            "plain".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        assert_eq!(sampler.add_log(untraced, Instant::now()).logs.len(), 1);
+        assert_eq!(sampler.pending(), 0);
+    }
+}
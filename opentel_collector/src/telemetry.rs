@@ -2,13 +2,16 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct LogEntry {
     pub timestamp: u64,
     pub level: LogLevel,
+    /// OTLP severity number (1-24), or 0 when unspecified
+    #[serde(default)]
+    pub severity_number: u8,
     pub message: String,
     pub service_name: String,
     pub pod_name: String,
@@ -18,7 +21,7 @@ pub struct LogEntry {
     pub attributes: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -41,17 +44,50 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+impl LogLevel {
+    /// Canonical OTLP severity number for this level
+    pub fn severity_number(&self) -> u8 {
+        match self {
+            LogLevel::Trace => 1,
+            LogLevel::Debug => 5,
+            LogLevel::Info => 9,
+            LogLevel::Warn => 13,
+            LogLevel::Error => 17,
+            LogLevel::Fatal => 21,
+        }
+    }
+
+    /// Level named by `name` or one of its aliases (e.g. `warning`), in any
+    /// case, if it is a known level
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "TRACE" | "VERBOSE" => Some(LogLevel::Trace),
+            "DEBUG" => Some(LogLevel::Debug),
+            "INFO" | "INFORMATION" => Some(LogLevel::Info),
+            "WARN" | "WARNING" => Some(LogLevel::Warn),
+            "ERROR" | "ERR" => Some(LogLevel::Error),
+            "FATAL" | "CRITICAL" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Level covering an OTLP severity number, if it is in range
+    pub fn from_severity_number(number: u8) -> Option<Self> {
+        match number {
+            1..=4 => Some(LogLevel::Trace),
+            5..=8 => Some(LogLevel::Debug),
+            9..=12 => Some(LogLevel::Info),
+            13..=16 => Some(LogLevel::Warn),
+            17..=20 => Some(LogLevel::Error),
+            21..=24 => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+}
+
 impl From<&str> for LogLevel {
     fn from(s: &str) -> Self {
-        match s.to_uppercase().as_str() {
-            "TRACE" | "VERBOSE" => LogLevel::Trace,
-            "DEBUG" => LogLevel::Debug,
-            "INFO" | "INFORMATION" => LogLevel::Info,
-            "WARN" | "WARNING" => LogLevel::Warn,
-            "ERROR" | "ERR" => LogLevel::Error,
-            "FATAL" | "CRITICAL" => LogLevel::Fatal,
-            _ => LogLevel::Info, // Default fallback
-        }
+        LogLevel::from_name(s).unwrap_or(LogLevel::Info) // Default fallback
     }
 }
 
@@ -104,17 +140,65 @@ impl From<&str> for SpanStatus {
 pub struct TelemetryBatch {
     pub logs: Vec<LogEntry>,
     pub spans: Vec<TraceSpan>,
+    /// Metrics derived by the collector; omitted when there are none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metrics: Vec<MetricPoint>,
     pub metadata: BatchMetadata,
+    /// When the batch was drained from the buffer; not sent on the wire
+    #[serde(skip, default = "Instant::now")]
+    pub created_at: Instant,
+}
+
+/// A single metric value, such as a RED metric derived from spans
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MetricPoint {
+    pub name: String,
+    pub value: f64,
+    pub timestamp: u64,
+    pub service_name: String,
+    pub attributes: HashMap<String, String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BatchMetadata {
     pub collector_id: String,
     pub batch_id: String,
+    /// Position of the batch among those sent by this collector, counting
+    /// from 1 and continuing across restarts when the collector keeps a
+    /// state file; zero until the batch is sequenced
+    #[serde(default)]
+    pub sequence: u64,
     pub timestamp: u64,
     pub source_pod: String,
     pub source_namespace: String,
     pub version: String,
+    pub resource_attributes: HashMap<String, String>,
+}
+
+// Serialized JSON of each type with empty strings and maps, leaving the
+// variable-length content to be added on top
+const LOG_ENTRY_OVERHEAD_BYTES: usize = 168;
+const SPAN_OVERHEAD_BYTES: usize = 177;
+const METRIC_OVERHEAD_BYTES: usize = 80;
+const BATCH_OVERHEAD_BYTES: usize = 220;
+const METRICS_FIELD_BYTES: usize = 14;
+
+/// Extra bytes for an optional string over `null`
+fn optional_len(value: &Option<String>) -> usize {
+    value.as_ref().map_or(0, |value| value.len())
+}
+
+/// Bytes for the entries of a string map: quotes, colon and separator
+fn map_bytes(map: &HashMap<String, String>) -> usize {
+    map.iter().map(|(key, value)| key.len() + value.len() + 6).sum()
+}
+
+impl MetricPoint {
+    /// Approximate size of the point serialized as JSON, see
+    /// [`TelemetryBatch::estimated_bytes`]
+    pub fn estimated_bytes(&self) -> usize {
+        METRIC_OVERHEAD_BYTES + self.name.len() + self.service_name.len() + map_bytes(&self.attributes)
+    }
 }
 
 impl LogEntry {
@@ -127,6 +211,7 @@ impl LogEntry {
     ) -> Self {
         Self {
             timestamp: current_timestamp(),
+            severity_number: level.severity_number(),
             level,
             message,
             service_name,
@@ -153,8 +238,37 @@ impl LogEntry {
         self.attributes.extend(attributes);
         self
     }
+
+    /// Approximate size of the entry serialized as JSON, see
+    /// [`TelemetryBatch::estimated_bytes`]
+    pub fn estimated_bytes(&self) -> usize {
+        LOG_ENTRY_OVERHEAD_BYTES
+            + self.message.len()
+            + self.service_name.len()
+            + self.pod_name.len()
+            + self.namespace.len()
+            + optional_len(&self.trace_id)
+            + optional_len(&self.span_id)
+            + map_bytes(&self.attributes)
+    }
+
+    /// Check the entry is well-formed enough for the gateway to accept
+    pub fn validate(&self) -> Result<(), String> {
+        if self.service_name.is_empty() {
+            return Err("log entry has an empty service_name".to_string());
+        }
+
+        if self.severity_number > 24 {
+            return Err(format!("log entry has out-of-range severity_number {}", self.severity_number));
+        }
+
+        Ok(())
+    }
 }
 
+/// Longest span duration kept as-is; longer ones are clamped and tagged
+pub const MAX_SPAN_DURATION_MS: u64 = 24 * 60 * 60 * 1000;
+
 impl TraceSpan {
     pub fn new(
         trace_id: String,
@@ -193,16 +307,104 @@ impl TraceSpan {
     }
 
     pub fn finish(mut self) -> Self {
-        self.end_time = current_timestamp();
-        self.duration_ms = self.end_time.saturating_sub(self.start_time) * 1000;
-        self
+        let now = current_timestamp();
+        if now < self.start_time {
+            // The clock moved backwards since the span started
+            self.end_time = self.start_time;
+            self.duration_ms = 0;
+            self.mark_timing_anomaly("clock_skew");
+            return self;
+        }
+
+        self.end_time = now;
+        self.duration_ms = (now - self.start_time).saturating_mul(1000);
+        self.normalize_timing()
     }
 
     pub fn set_duration_ms(mut self, duration_ms: u64) -> Self {
         self.duration_ms = duration_ms;
-        self.end_time = self.start_time + (duration_ms / 1000);
+        self.end_time = self.start_time.saturating_add(duration_ms / 1000);
+        self.normalize_timing()
+    }
+
+    /// Make start, end and duration agree. Swapped start and end times are
+    /// put back in order, a duration that disagrees with them wins (it is
+    /// the finer-grained value) and durations over a day are clamped. Each
+    /// correction is recorded in the `span.timing_anomaly` tag.
+    pub fn normalize_timing(mut self) -> Self {
+        if self.end_time < self.start_time {
+            std::mem::swap(&mut self.start_time, &mut self.end_time);
+            self.mark_timing_anomaly("end_before_start");
+        }
+
+        // Start and end are whole seconds, so allow up to a second of truncation
+        let elapsed_ms = (self.end_time - self.start_time).saturating_mul(1000);
+        if self.duration_ms.abs_diff(elapsed_ms) >= 1000 {
+            self.end_time = self.start_time.saturating_add(self.duration_ms / 1000);
+            self.mark_timing_anomaly("duration_mismatch");
+        }
+
+        if self.duration_ms > MAX_SPAN_DURATION_MS {
+            self.duration_ms = MAX_SPAN_DURATION_MS;
+            self.end_time = self.start_time.saturating_add(MAX_SPAN_DURATION_MS / 1000);
+            self.mark_timing_anomaly("duration_clamped");
+        }
+
         self
     }
+
+    fn mark_timing_anomaly(&mut self, anomaly: &str) {
+        self.tags
+            .entry("span.timing_anomaly".to_string())
+            .and_modify(|existing| {
+                existing.push(',');
+                existing.push_str(anomaly);
+            })
+            .or_insert_with(|| anomaly.to_string());
+    }
+
+    /// Approximate size of the span serialized as JSON, see
+    /// [`TelemetryBatch::estimated_bytes`]
+    pub fn estimated_bytes(&self) -> usize {
+        SPAN_OVERHEAD_BYTES
+            + self.trace_id.len()
+            + self.span_id.len()
+            + optional_len(&self.parent_span_id)
+            + self.operation_name.len()
+            + self.service_name.len()
+            + map_bytes(&self.tags)
+    }
+
+    /// Check the span is well-formed enough for the gateway to accept
+    pub fn validate(&self) -> Result<(), String> {
+        if self.service_name.is_empty() {
+            return Err(format!("span {} has an empty service_name", self.span_id));
+        }
+
+        if self.trace_id.is_empty() || self.span_id.is_empty() {
+            return Err("span is missing its trace_id or span_id".to_string());
+        }
+
+        if self.end_time < self.start_time {
+            return Err(format!(
+                "span {} ends before it starts ({} < {})",
+                self.span_id, self.end_time, self.start_time
+            ));
+        }
+
+        // Start and end are whole seconds, so allow up to a second of truncation
+        let elapsed_ms = (self.end_time - self.start_time).saturating_mul(1000);
+        if self.duration_ms.abs_diff(elapsed_ms) >= 1000 {
+            return Err(format!(
+                "span {} duration {}ms is inconsistent with its {}s start-to-end time",
+                self.span_id,
+                self.duration_ms,
+                self.end_time - self.start_time
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl TelemetryBatch {
@@ -216,24 +418,168 @@ impl TelemetryBatch {
         Self {
             logs,
             spans,
+            metrics: Vec::new(),
             metadata: BatchMetadata {
                 collector_id,
                 batch_id: Uuid::new_v4().to_string(),
+                sequence: 0,
                 timestamp: current_timestamp(),
                 source_pod,
                 source_namespace,
                 version: env!("CARGO_PKG_VERSION").to_string(),
+                resource_attributes: HashMap::new(),
             },
+            created_at: Instant::now(),
         }
     }
 
+    /// How long ago the batch was drained from the buffer
+    pub fn age(&self) -> Duration {
+        self.created_at.elapsed()
+    }
+
+    /// Whether the batch has waited longer than `max_age`; a zero `max_age`
+    /// never expires
+    pub fn is_expired(&self, max_age: Duration) -> bool {
+        !max_age.is_zero() && self.age() > max_age
+    }
+
+    /// Approximate size of the batch serialized as JSON, computed from field
+    /// lengths without serializing. Fixed-width fields are assumed to take
+    /// typical widths and strings to need no escaping, so the estimate is
+    /// usually within a few percent of the real payload.
+    pub fn estimated_bytes(&self) -> usize {
+        let metadata = &self.metadata;
+        let metrics = if self.metrics.is_empty() {
+            0
+        } else {
+            METRICS_FIELD_BYTES + self.metrics.iter().map(MetricPoint::estimated_bytes).sum::<usize>()
+        };
+
+        BATCH_OVERHEAD_BYTES
+            + metadata.collector_id.len()
+            + metadata.source_pod.len()
+            + metadata.source_namespace.len()
+            + map_bytes(&metadata.resource_attributes)
+            + self.logs.iter().map(LogEntry::estimated_bytes).sum::<usize>()
+            + self.spans.iter().map(TraceSpan::estimated_bytes).sum::<usize>()
+            + metrics
+            // Separators between array elements
+            + self.len()
+    }
+
+    /// Check the batch metadata and every entry, returning the first problem found
+    pub fn validate(&self) -> Result<(), String> {
+        if self.metadata.collector_id.is_empty() || self.metadata.batch_id.is_empty() {
+            return Err("batch is missing its collector_id or batch_id".to_string());
+        }
+
+        self.logs.iter().try_for_each(LogEntry::validate)?;
+        self.spans.iter().try_for_each(TraceSpan::validate)
+    }
+
+    /// Remove invalid logs and spans, returning why each one was dropped
+    pub fn drop_invalid(&mut self) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        self.logs.retain(|log| match log.validate() {
+            Ok(()) => true,
+            Err(reason) => {
+                reasons.push(reason);
+                false
+            }
+        });
+
+        self.spans.retain(|span| match span.validate() {
+            Ok(()) => true,
+            Err(reason) => {
+                reasons.push(reason);
+                false
+            }
+        });
+
+        reasons
+    }
+
+    pub fn with_resource_attributes(mut self, attributes: HashMap<String, String>) -> Self {
+        self.metadata.resource_attributes.extend(attributes);
+        self
+    }
+
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.metadata.sequence = sequence;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Vec<MetricPoint>) -> Self {
+        self.metrics.extend(metrics);
+        self
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.logs.is_empty() && self.spans.is_empty()
+        self.logs.is_empty() && self.spans.is_empty() && self.metrics.is_empty()
     }
 
     pub fn len(&self) -> usize {
-        self.logs.len() + self.spans.len()
+        self.logs.len() + self.spans.len() + self.metrics.len()
+    }
+}
+
+impl MetricPoint {
+    pub fn new(name: String, value: f64, service_name: String) -> Self {
+        Self {
+            name,
+            value,
+            timestamp: current_timestamp(),
+            service_name,
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, key: String, value: String) -> Self {
+        self.attributes.insert(key, value);
+        self
+    }
+}
+
+/// Hex digits in a W3C trace id
+pub const TRACE_ID_HEX_LEN: usize = 32;
+
+/// Hex digits in a W3C span id
+pub const SPAN_ID_HEX_LEN: usize = 16;
+
+/// Lowercase `id` if it is a valid W3C id of `hex_len` hex digits. Ids of
+/// the wrong length, with non-hex characters or all zeros are invalid.
+pub fn normalize_id(id: &str, hex_len: usize) -> Option<String> {
+    let valid = id.len() == hex_len
+        && id.bytes().all(|b| b.is_ascii_hexdigit())
+        && id.bytes().any(|b| b != b'0');
+
+    valid.then(|| id.to_ascii_lowercase())
+}
+
+/// Derive a valid id of `hex_len` hex digits from a malformed one; the same
+/// input always gives the same id, across builds and Rust versions, so
+/// collectors derive matching ids for the same trace
+pub fn derive_id(original: &str, hex_len: usize) -> String {
+    let mut id = String::with_capacity(hex_len + 16);
+    let mut round = 0u8;
+    while id.len() < hex_len {
+        id.push_str(&format!("{:016x}", fnv1a(round, original.as_bytes())));
+        round += 1;
     }
+    id.truncate(hex_len);
+    id
+}
+
+/// 64-bit FNV-1a hash of `bytes`, seeded with `round`
+fn fnv1a(round: u8, bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    std::iter::once(round)
+        .chain(bytes.iter().copied())
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
 }
 
 /// Generate a new trace ID
@@ -258,6 +604,194 @@ pub fn current_timestamp() -> u64 {
 mod tests {
     use super::*;
 
+    fn span(start_time: u64, end_time: u64, duration_ms: u64) -> TraceSpan {
+        TraceSpan {
+            start_time,
+            end_time,
+            duration_ms,
+            ..TraceSpan::new(
+                "trace-1".to_string(),
+                "span-1".to_string(),
+                "db.query".to_string(),
+                "test-service".to_string(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_estimated_bytes_close_to_serialized_length() {
+        let logs = (0..40)
+            .map(|i| {
+                let log = LogEntry::new(
+                    LogLevel::Warn,
+                    format!("request {} took longer than expected", i),
+                    "checkout".to_string(),
+                    "checkout-7d9f".to_string(),
+                    "shop".to_string(),
+                )
+                .with_attribute("http.route".to_string(), "/api/orders/{id}".to_string());
+                if i % 2 == 0 {
+                    log.with_trace_context("4bf92f3577b34da6a3ce929d0e0e4736".to_string(), "00f067aa0ba902b7".to_string())
+                } else {
+                    log
+                }
+            })
+            .collect();
+        let spans = (0..10)
+            .map(|i| span(1_700_000_000, 1_700_000_001, 1000 + i).with_parent("b7ad6b7169203331".to_string()))
+            .collect();
+        let metric = MetricPoint {
+            name: "span.requests".to_string(),
+            value: 12.0,
+            timestamp: 1_700_000_000,
+            service_name: "checkout".to_string(),
+            attributes: HashMap::from([("operation".to_string(), "db.query".to_string())]),
+        };
+
+        let batch = TelemetryBatch::new(
+            logs,
+            spans,
+            "collector-1".to_string(),
+            "checkout-7d9f".to_string(),
+            "shop".to_string(),
+        )
+        .with_metrics(vec![metric])
+        .with_resource_attributes(HashMap::from([("team".to_string(), "sre".to_string())]));
+
+        let actual = serde_json::to_vec(&batch).unwrap().len() as f64;
+        let estimate = batch.estimated_bytes() as f64;
+        assert!((estimate - actual).abs() / actual < 0.05, "estimated {} for {} bytes", estimate, actual);
+
+        let log = &batch.logs[0];
+        let actual = serde_json::to_vec(log).unwrap().len() as f64;
+        assert!((log.estimated_bytes() as f64 - actual).abs() / actual < 0.05);
+    }
+
+    #[test]
+    fn test_span_validation() {
+        assert!(span(100, 100, 0).validate().is_ok());
+        assert!(span(100, 102, 2000).validate().is_ok());
+        // Whole-second timestamps truncate, so sub-second differences are fine
+        assert!(span(100, 101, 800).validate().is_ok());
+        assert!(span(100, 100, 999).validate().is_ok());
+
+        assert!(span(100, 99, 0).validate().unwrap_err().contains("ends before it starts"));
+        assert!(span(100, 100, 5000).validate().unwrap_err().contains("inconsistent"));
+        assert!(span(100, 110, 10).validate().unwrap_err().contains("inconsistent"));
+
+        let unnamed = TraceSpan { service_name: String::new(), ..span(100, 100, 0) };
+        assert!(unnamed.validate().is_err());
+
+        let unidentified = TraceSpan { span_id: String::new(), ..span(100, 100, 0) };
+        assert!(unidentified.validate().is_err());
+    }
+
+    #[test]
+    fn test_span_timing_normalization() {
+        let consistent = span(100, 102, 2000).normalize_timing();
+        assert_eq!((consistent.start_time, consistent.end_time, consistent.duration_ms), (100, 102, 2000));
+        assert!(!consistent.tags.contains_key("span.timing_anomaly"));
+
+        let swapped = span(102, 100, 2000).normalize_timing();
+        assert_eq!((swapped.start_time, swapped.end_time), (100, 102));
+        assert_eq!(swapped.tags["span.timing_anomaly"], "end_before_start");
+        assert!(swapped.validate().is_ok());
+
+        let mismatched = span(100, 110, 1500).normalize_timing();
+        assert_eq!((mismatched.end_time, mismatched.duration_ms), (101, 1500));
+        assert_eq!(mismatched.tags["span.timing_anomaly"], "duration_mismatch");
+
+        let endless = span(100, 100, 0).set_duration_ms(MAX_SPAN_DURATION_MS * 2);
+        assert_eq!(endless.duration_ms, MAX_SPAN_DURATION_MS);
+        assert_eq!(endless.tags["span.timing_anomaly"], "duration_clamped");
+        assert!(endless.validate().is_ok());
+    }
+
+    #[test]
+    fn test_finish_with_clock_skew() {
+        // Started "in the future", as if the clock stepped back mid-span
+        let skewed = TraceSpan {
+            start_time: current_timestamp() + 3600,
+            ..span(0, 0, 0)
+        }
+        .finish();
+
+        assert_eq!(skewed.duration_ms, 0);
+        assert_eq!(skewed.end_time, skewed.start_time);
+        assert_eq!(skewed.tags["span.timing_anomaly"], "clock_skew");
+
+        let normal = span(current_timestamp(), 0, 0).finish();
+        assert!(!normal.tags.contains_key("span.timing_anomaly"));
+        assert!(normal.validate().is_ok());
+    }
+
+    #[test]
+    fn test_log_validation() {
+        let log = LogEntry::new(
+            LogLevel::Info,
+            "hello".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        assert!(log.validate().is_ok());
+
+        let unnamed = LogEntry { service_name: String::new(), ..log.clone() };
+        assert!(unnamed.validate().is_err());
+
+        let out_of_range = LogEntry { severity_number: 25, ..log };
+        assert!(out_of_range.validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_drop_invalid() {
+        let valid_log = LogEntry::new(
+            LogLevel::Info,
+            "hello".to_string(),
+            "test-service".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        let invalid_log = LogEntry { service_name: String::new(), ..valid_log.clone() };
+
+        let mut batch = TelemetryBatch::new(
+            vec![valid_log, invalid_log],
+            vec![span(100, 101, 1000), span(100, 99, 0)],
+            "collector-1".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        assert!(batch.validate().is_err());
+
+        let reasons = batch.drop_invalid();
+
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(batch.logs.len(), 1);
+        assert_eq!(batch.spans.len(), 1);
+        assert!(batch.validate().is_ok());
+    }
+
+    #[test]
+    fn test_severity_numbers() {
+        let expected = [
+            (LogLevel::Trace, 1),
+            (LogLevel::Debug, 5),
+            (LogLevel::Info, 9),
+            (LogLevel::Warn, 13),
+            (LogLevel::Error, 17),
+            (LogLevel::Fatal, 21),
+        ];
+
+        for (level, number) in expected {
+            assert_eq!(level.severity_number(), number);
+            assert_eq!(LogLevel::from_severity_number(number), Some(level));
+        }
+
+        assert_eq!(LogLevel::from_severity_number(18), Some(LogLevel::Error));
+        assert_eq!(LogLevel::from_severity_number(0), None);
+        assert_eq!(LogLevel::from_severity_number(25), None);
+    }
+
     #[test]
     fn test_log_level_from_str() {
         assert_eq!(LogLevel::from("INFO"), LogLevel::Info);
@@ -327,4 +861,21 @@ mod tests {
         assert!(!batch.is_empty());
         assert_eq!(batch.metadata.source_pod, "test-pod");
     }
+
+    #[test]
+    fn test_batch_expiry() {
+        let mut batch = TelemetryBatch::new(
+            vec![],
+            vec![],
+            "collector-1".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        assert!(!batch.is_expired(Duration::from_secs(60)));
+
+        batch.created_at = Instant::now().checked_sub(Duration::from_secs(120)).unwrap();
+        assert!(batch.is_expired(Duration::from_secs(60)));
+        assert!(!batch.is_expired(Duration::ZERO));
+    }
 }
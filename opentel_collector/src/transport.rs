@@ -1,13 +1,53 @@
 //! HTTP transport layer for sending telemetry data to the gateway
 
+use crate::config::{Config, GatewayFormat, HttpVersion, TlsBackend, TlsVersion};
+use crate::otlp;
 use crate::telemetry::TelemetryBatch;
 use crate::errors::{CollectorError, Result};
-use reqwest::{Client, Response};
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Client, ClientBuilder, Response};
+use serde::Deserialize;
 use serde_json::Value;
+use std::future::Future;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::sync::Semaphore;
 use tokio::time::{sleep, timeout};
 use tracing::{debug, warn, error, info};
 
+/// Destination for telemetry batches
+#[async_trait]
+pub trait Transport: Send + Sync + std::fmt::Debug {
+    /// Deliver a batch, retrying as appropriate for the transport, and report
+    /// how many bytes it took on the wire
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize>;
+
+    /// Check whether the destination is reachable
+    async fn test_connectivity(&self) -> bool;
+}
+
+/// Size of a delivered batch payload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayloadSize {
+    pub uncompressed_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+impl PayloadSize {
+    /// Size of a payload sent without compression
+    pub fn uncompressed(bytes: usize) -> Self {
+        Self {
+            uncompressed_bytes: bytes as u64,
+            compressed_bytes: bytes as u64,
+        }
+    }
+}
+
 /// HTTP transport for telemetry data
 #[derive(Debug, Clone)]
 pub struct HttpTransport {
@@ -16,35 +56,251 @@ pub struct HttpTransport {
     timeout: Duration,
     max_retries: u32,
     retry_backoff_ms: u64,
+    max_batch_send_age: Duration,
+    /// Configured payload format; `Auto` follows the gateway's health endpoint
+    format: GatewayFormat,
+    /// Format batches are currently sent in
+    payload_format: Arc<RwLock<PayloadFormat>>,
+    /// Whether JSON batches must be confirmed by a delivery receipt
+    delivery_receipts: bool,
+    /// Smallest body that is gzipped; `None` when compression is off
+    gzip_min_bytes: Option<usize>,
+    /// TLS floor the client was built with, reported by the connectivity test
+    tls_min_version: TlsVersion,
+    /// TLS implementation the client was built with, reported by the
+    /// connectivity test
+    tls_backend: TlsBackend,
+}
+
+/// Wire format of batches sent to the gateway
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// The collector's JSON batch, posted to `/v1/telemetry`
+    Json,
+    /// OTLP/HTTP JSON, one request per signal to `/v1/logs`, `/v1/traces`
+    /// and `/v1/metrics`
+    Otlp,
+}
+
+impl PayloadFormat {
+    /// Preferred format among those a gateway advertises, OTLP first; `None`
+    /// when it advertises neither
+    pub fn select(supported_formats: &[String]) -> Option<Self> {
+        let supports = |name: &str| supported_formats.iter().any(|format| format.eq_ignore_ascii_case(name));
+
+        if supports("otlp") {
+            Some(PayloadFormat::Otlp)
+        } else if supports("json") {
+            Some(PayloadFormat::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// Connection reuse settings for the gateway HTTP client
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionOptions {
+    pub http_version: HttpVersion,
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+    /// Limit on establishing a connection, separate from the request timeout
+    pub connect_timeout: Option<Duration>,
+    /// Handshakes negotiating a lower TLS version fail
+    pub tls_min_version: TlsVersion,
+    /// TLS implementation; only `Rustls` restricts cipher suites, `Native`
+    /// follows the system OpenSSL policy
+    pub tls_backend: TlsBackend,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+impl ConnectionOptions {
+    /// Build connection options from the collector configuration, treating
+    /// zero durations as "no limit" / "disabled"
+    pub fn from_config(config: &Config) -> Self {
+        let non_zero = |duration: Duration| (!duration.is_zero()).then_some(duration);
+
+        Self {
+            http_version: config.gateway_http_version,
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            pool_idle_timeout: non_zero(config.pool_idle_timeout),
+            tcp_keepalive: non_zero(config.tcp_keepalive),
+            connect_timeout: non_zero(config.connect_timeout),
+            tls_min_version: config.gateway_tls_min_version,
+            tls_backend: config.gateway_tls_backend,
+        }
+    }
+
+    fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        let mut builder = builder
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        builder = match self.tls_backend {
+            TlsBackend::Native => builder.use_native_tls(),
+            TlsBackend::Rustls => builder.use_rustls_tls(),
+        };
+        let tls_min_version = match self.tls_min_version {
+            TlsVersion::Tls10 => reqwest::tls::Version::TLS_1_0,
+            TlsVersion::Tls11 => reqwest::tls::Version::TLS_1_1,
+            TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+        };
+        builder = builder.min_tls_version(tls_min_version);
+
+        match self.http_version {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+        }
+    }
 }
 
 impl HttpTransport {
     /// Create a new HTTP transport
     pub fn new(
         gateway_url: String,
-        http_timeout: Duration,
+        request_timeout: Duration,
+        max_retries: u32,
+        retry_backoff_ms: u64,
+    ) -> Result<Self> {
+        Self::with_connection_options(
+            gateway_url,
+            request_timeout,
+            max_retries,
+            retry_backoff_ms,
+            ConnectionOptions::default(),
+        )
+    }
+
+    /// Create an HTTP transport from the collector configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let transport = Self::with_connection_options(
+            config.gateway_url.clone(),
+            config.request_timeout,
+            config.max_retries,
+            config.retry_backoff_ms,
+            ConnectionOptions::from_config(config),
+        )?;
+
+        Ok(transport
+            .with_max_batch_send_age(config.max_batch_send_age)
+            .with_format(config.gateway_format)
+            .with_delivery_receipts(config.delivery_receipts)
+            .with_gzip(config.gzip_compression.then_some(config.compression_min_bytes)))
+    }
+
+    /// Create an HTTP transport with explicit connection reuse settings
+    pub fn with_connection_options(
+        gateway_url: String,
+        request_timeout: Duration,
         max_retries: u32,
         retry_backoff_ms: u64,
+        options: ConnectionOptions,
     ) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(http_timeout)
-            .user_agent(format!("opentel_collector/{}", env!("CARGO_PKG_VERSION")))
+        let builder = Client::builder()
+            .timeout(request_timeout)
+            .user_agent(format!("opentel_collector/{}", env!("CARGO_PKG_VERSION")));
+
+        let client = options
+            .apply(builder)
             .build()
             .map_err(CollectorError::Http)?;
+        let (tls_min_version, tls_backend) = (options.tls_min_version, options.tls_backend);
 
         Ok(Self {
             client,
             gateway_url,
-            timeout: http_timeout,
+            timeout: request_timeout,
             max_retries,
             retry_backoff_ms,
+            max_batch_send_age: Duration::ZERO,
+            format: GatewayFormat::Auto,
+            payload_format: Arc::new(RwLock::new(PayloadFormat::Json)),
+            delivery_receipts: false,
+            gzip_min_bytes: None,
+            tls_min_version,
+            tls_backend,
         })
     }
 
+    /// Send batches in a fixed format, or `Auto` to use what the gateway
+    /// advertises at the next connectivity test (JSON until then)
+    pub fn with_format(mut self, format: GatewayFormat) -> Self {
+        let payload_format = match format {
+            GatewayFormat::Otlp => PayloadFormat::Otlp,
+            GatewayFormat::Auto | GatewayFormat::Json => PayloadFormat::Json,
+        };
+        self.format = format;
+        self.payload_format = Arc::new(RwLock::new(payload_format));
+        self
+    }
+
+    /// Format batches are currently sent in
+    pub fn payload_format(&self) -> PayloadFormat {
+        *self.payload_format.read().unwrap()
+    }
+
+    /// Stop retrying batches drained longer ago than `max_age`; zero retries
+    /// regardless of age
+    pub fn with_max_batch_send_age(mut self, max_age: Duration) -> Self {
+        self.max_batch_send_age = max_age;
+        self
+    }
+
+    /// Require the gateway to confirm each JSON batch by listing its id in
+    /// a [`DeliveryReceipt`]. A batch the receipt leaves out is treated as
+    /// failed and retried, so the gateway should deduplicate by batch id.
+    /// OTLP requests have their own response schema and are not checked.
+    pub fn with_delivery_receipts(mut self, enabled: bool) -> Self {
+        self.delivery_receipts = enabled;
+        self
+    }
+
+    /// Gzip request bodies of at least `min_bytes`, sending them with
+    /// `Content-Encoding: gzip`; `None` sends every body uncompressed
+    pub fn with_gzip(mut self, min_bytes: Option<usize>) -> Self {
+        self.gzip_min_bytes = min_bytes;
+        self
+    }
+
     /// Send a telemetry batch to the gateway
-    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<()> {
-        let url = format!("{}/v1/telemetry", self.gateway_url);
+    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        match self.payload_format() {
+            PayloadFormat::Json => {
+                let url = format!("{}/v1/telemetry", self.gateway_url);
+                // Serialize once; retries share the same buffer
+                let body = self.serialize_batch(&batch)?;
+                self.send_with_retries(&batch, &url, body).await
+            }
+            PayloadFormat::Otlp => {
+                // Each signal is delivered (and retried) on its own, so a
+                // failure after logs were accepted does not resend them
+                let mut size = PayloadSize::default();
+                for (path, request) in otlp::export_requests(&batch) {
+                    let url = format!("{}{}", self.gateway_url, path);
+                    let body = Bytes::from(serde_json::to_vec(&request)?);
+                    let sent = self.send_with_retries(&batch, &url, body).await?;
+                    size.uncompressed_bytes += sent.uncompressed_bytes;
+                    size.compressed_bytes += sent.compressed_bytes;
+                }
+                Ok(size)
+            }
+        }
+    }
 
+    /// Post one body for a batch, retrying with backoff
+    async fn send_with_retries(&self, batch: &TelemetryBatch, url: &str, body: Bytes) -> Result<PayloadSize> {
         debug!(
             "Sending batch {} with {} logs and {} spans to {}",
             batch.metadata.batch_id,
@@ -53,62 +309,72 @@ impl HttpTransport {
             url
         );
 
-        let mut attempt = 0;
-        let mut last_error = None;
-
-        while attempt <= self.max_retries {
-            match self.send_batch_attempt(&url, &batch).await {
-                Ok(_) => {
-                    info!(
-                        "Successfully sent batch {} (attempt {})",
-                        batch.metadata.batch_id,
-                        attempt + 1
-                    );
-                    return Ok(());
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    attempt += 1;
-
-                    if attempt <= self.max_retries {
-                        let backoff_ms = self.retry_backoff_ms * (2_u64.pow(attempt - 1));
-                        warn!(
-                            "Failed to send batch {} (attempt {}), retrying in {}ms: {}",
-                            batch.metadata.batch_id,
-                            attempt,
-                            backoff_ms,
-                            last_error.as_ref().unwrap()
-                        );
-                        sleep(Duration::from_millis(backoff_ms)).await;
-                    }
-                }
-            }
-        }
+        let uncompressed_bytes = body.len();
+        let (body, gzipped) = self.encode_body(body)?;
 
-        let final_error = last_error.unwrap_or(CollectorError::Other(
-            "All retry attempts failed".to_string()
-        ));
+        let retry_policy = RetryPolicy {
+            max_retries: self.max_retries,
+            retry_backoff_ms: self.retry_backoff_ms,
+            max_batch_send_age: self.max_batch_send_age,
+        };
+        let attempts = retry_policy
+            .run(batch, "send", || async {
+                self.send_batch_attempt(url, body.clone(), gzipped, &batch.metadata.batch_id)
+                    .await
+                    .map_err(AttemptError::Retryable)
+            })
+            .await?;
 
-        error!(
-            "Failed to send batch {} after {} attempts: {}",
+        info!(
+            "Successfully sent batch {} (attempt {}, {} bytes)",
             batch.metadata.batch_id,
-            self.max_retries + 1,
-            final_error
+            attempts,
+            body.len()
         );
+        Ok(PayloadSize {
+            uncompressed_bytes: uncompressed_bytes as u64,
+            compressed_bytes: body.len() as u64,
+        })
+    }
+
+    /// Gzip a body when compression is on and it reaches the threshold,
+    /// returning the body to send and whether it was compressed
+    fn encode_body(&self, body: Bytes) -> Result<(Bytes, bool)> {
+        match self.gzip_min_bytes {
+            Some(min_bytes) if body.len() >= min_bytes => {
+                let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+                encoder.write_all(&body)?;
+                Ok((Bytes::from(encoder.finish()?), true))
+            }
+            _ => Ok((body, false)),
+        }
+    }
 
-        Err(final_error)
+    /// Serialize a batch into a buffer pre-sized from its estimated size, so
+    /// it is written without the buffer repeatedly growing
+    fn serialize_batch(&self, batch: &TelemetryBatch) -> Result<Bytes> {
+        let estimate = batch.estimated_bytes();
+        let mut buffer = Vec::with_capacity(estimate + estimate / 8);
+        serde_json::to_writer(&mut buffer, batch)?;
+        Ok(Bytes::from(buffer))
     }
 
     /// Single attempt to send a batch
-    async fn send_batch_attempt(&self, url: &str, batch: &TelemetryBatch) -> Result<()> {
-        let response = timeout(
-            self.timeout,
-            self.client.post(url).json(batch).send()
-        ).await
-        .map_err(|_| CollectorError::Transport("Request timeout".to_string()))?
-        .map_err(CollectorError::Http)?;
+    async fn send_batch_attempt(&self, url: &str, body: Bytes, gzipped: bool, batch_id: &str) -> Result<()> {
+        let mut request = self.client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json");
+        if gzipped {
+            request = request.header(CONTENT_ENCODING, "gzip");
+        }
+        let request = request.body(body);
+
+        let response = timeout(self.timeout, request.send())
+            .await
+            .map_err(|_| CollectorError::Transport("Request timeout".to_string()))?
+            .map_err(CollectorError::Http)?;
 
-        self.handle_response(response, &batch.metadata.batch_id).await
+        self.handle_response(response, batch_id).await
     }
 
     /// Handle the HTTP response from the gateway
@@ -116,6 +382,9 @@ impl HttpTransport {
         let status = response.status();
 
         if status.is_success() {
+            if self.delivery_receipts && self.payload_format() == PayloadFormat::Json {
+                return Self::check_receipt(response, batch_id).await;
+            }
             debug!("Batch {} accepted by gateway", batch_id);
             return Ok(());
         }
@@ -136,6 +405,24 @@ impl HttpTransport {
         Err(CollectorError::Transport(error_message))
     }
 
+    /// Check that a successful response's receipt lists the batch
+    async fn check_receipt(response: Response, batch_id: &str) -> Result<()> {
+        let body = response.bytes().await.map_err(CollectorError::Http)?;
+        let receipt: DeliveryReceipt = serde_json::from_slice(&body).map_err(|e| {
+            CollectorError::Unconfirmed(format!("response for batch {} is not a delivery receipt: {}", batch_id, e))
+        })?;
+
+        if receipt.confirms(batch_id) {
+            debug!("Batch {} confirmed by gateway", batch_id);
+            Ok(())
+        } else {
+            Err(CollectorError::Unconfirmed(format!(
+                "gateway accepted {:?} but not batch {}",
+                receipt.accepted_batch_ids, batch_id
+            )))
+        }
+    }
+
     /// Health check the gateway endpoint
     pub async fn health_check(&self) -> Result<GatewayHealth> {
         let url = format!("{}/health", self.gateway_url);
@@ -156,9 +443,20 @@ impl HttpTransport {
             )));
         }
 
+        let protocol = format!("{:?}", response.version());
         let health_data: Value = response.json().await.map_err(CollectorError::Http)?;
+        let supported_formats = health_data["supported_formats"]
+            .as_array()
+            .map(|formats| {
+                formats
+                    .iter()
+                    .filter_map(|format| format.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         Ok(GatewayHealth {
+            protocol,
             status: health_data["status"]
                 .as_str()
                 .unwrap_or("unknown")
@@ -171,26 +469,54 @@ impl HttpTransport {
                 .as_str()
                 .unwrap_or("unknown")
                 .to_string(),
+            supported_formats,
         })
     }
 
+    /// Switch to the gateway's preferred format when the format is `Auto`
+    /// and the gateway advertises one we support
+    fn negotiate_format(&self, health: &GatewayHealth) {
+        if self.format != GatewayFormat::Auto {
+            return;
+        }
+
+        if let Some(selected) = PayloadFormat::select(&health.supported_formats) {
+            let mut payload_format = self.payload_format.write().unwrap();
+            if *payload_format != selected {
+                info!("Gateway advertises {:?}, sending batches as {:?}", health.supported_formats, selected);
+                *payload_format = selected;
+            }
+        }
+    }
+
     /// Test connectivity to the gateway
     pub async fn test_connectivity(&self) -> bool {
         match self.health_check().await {
             Ok(health) => {
                 info!(
-                    "Gateway connectivity test successful: {} v{} - {}",
-                    health.service, health.version, health.status
+                    "Gateway connectivity test successful: {} v{} - {} over {}{}",
+                    health.service, health.version, health.status, health.protocol, self.tls_summary()
                 );
+                self.negotiate_format(&health);
                 true
             }
             Err(e) => {
-                warn!("Gateway connectivity test failed: {}", e);
+                warn!("Gateway connectivity test failed{}: {}", self.tls_summary(), e);
                 false
             }
         }
     }
 
+    /// TLS settings for log lines about an HTTPS gateway. The client does
+    /// not expose the negotiated version, but a handshake below the floor
+    /// fails, so a working connection is at least at the floor.
+    fn tls_summary(&self) -> String {
+        if !self.gateway_url.starts_with("https://") {
+            return String::new();
+        }
+        format!(" (TLS {} or newer via {:?})", self.tls_min_version, self.tls_backend)
+    }
+
     /// Get transport statistics
     pub fn stats(&self) -> TransportStats {
         TransportStats {
@@ -202,12 +528,44 @@ impl HttpTransport {
     }
 }
 
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        HttpTransport::send_batch(self, batch).await
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        HttpTransport::test_connectivity(self).await
+    }
+}
+
+/// Body of a successful gateway response when delivery receipts are
+/// enabled, e.g. `{"accepted_batch_ids": ["7f0c..."]}`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeliveryReceipt {
+    /// Ids of the batches the gateway has durably stored
+    #[serde(default)]
+    pub accepted_batch_ids: Vec<String>,
+}
+
+impl DeliveryReceipt {
+    /// Whether the receipt confirms the batch
+    pub fn confirms(&self, batch_id: &str) -> bool {
+        self.accepted_batch_ids.iter().any(|id| id == batch_id)
+    }
+}
+
 /// Gateway health information
 #[derive(Debug, Clone)]
 pub struct GatewayHealth {
     pub status: String,
     pub service: String,
     pub version: String,
+    /// HTTP protocol version negotiated for the health check, e.g. `HTTP/2.0`
+    pub protocol: String,
+    /// Payload formats the gateway accepts, e.g. `["otlp", "json"]`; empty
+    /// when it does not say
+    pub supported_formats: Vec<String>,
 }
 
 /// Transport statistics
@@ -222,28 +580,63 @@ pub struct TransportStats {
 /// Batch transport with enhanced error handling and metrics
 #[derive(Debug)]
 pub struct EnhancedTransport {
-    transport: HttpTransport,
+    transport: Box<dyn Transport>,
     metrics: TransportMetrics,
+    in_flight: Semaphore,
+    max_batch_send_age: Duration,
 }
 
 impl EnhancedTransport {
-    pub fn new(transport: HttpTransport) -> Self {
+    pub fn new(transport: impl Transport + 'static) -> Self {
         Self {
-            transport,
+            transport: Box::new(transport),
             metrics: TransportMetrics::new(),
+            in_flight: Semaphore::new(1),
+            max_batch_send_age: Duration::ZERO,
         }
     }
 
-    /// Send a batch with metrics tracking
-    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<()> {
+    /// Allow up to `max_in_flight` batches to be sent concurrently
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.in_flight = Semaphore::new(max_in_flight.max(1));
+        self
+    }
+
+    /// Drop batches that were drained longer ago than `max_age` by the time an
+    /// in-flight slot frees up; zero sends regardless of age
+    pub fn with_max_batch_send_age(mut self, max_age: Duration) -> Self {
+        self.max_batch_send_age = max_age;
+        self
+    }
+
+    /// Test connectivity of the underlying transport
+    pub async fn test_connectivity(&self) -> bool {
+        self.transport.test_connectivity().await
+    }
+
+    /// Send a batch with metrics tracking, waiting for an in-flight slot first
+    pub async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let _permit = self.in_flight.acquire().await.map_err(|_| {
+            CollectorError::Transport("Transport is shut down".to_string())
+        })?;
+
+        if batch.is_expired(self.max_batch_send_age) {
+            self.metrics.record_expired().await;
+            return Err(expired_error(&batch));
+        }
+
         let start_time = std::time::Instant::now();
         self.metrics.increment_attempts().await;
 
         match self.transport.send_batch(batch).await {
-            Ok(()) => {
+            Ok(size) => {
                 let duration = start_time.elapsed();
-                self.metrics.record_success(duration).await;
-                Ok(())
+                self.metrics.record_success(duration, size).await;
+                Ok(size)
+            }
+            Err(e @ CollectorError::BatchExpired(_)) => {
+                self.metrics.record_expired().await;
+                Err(e)
             }
             Err(e) => {
                 let duration = start_time.elapsed();
@@ -253,6 +646,16 @@ impl EnhancedTransport {
         }
     }
 
+    /// Send a batch without counting it in the transport metrics, e.g. the
+    /// collector's own heartbeats, still waiting for an in-flight slot
+    pub async fn send_untracked(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let _permit = self.in_flight.acquire().await.map_err(|_| {
+            CollectorError::Transport("Transport is shut down".to_string())
+        })?;
+
+        self.transport.send_batch(batch).await
+    }
+
     /// Get transport metrics
     pub async fn metrics(&self) -> TransportMetricsSnapshot {
         self.metrics.snapshot().await
@@ -264,15 +667,99 @@ impl EnhancedTransport {
     }
 }
 
+/// Why a send attempt failed, deciding whether it is retried
+#[derive(Debug)]
+pub(crate) enum AttemptError {
+    /// The attempt may succeed later, e.g. the destination was unreachable
+    Retryable(CollectorError),
+    /// The destination will never accept the batch
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    Fatal(CollectorError),
+}
+
+/// Retries shared by the transports: up to `max_retries` retries with a
+/// backoff doubling from `retry_backoff_ms`, given up early once the batch
+/// is older than `max_batch_send_age` (zero retries regardless of age)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) retry_backoff_ms: u64,
+    pub(crate) max_batch_send_age: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry, counting from 1
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(retry.saturating_sub(1));
+        Duration::from_millis(self.retry_backoff_ms.saturating_mul(factor))
+    }
+
+    /// Make `attempt` until it succeeds, returning the number of attempts
+    /// taken. `action` names the operation in log messages, e.g. `send`.
+    pub(crate) async fn run<F, Fut>(&self, batch: &TelemetryBatch, action: &str, mut attempt: F) -> Result<u32>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<(), AttemptError>>,
+    {
+        let batch_id = &batch.metadata.batch_id;
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            let e = match attempt().await {
+                Ok(()) => return Ok(attempts),
+                Err(AttemptError::Fatal(e)) => {
+                    error!("Batch {} was rejected, not retrying: {}", batch_id, e);
+                    return Err(e);
+                }
+                Err(AttemptError::Retryable(e)) => e,
+            };
+
+            if attempts > self.max_retries {
+                error!("Failed to {} batch {} after {} attempts: {}", action, batch_id, attempts, e);
+                return Err(e);
+            }
+
+            // Stale data is not worth retrying once fresher batches are waiting
+            if batch.is_expired(self.max_batch_send_age) {
+                return Err(expired_error(batch));
+            }
+
+            let backoff = self.backoff(attempts);
+            warn!(
+                "Failed to {} batch {} (attempt {}), retrying in {}ms: {}",
+                action,
+                batch_id,
+                attempts,
+                backoff.as_millis(),
+                e
+            );
+            sleep(backoff).await;
+        }
+    }
+}
+
+pub(crate) fn expired_error(batch: &TelemetryBatch) -> CollectorError {
+    CollectorError::BatchExpired(format!(
+        "batch {} with {} logs and {} spans dropped after waiting {}s",
+        batch.metadata.batch_id,
+        batch.logs.len(),
+        batch.spans.len(),
+        batch.age().as_secs()
+    ))
+}
+
 /// Transport metrics tracking
 #[derive(Debug)]
 struct TransportMetrics {
     attempts: tokio::sync::RwLock<u64>,
     successes: tokio::sync::RwLock<u64>,
     failures: tokio::sync::RwLock<u64>,
+    expired: tokio::sync::RwLock<u64>,
     total_duration: tokio::sync::RwLock<Duration>,
     min_duration: tokio::sync::RwLock<Option<Duration>>,
     max_duration: tokio::sync::RwLock<Option<Duration>>,
+    bytes_sent: tokio::sync::RwLock<PayloadSize>,
 }
 
 impl TransportMetrics {
@@ -281,9 +768,11 @@ impl TransportMetrics {
             attempts: tokio::sync::RwLock::new(0),
             successes: tokio::sync::RwLock::new(0),
             failures: tokio::sync::RwLock::new(0),
+            expired: tokio::sync::RwLock::new(0),
             total_duration: tokio::sync::RwLock::new(Duration::ZERO),
             min_duration: tokio::sync::RwLock::new(None),
             max_duration: tokio::sync::RwLock::new(None),
+            bytes_sent: tokio::sync::RwLock::new(PayloadSize::default()),
         }
     }
 
@@ -292,11 +781,16 @@ impl TransportMetrics {
         *attempts += 1;
     }
 
-    async fn record_success(&self, duration: Duration) {
+    async fn record_success(&self, duration: Duration, size: PayloadSize) {
         let mut successes = self.successes.write().await;
         *successes += 1;
         drop(successes);
 
+        let mut bytes_sent = self.bytes_sent.write().await;
+        bytes_sent.uncompressed_bytes += size.uncompressed_bytes;
+        bytes_sent.compressed_bytes += size.compressed_bytes;
+        drop(bytes_sent);
+
         self.update_duration_stats(duration).await;
     }
 
@@ -308,6 +802,11 @@ impl TransportMetrics {
         self.update_duration_stats(duration).await;
     }
 
+    async fn record_expired(&self) {
+        let mut expired = self.expired.write().await;
+        *expired += 1;
+    }
+
     async fn update_duration_stats(&self, duration: Duration) {
         let mut total = self.total_duration.write().await;
         *total += duration;
@@ -325,9 +824,11 @@ impl TransportMetrics {
         let attempts = *self.attempts.read().await;
         let successes = *self.successes.read().await;
         let failures = *self.failures.read().await;
+        let expired = *self.expired.read().await;
         let total_duration = *self.total_duration.read().await;
         let min_duration = *self.min_duration.read().await;
         let max_duration = *self.max_duration.read().await;
+        let bytes_sent = *self.bytes_sent.read().await;
 
         let success_rate = if attempts > 0 {
             (successes as f64 / attempts as f64) * 100.0
@@ -341,14 +842,27 @@ impl TransportMetrics {
             Duration::ZERO
         };
 
+        let compression_ratio = if bytes_sent.compressed_bytes > 0 {
+            bytes_sent.uncompressed_bytes as f64 / bytes_sent.compressed_bytes as f64
+        } else {
+            1.0
+        };
+
+        let avg_batch_bytes = bytes_sent.uncompressed_bytes.checked_div(successes).unwrap_or(0);
+
         TransportMetricsSnapshot {
             attempts,
             successes,
             failures,
+            expired,
             success_rate,
             avg_duration_ms: avg_duration.as_millis() as u64,
             min_duration_ms: min_duration.map(|d| d.as_millis() as u64),
             max_duration_ms: max_duration.map(|d| d.as_millis() as u64),
+            bytes_sent_uncompressed: bytes_sent.uncompressed_bytes,
+            bytes_sent_compressed: bytes_sent.compressed_bytes,
+            compression_ratio,
+            avg_batch_bytes,
         }
     }
 
@@ -356,9 +870,11 @@ impl TransportMetrics {
         *self.attempts.write().await = 0;
         *self.successes.write().await = 0;
         *self.failures.write().await = 0;
+        *self.expired.write().await = 0;
         *self.total_duration.write().await = Duration::ZERO;
         *self.min_duration.write().await = None;
         *self.max_duration.write().await = None;
+        *self.bytes_sent.write().await = PayloadSize::default();
     }
 }
 
@@ -368,16 +884,26 @@ pub struct TransportMetricsSnapshot {
     pub attempts: u64,
     pub successes: u64,
     pub failures: u64,
+    /// Batches dropped for exceeding the maximum send age
+    pub expired: u64,
     pub success_rate: f64,
     pub avg_duration_ms: u64,
     pub min_duration_ms: Option<u64>,
     pub max_duration_ms: Option<u64>,
+    /// Payload bytes of delivered batches before compression
+    pub bytes_sent_uncompressed: u64,
+    /// Payload bytes of delivered batches as sent on the wire
+    pub bytes_sent_compressed: u64,
+    /// Uncompressed over compressed bytes; 1.0 without compression
+    pub compression_ratio: f64,
+    pub avg_batch_bytes: u64,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::telemetry::{LogEntry, LogLevel, BatchMetadata};
+    use crate::telemetry::{LogEntry, LogLevel};
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_transport_creation() {
@@ -413,6 +939,189 @@ mod tests {
         assert_eq!(metrics.success_rate, 0.0);
     }
 
+    #[test]
+    fn test_serialize_batch_presized_from_estimate() {
+        let transport = HttpTransport::new(
+            "http://localhost:8080".to_string(),
+            Duration::from_secs(1),
+            0,
+            1000,
+        ).unwrap();
+
+        let logs = (0..50)
+            .map(|i| LogEntry::new(
+                LogLevel::Info,
+                format!("Message {}", i),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            ))
+            .collect();
+        let batch = TelemetryBatch::new(
+            logs,
+            vec![],
+            "collector-1".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+
+        let body = transport.serialize_batch(&batch).unwrap();
+        assert_eq!(body.as_ref(), serde_json::to_vec(&batch).unwrap().as_slice());
+
+        // The buffer was large enough up front
+        let estimate = batch.estimated_bytes();
+        assert!(body.len() <= estimate + estimate / 8);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            retry_backoff_ms: 1,
+            max_batch_send_age: Duration::ZERO,
+        };
+        let batch = TelemetryBatch::new(
+            vec![],
+            vec![],
+            "collector-1".to_string(),
+            "test-pod".to_string(),
+            "test-namespace".to_string(),
+        );
+        let attempts = AtomicUsize::new(0);
+        let failing = |fatal: bool| {
+            let attempts = &attempts;
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                let e = CollectorError::Transport("unavailable".to_string());
+                Err(if fatal { AttemptError::Fatal(e) } else { AttemptError::Retryable(e) })
+            }
+        };
+
+        assert!(policy.run(&batch, "send", failing(false)).await.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 3);
+        assert!(policy.run(&batch, "send", failing(true)).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        assert_eq!(policy.run(&batch, "send", || async { Ok(()) }).await.unwrap(), 1);
+        assert_eq!(policy.backoff(3), Duration::from_millis(4));
+        assert_eq!(policy.backoff(100), Duration::from_millis(u64::MAX));
+    }
+
+    /// Transport that tracks how many sends are running at once
+    #[derive(Debug, Clone, Default)]
+    struct ConcurrencyTrackingTransport {
+        current: Arc<AtomicUsize>,
+        max_observed: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Transport for ConcurrencyTrackingTransport {
+        async fn send_batch(&self, _batch: TelemetryBatch) -> Result<PayloadSize> {
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_observed.fetch_max(current, Ordering::SeqCst);
+            sleep(Duration::from_millis(20)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok(PayloadSize::default())
+        }
+
+        async fn test_connectivity(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_batches() {
+        let tracker = ConcurrencyTrackingTransport::default();
+        let enhanced = EnhancedTransport::new(tracker.clone()).with_max_in_flight(3);
+
+        let sends = (0..10).map(|_| {
+            enhanced.send_batch(TelemetryBatch::new(
+                vec![],
+                vec![],
+                "collector-1".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            ))
+        });
+        let results = futures::future::join_all(sends).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(tracker.max_observed.load(Ordering::SeqCst), 3);
+        assert_eq!(enhanced.metrics().await.successes, 10);
+    }
+
+    #[tokio::test]
+    async fn test_untracked_sends_leave_metrics_unchanged() {
+        let enhanced = EnhancedTransport::new(ConcurrencyTrackingTransport::default());
+        let batch = || {
+            TelemetryBatch::new(
+                vec![],
+                vec![],
+                "collector-1".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )
+        };
+
+        enhanced.send_batch(batch()).await.unwrap();
+        let before = enhanced.metrics().await;
+        enhanced.send_untracked(batch()).await.unwrap();
+        let after = enhanced.metrics().await;
+
+        assert_eq!(after.attempts, before.attempts);
+        assert_eq!(after.successes, before.successes);
+        assert_eq!(after.bytes_sent_uncompressed, before.bytes_sent_uncompressed);
+    }
+
+    #[test]
+    fn test_connection_options_from_config() {
+        let config = Config {
+            pool_max_idle_per_host: 4,
+            pool_idle_timeout: Duration::from_secs(30),
+            tcp_keepalive: Duration::ZERO,
+            connect_timeout: Duration::from_millis(500),
+            ..Config::default()
+        };
+
+        let options = ConnectionOptions::from_config(&config);
+
+        assert_eq!(options.pool_max_idle_per_host, 4);
+        assert_eq!(options.pool_idle_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(options.tcp_keepalive, None);
+        assert_eq!(options.connect_timeout, Some(Duration::from_millis(500)));
+
+        let defaults = ConnectionOptions::default();
+        assert_eq!(defaults.pool_max_idle_per_host, 16);
+        assert_eq!(defaults.pool_idle_timeout, Some(Duration::from_secs(90)));
+        assert_eq!(defaults.tcp_keepalive, Some(Duration::from_secs(60)));
+
+        assert!(HttpTransport::from_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_http_version_applied_to_builder() {
+        let builder_for = |http_version| {
+            let options = ConnectionOptions { http_version, ..ConnectionOptions::default() };
+            format!("{:?}", options.apply(Client::builder()))
+        };
+
+        assert!(builder_for(HttpVersion::Http2).contains("http2_prior_knowledge"));
+        assert!(builder_for(HttpVersion::Http1).contains("http1_only"));
+
+        let auto = builder_for(HttpVersion::Auto);
+        assert!(!auto.contains("http2_prior_knowledge") && !auto.contains("http1_only"));
+    }
+
+    #[test]
+    fn test_payload_format_select() {
+        let formats = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(PayloadFormat::select(&formats(&["json", "OTLP"])), Some(PayloadFormat::Otlp));
+        assert_eq!(PayloadFormat::select(&formats(&["json"])), Some(PayloadFormat::Json));
+        assert_eq!(PayloadFormat::select(&formats(&["protobuf"])), None);
+        assert_eq!(PayloadFormat::select(&[]), None);
+    }
+
     #[test]
     fn test_gateway_health_parsing() {
         // This would be a more comprehensive test with a mock HTTP server
@@ -421,6 +1130,8 @@ mod tests {
             status: "healthy".to_string(),
             service: "telemetry-gateway".to_string(),
             version: "1.0.0".to_string(),
+            protocol: "HTTP/1.1".to_string(),
+            supported_formats: vec!["json".to_string()],
         };
 
         assert_eq!(health.status, "healthy");
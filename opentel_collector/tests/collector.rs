@@ -0,0 +1,2240 @@
+//! End-to-end collector tests: file write → tail → parse → buffer → flush → send
+
+mod common;
+
+use async_trait::async_trait;
+use common::{MockGateway, sample_batch};
+use opentel_collector::checkpoint::Checkpoint;
+use opentel_collector::collector::ReadinessState;
+use opentel_collector::config::{LogFileFormat, SamplingRule, StartPosition};
+use opentel_collector::telemetry::{LogLevel, SpanStatus};
+use opentel_collector::transport::{PayloadSize, Transport};
+use opentel_collector::stats_server;
+use opentel_collector::recent_errors::RecordedError;
+use opentel_collector::self_logs;
+use opentel_collector::state::FileOffsets;
+use opentel_collector::{CollectorError, Config, LogEntry, Result, SidecarCollector, TelemetryBatch};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Transport that records every batch it is asked to send
+#[derive(Debug, Default, Clone)]
+struct RecordingTransport {
+    batches: Arc<Mutex<Vec<TelemetryBatch>>>,
+}
+
+impl RecordingTransport {
+    fn messages(&self) -> Vec<String> {
+        self.batches
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|batch| batch.logs.iter().map(|log| log.message.clone()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        let size = PayloadSize::uncompressed(serde_json::to_vec(&batch).unwrap().len());
+        self.batches.lock().unwrap().push(batch);
+        Ok(size)
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        true
+    }
+}
+
+/// Transport that holds every send until the test releases it
+#[derive(Debug, Clone)]
+struct GatedTransport {
+    gate: Arc<tokio::sync::Semaphore>,
+    started: Arc<AtomicUsize>,
+    logs_sent: Arc<AtomicUsize>,
+}
+
+impl GatedTransport {
+    fn closed() -> Self {
+        Self {
+            gate: Arc::new(tokio::sync::Semaphore::new(0)),
+            started: Arc::new(AtomicUsize::new(0)),
+            logs_sent: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for GatedTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        self.gate.acquire().await.unwrap().forget();
+        self.logs_sent.fetch_add(batch.logs.len(), Ordering::SeqCst);
+        Ok(PayloadSize::uncompressed(0))
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        true
+    }
+}
+
+/// Transport whose sends always fail
+#[derive(Debug, Clone)]
+struct FailingTransport;
+
+#[async_trait]
+impl Transport for FailingTransport {
+    async fn send_batch(&self, _batch: TelemetryBatch) -> Result<PayloadSize> {
+        Err(CollectorError::Transport("gateway returned 503".to_string()))
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        true
+    }
+}
+
+/// Transport whose batches always expire before they are delivered
+#[derive(Debug, Clone)]
+struct ExpiringTransport;
+
+#[async_trait]
+impl Transport for ExpiringTransport {
+    async fn send_batch(&self, batch: TelemetryBatch) -> Result<PayloadSize> {
+        Err(CollectorError::BatchExpired(format!("batch {} is stale", batch.metadata.batch_id)))
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        true
+    }
+}
+
+/// Transport whose sends fail while `failing` is set
+#[derive(Debug, Clone, Default)]
+struct SwitchableTransport {
+    failing: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Transport for SwitchableTransport {
+    async fn send_batch(&self, _batch: TelemetryBatch) -> Result<PayloadSize> {
+        if self.failing.load(Ordering::SeqCst) {
+            Err(CollectorError::Transport("gateway returned 503".to_string()))
+        } else {
+            Ok(PayloadSize::uncompressed(0))
+        }
+    }
+
+    async fn test_connectivity(&self) -> bool {
+        !self.failing.load(Ordering::SeqCst)
+    }
+}
+
+fn test_config(log_path: &Path, gateway_url: String) -> Config {
+    Config {
+        service_name: "test-service".to_string(),
+        pod_name: "test-pod".to_string(),
+        namespace: "test-namespace".to_string(),
+        gateway_url,
+        log_paths: vec![log_path.to_string_lossy().to_string()],
+        flush_interval: Duration::from_millis(100),
+        file_poll_interval: Duration::from_millis(20),
+        retry_backoff_ms: 1,
+        ..Config::default()
+    }
+}
+
+/// Run a collector in the background until the returned sender fires
+fn spawn_collector(collector: SidecarCollector) -> (oneshot::Sender<()>, JoinHandle<Result<()>>) {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
+        collector
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    (shutdown_tx, handle)
+}
+
+/// Poll `condition` until it holds or the timeout elapses
+async fn wait_until<F, Fut>(mut condition: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::time::Instant::now() < deadline {
+        if condition().await {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    false
+}
+
+fn append(path: &Path, content: &str) {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+}
+
+#[tokio::test]
+async fn test_tail_file_to_gateway() {
+    let gateway = MockGateway::start().await;
+    gateway.healthy().await;
+    gateway.accept_batches().await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "[2023-12-01T10:30:45Z] ERROR: Database connection failed\n");
+
+    let collector = SidecarCollector::new(test_config(&log_path, gateway.url())).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    append(
+        &log_path,
+        concat!(
+            r#"{"level": "WARN", "message": "Slow query", "trace_id": "abc123", "span_id": "def456", "operation": "db.query", "duration_ms": 1500}"#,
+            "\n",
+            "INFO: Request completed\n",
+        ),
+    );
+
+    let received = wait_until(|| async {
+        let batches = gateway.received_batches().await;
+        batches.iter().map(|b| b.logs.len()).sum::<usize>() >= 3
+    }).await;
+    assert!(received, "gateway did not receive all log entries");
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let batches = gateway.received_batches().await;
+    let logs: Vec<_> = batches.iter().flat_map(|b| b.logs.iter()).collect();
+    let spans: Vec<_> = batches.iter().flat_map(|b| b.spans.iter()).collect();
+
+    assert_eq!(logs.len(), 3);
+    assert_eq!(logs[0].level, LogLevel::Error);
+    assert_eq!(logs[0].message, "Database connection failed");
+    assert_eq!(logs[1].level, LogLevel::Warn);
+    assert_eq!(logs[1].trace_id.as_deref(), Some("abc123"));
+    assert_eq!(logs[2].message, "Request completed");
+    assert!(logs.iter().all(|log| log.service_name == "test-service" && log.pod_name == "test-pod"));
+
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].operation_name, "db.query");
+    assert_eq!(spans[0].duration_ms, 1500);
+    assert!(batches.iter().all(|b| b.metadata.source_namespace == "test-namespace"));
+}
+
+#[tokio::test]
+async fn test_log_source_per_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let access_path = dir.path().join("access.log");
+    let app_path = dir.path().join("app.log");
+    append(&access_path, "INFO: GET /orders 200\n");
+    append(&app_path, "ERROR: order lookup failed\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        log_paths: vec![
+            access_path.to_string_lossy().to_string(),
+            app_path.to_string_lossy().to_string(),
+        ],
+        log_source_names: [(app_path.to_string_lossy().to_string(), "application".to_string())].into(),
+        ..test_config(&access_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let source_of = |message: &str| {
+        batches
+            .iter()
+            .flat_map(|batch| batch.logs.iter())
+            .find(|log| log.message == message)
+            .map(|log| log.attributes["log.source"].clone())
+            .unwrap()
+    };
+
+    // Unnamed paths fall back to the file name
+    assert_eq!(source_of("GET /orders 200"), "access.log");
+    assert_eq!(source_of("order lookup failed"), "application");
+}
+
+#[tokio::test]
+async fn test_self_telemetry_heartbeat() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        self_telemetry_interval: Duration::from_millis(50),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let heartbeat = batches
+        .iter()
+        .flat_map(|batch| batch.logs.iter())
+        .find(|log| log.message == "collector heartbeat")
+        .expect("a heartbeat was delivered");
+
+    assert_eq!(heartbeat.attributes["telemetry.self"], "true");
+    assert_eq!(heartbeat.attributes["collector.id"], "test-namespace/test-pod");
+    assert!(heartbeat.attributes.contains_key("buffer.logs"));
+    assert!(heartbeat.attributes.contains_key("transport.success_rate"));
+}
+
+#[tokio::test]
+async fn test_heartbeats_do_not_evict_buffered_logs() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO: one\nINFO: two\nINFO: three\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        max_buffer_size: 3,
+        batch_size: 100,
+        flush_interval: Duration::from_secs(60),
+        self_telemetry_interval: Duration::from_millis(20),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, transport.clone()).unwrap());
+    let running = Arc::clone(&collector);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    // Heartbeats keep arriving while the full buffer waits for its flush
+    assert!(wait_until(|| async { transport.messages().len() >= 5 }).await);
+    assert_eq!(collector.stats().await.buffered_logs, 3);
+    assert!(transport.batches.lock().unwrap().iter().all(|batch| batch.logs.len() == 1));
+
+    // Heartbeats are not counted as transport attempts
+    let stats = collector.stats().await;
+    assert_eq!(stats.transport_attempts, 0);
+    assert_eq!(stats.bytes_sent_uncompressed, 0);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let messages = transport.messages();
+    let logs: Vec<_> = messages.iter().filter(|message| *message != "collector heartbeat").collect();
+    assert_eq!(logs, ["one", "two", "three"]);
+}
+
+#[tokio::test]
+async fn test_restarts_counted_across_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    let config = Config {
+        state_path: Some(dir.path().join("state").join("collector.state").to_string_lossy().to_string()),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+
+    // Each run stands in for a restarted sidecar reusing the same volume
+    for expected_restarts in 0..3 {
+        let collector = Arc::new(SidecarCollector::with_transport(config.clone(), RecordingTransport::default()).unwrap());
+        let running = Arc::clone(&collector);
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            running
+                .run_until(async {
+                    let _ = shutdown_rx.await;
+                    Ok(())
+                })
+                .await
+        });
+
+        assert!(wait_until(|| async { collector.stats().await.restarts == expected_restarts }).await);
+        shutdown_tx.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    let collector = SidecarCollector::with_transport(config, RecordingTransport::default()).unwrap();
+    assert_eq!(collector.stats().await.uptime_seconds, 0);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(collector.stats().await.uptime_seconds >= 1);
+}
+
+#[tokio::test]
+async fn test_batch_sequence_continues_across_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO: one\n");
+    let config = Config {
+        state_path: Some(dir.path().join("collector.state").to_string_lossy().to_string()),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let sequences = |transport: &RecordingTransport| -> Vec<u64> {
+        transport.batches.lock().unwrap().iter().map(|batch| batch.metadata.sequence).collect()
+    };
+
+    // A clean shutdown continues right after the last batch
+    for expected in [1, 2] {
+        let transport = RecordingTransport::default();
+        let collector = SidecarCollector::with_transport(config.clone(), transport.clone()).unwrap();
+        let (shutdown_tx, handle) = spawn_collector(collector);
+        assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+        shutdown_tx.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+        assert_eq!(sequences(&transport), vec![expected]);
+    }
+
+    // A killed run skips the rest of its reserved range
+    let killed_config = config.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let shutdown_tx = runtime.block_on(async {
+            let transport = RecordingTransport::default();
+            let collector = SidecarCollector::with_transport(killed_config, transport.clone()).unwrap();
+            let (shutdown_tx, _handle) = spawn_collector(collector);
+            assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+            assert_eq!(sequences(&transport), vec![3]);
+            shutdown_tx
+        });
+        drop(runtime);
+        drop(shutdown_tx);
+    })
+    .join()
+    .unwrap();
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+    assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+    assert_eq!(sequences(&transport), vec![1003]);
+}
+
+#[tokio::test]
+async fn test_restart_resumes_from_saved_offsets() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO: one\nINFO: two\n");
+    let config = Config {
+        offsets_path: Some(dir.path().join("state").join("offsets.json").to_string_lossy().to_string()),
+        buffer_checkpoint_path: Some(dir.path().join("buffer.checkpoint").to_string_lossy().to_string()),
+        buffer_checkpoint_interval: Duration::from_secs(1),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+
+    // Each run stands in for a restarted sidecar reusing the same volume,
+    // reading from the beginning unless an offset was saved
+    let run = |expected: &'static [&'static str]| {
+        let config = config.clone();
+        async move {
+            let transport = RecordingTransport::default();
+            let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+            let (shutdown_tx, handle) = spawn_collector(collector);
+            assert!(wait_until(|| async { transport.messages().len() >= expected.len() }).await);
+            // Give a wrong resume position time to show up as extra lines
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            shutdown_tx.send(()).unwrap();
+            handle.await.unwrap().unwrap();
+            assert_eq!(transport.messages(), expected);
+        }
+    };
+
+    run(&["one", "two"]).await;
+
+    append(&log_path, "INFO: three\n");
+    run(&["three"]).await;
+
+    // Rotated while the collector was down: the rest of the old file, then
+    // the new file from the start
+    append(&log_path, "INFO: four\n");
+    std::fs::rename(&log_path, dir.path().join("application.log.1")).unwrap();
+    append(&log_path, "INFO: fresh\n");
+    run(&["four", "fresh"]).await;
+}
+
+#[tokio::test]
+async fn test_saved_offsets_keep_held_logs() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    let offsets_path = dir.path().join("offsets.json");
+    let checkpoint_path = dir.path().join("buffer.checkpoint");
+    append(&log_path, "");
+
+    let config = Config {
+        offsets_path: Some(offsets_path.to_string_lossy().to_string()),
+        buffer_checkpoint_path: Some(checkpoint_path.to_string_lossy().to_string()),
+        buffer_checkpoint_interval: Duration::from_secs(1),
+        trace_correlation_window: Duration::from_secs(60),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+
+    // The first run is killed while a log waits for its span; the saved
+    // offset is already past it, so only the checkpoint still has it
+    let first_config = config.clone();
+    let first_log_path = log_path.clone();
+    let first_checkpoint_path = checkpoint_path.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let shutdown_tx = runtime.block_on(async {
+            let transport = RecordingTransport::default();
+            let collector = SidecarCollector::with_transport(first_config, transport.clone()).unwrap();
+            let (shutdown_tx, _handle) = spawn_collector(collector);
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            append(
+                &first_log_path,
+                concat!(r#"{"message": "job queued", "trace_id": "trace-1"}"#, "\nINFO: sent\n"),
+            );
+            assert!(wait_until(|| async { transport.messages() == ["sent"] }).await);
+            // Offsets are saved before the checkpoint is collected, so wait
+            // for offsets covering both lines, not just any pair of files
+            let file_len = std::fs::metadata(&first_log_path).unwrap().len();
+            let log_key = first_log_path.to_string_lossy().to_string();
+            assert!(
+                wait_until(|| async {
+                    FileOffsets::load(&offsets_path)
+                        .unwrap()
+                        .files
+                        .get(&log_key)
+                        .is_some_and(|offset| offset.position == file_len)
+                        && Checkpoint::load(&first_checkpoint_path)
+                            .unwrap()
+                            .is_some_and(|checkpoint| checkpoint.logs.len() == 1)
+                })
+                .await
+            );
+            shutdown_tx
+        });
+
+        drop(runtime);
+        drop(shutdown_tx);
+    })
+    .join()
+    .unwrap();
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["job queued"]);
+}
+
+#[tokio::test]
+async fn test_stale_offsets_resend_rather_than_lose() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    let offsets_path = dir.path().join("offsets.json");
+    let checkpoint_path = dir.path().join("buffer.checkpoint");
+    append(&log_path, "INFO: job queued\nINFO: sent\n");
+
+    // A run killed after reading both lines but before saving offsets for
+    // them: the checkpoint holds an entry the saved offsets do not cover
+    let held = LogEntry::new(
+        LogLevel::Info,
+        "job queued".to_string(),
+        "test-service".to_string(),
+        "test-pod".to_string(),
+        "test-namespace".to_string(),
+    );
+    Checkpoint::new(vec![held], Vec::new()).store(&checkpoint_path).unwrap();
+    FileOffsets::default().store(&offsets_path).unwrap();
+
+    let config = Config {
+        offsets_path: Some(offsets_path.to_string_lossy().to_string()),
+        buffer_checkpoint_path: Some(checkpoint_path.to_string_lossy().to_string()),
+        buffer_checkpoint_interval: Duration::from_secs(1),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() >= 3 }).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    // Delivery is at least once: the held entry is sent twice, nothing is lost
+    let mut messages = transport.messages();
+    messages.sort();
+    assert_eq!(messages, vec!["job queued", "job queued", "sent"]);
+}
+
+#[tokio::test]
+async fn test_self_logs_forwarded_when_enabled() {
+    let (layer, receiver) = self_logs::channel();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        forward_self_logs: true,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone())
+        .unwrap()
+        .with_self_logs(receiver);
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    let monitor_message = format!("Starting file monitor for: {}", log_path.display());
+    assert!(wait_until(|| async { transport.messages().contains(&monitor_message) }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let self_logs: Vec<_> = batches.iter().flat_map(|batch| batch.logs.iter()).collect();
+    assert!(self_logs.iter().all(|log| log.attributes["source"] == "collector"));
+    assert!(self_logs.iter().all(|log| log.service_name == "test-service" && log.pod_name == "test-pod"));
+    let monitor_log = self_logs.iter().find(|log| log.message == monitor_message).unwrap();
+    assert_eq!(monitor_log.level, LogLevel::Info);
+}
+
+#[tokio::test]
+async fn test_min_flush_size_holds_until_shutdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        min_flush_size: 10,
+        max_flush_delay: Duration::from_secs(60),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    append(&log_path, "INFO: one\nINFO: two\nINFO: three\n");
+
+    // Several flush intervals pass without a send
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(transport.batches.lock().unwrap().is_empty());
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["one", "two", "three"]);
+    assert_eq!(transport.batches.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_max_flush_delay_overrides_min_flush_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        min_flush_size: 10,
+        max_flush_delay: Duration::from_millis(200),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    append(&log_path, "INFO: one\nINFO: two\n");
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_low_latency_ships_single_entry_within_max_delay() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        flush_interval: Duration::from_secs(30),
+        min_flush_size: 100,
+        low_latency: true,
+        low_latency_max_delay: Duration::from_millis(300),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    // Let the monitor start so the write is picked up by the next poll
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let written = std::time::Instant::now();
+    append(&log_path, "INFO: order placed\n");
+    assert!(wait_until(|| async { transport.messages().len() == 1 }).await);
+
+    // One file poll plus one low latency tick, with slack for a busy machine
+    let elapsed = written.elapsed();
+    assert!(elapsed < Duration::from_millis(1500), "shipped after {:?}", elapsed);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_slow_transport_leaves_unsent_data_buffered() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("app.log");
+    for i in 0..50 {
+        append(&log_path, &format!("INFO line {}\n", i));
+    }
+
+    let transport = GatedTransport::closed();
+    let config = Config {
+        batch_size: 10,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, transport.clone()).unwrap());
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    assert!(wait_until(|| async { transport.started.load(Ordering::SeqCst) == 1 }).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Only the batch being sent has left the buffer
+    assert_eq!(transport.started.load(Ordering::SeqCst), 1);
+    assert_eq!(collector.stats().await.buffered_logs, 40);
+
+    transport.gate.add_permits(100);
+    assert!(wait_until(|| async { transport.logs_sent.load(Ordering::SeqCst) == 50 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_readiness_follows_collector_lifecycle() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO first\n");
+
+    let transport = SwitchableTransport::default();
+    let config = Config {
+        batch_size: 1,
+        max_retries: 0,
+        readiness_max_send_failures: 2,
+        readiness_require_gateway: true,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, transport.clone()).unwrap());
+    let readiness = collector.readiness().await;
+    assert_eq!(readiness.status, ReadinessState::Starting);
+    assert_eq!(readiness.reasons.len(), 2);
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+    assert!(wait_until(|| async { collector.readiness().await.status == ReadinessState::Ready }).await);
+
+    // Failed sends in a row mark the collector degraded, and it is still ready
+    transport.failing.store(true, Ordering::SeqCst);
+    for i in 0..3 {
+        append(&log_path, &format!("INFO failing {}\n", i));
+    }
+    assert!(wait_until(|| async { collector.readiness().await.status == ReadinessState::Degraded }).await);
+    let readiness = collector.readiness().await;
+    assert!(readiness.is_ready());
+    assert!(readiness.reasons[0].contains("batch sends failed"));
+
+    // One successful send is enough to recover
+    transport.failing.store(false, Ordering::SeqCst);
+    append(&log_path, "INFO recovered\n");
+    assert!(wait_until(|| async { collector.readiness().await.status == ReadinessState::Ready }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+    let readiness = collector.readiness().await;
+    assert_eq!(readiness.status, ReadinessState::ShuttingDown);
+    assert!(!readiness.is_ready());
+}
+
+#[tokio::test]
+async fn test_ready_while_waiting_for_file_and_gateway() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+
+    // The application has not created its log yet and the gateway is down
+    let transport = SwitchableTransport::default();
+    transport.failing.store(true, Ordering::SeqCst);
+    let collector = Arc::new(SidecarCollector::with_transport(test_config(&log_path, "http://unused".to_string()), transport).unwrap());
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+    assert!(wait_until(|| async { collector.readiness().await.status == ReadinessState::Ready }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_backpressure_pauses_reads_until_buffer_drains() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO first\nINFO second\n");
+
+    let transport = GatedTransport::closed();
+    let config = Config {
+        batch_size: 2,
+        max_buffer_size: 10,
+        backpressure_high_water_percent: 50.0,
+        backpressure_low_water_percent: 20.0,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, transport.clone()).unwrap());
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    // The gateway is stuck on the first batch, so the buffer fills past the high-water mark
+    assert!(wait_until(|| async { transport.started.load(Ordering::SeqCst) == 1 }).await);
+    for i in 0..6 {
+        append(&log_path, &format!("INFO backlog {}\n", i));
+    }
+    assert!(wait_until(|| async { collector.stats().await.reading_throttled }).await);
+
+    // Lines written meanwhile stay in the file
+    for i in 0..3 {
+        append(&log_path, &format!("INFO waiting {}\n", i));
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let stats = collector.stats().await;
+    assert_eq!((stats.buffered_logs, stats.read_throttles), (6, 1));
+
+    // Once the buffer drains below the low-water mark, reading resumes
+    transport.gate.add_permits(100);
+    assert!(wait_until(|| async { transport.logs_sent.load(Ordering::SeqCst) == 11 }).await);
+    assert!(!collector.stats().await.reading_throttled);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_truncation_and_rotation() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "ERROR: first\nINFO: second\n");
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(
+        test_config(&log_path, "http://unused".to_string()),
+        transport.clone(),
+    ).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    // copytruncate-style: same file, truncated and rewritten with less data
+    std::fs::write(&log_path, "WARN: after truncate\n").unwrap();
+    assert!(wait_until(|| async { transport.messages().len() == 3 }).await);
+
+    // rename-style rotation: the old file moves away and a new one is created
+    std::fs::rename(&log_path, dir.path().join("application.log.1")).unwrap();
+    std::fs::write(&log_path, "INFO: rotated\n").unwrap();
+    assert!(wait_until(|| async { transport.messages().len() == 4 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(
+        transport.messages(),
+        vec!["first", "second", "after truncate", "rotated"]
+    );
+}
+
+#[tokio::test]
+async fn test_rename_rotation_drains_old_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO: first\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        // Slow enough that the writes below land between two checks
+        file_poll_interval: Duration::from_millis(300),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() == 1 }).await);
+
+    // Written just before logrotate renames the file and creates a new one
+    append(&log_path, "WARN: just before rotation\nINFO: last old line\n");
+    std::fs::rename(&log_path, dir.path().join("application.log.1")).unwrap();
+    append(&log_path, "INFO: just after rotation\n");
+
+    assert!(wait_until(|| async { transport.messages().len() == 4 }).await);
+
+    // A gap between rename and recreate still reads the old file to the end
+    append(&log_path, "INFO: before gap\n");
+    std::fs::rename(&log_path, dir.path().join("application.log.2")).unwrap();
+    assert!(wait_until(|| async { transport.messages().len() == 5 }).await);
+    append(&log_path, "INFO: recreated\n");
+    assert!(wait_until(|| async { transport.messages().len() == 6 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(
+        transport.messages(),
+        vec!["first", "just before rotation", "last old line", "just after rotation", "before gap", "recreated"]
+    );
+}
+
+#[tokio::test]
+async fn test_bom_and_nul_padding() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "\u{feff}ERROR: first\rINFO: second\r\n");
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(
+        test_config(&log_path, "http://unused".to_string()),
+        transport.clone(),
+    ).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    // Truncation that leaves the writer's old offset as a NUL-filled hole
+    std::fs::write(&log_path, "\0\0\0\0\0\0\0\0WARN: padded\n\0\0\0\0\n").unwrap();
+    assert!(wait_until(|| async { transport.messages().len() == 3 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["first", "second", "padded"]);
+}
+
+#[tokio::test]
+async fn test_invalid_utf8_line_decoded_lossily() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    std::fs::write(&log_path, b"ERROR: upload of caf\xe9.png failed\nINFO: next line\n").unwrap();
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(
+        test_config(&log_path, "http://unused".to_string()),
+        transport.clone(),
+    ).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    let logs: Vec<_> = transport.batches.lock().unwrap().iter().flat_map(|batch| batch.logs.clone()).collect();
+    assert_eq!(logs[0].message, "upload of caf\u{fffd}.png failed");
+    assert_eq!(logs[0].level, LogLevel::Error);
+    assert_eq!(logs[0].attributes["encoding.lossy"], "true");
+    assert!(!logs[1].attributes.contains_key("encoding.lossy"));
+}
+
+#[tokio::test]
+async fn test_missing_file_polled_without_backoff() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        file_error_threshold: 1,
+        file_error_backoff: Duration::from_secs(60),
+        file_error_max_backoff: Duration::from_secs(60),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    // Many checks of the missing file, each of which would back off for a
+    // minute if a missing file counted as a failure
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert!(transport.messages().is_empty());
+
+    append(&log_path, "INFO: appeared\n");
+    assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+
+    append(&log_path, "INFO: still tailing\n");
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["appeared", "still tailing"]);
+}
+
+#[tokio::test]
+async fn test_start_from_end_skips_existing_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    append(&log_path, "INFO: old one\nINFO: old two\nINFO: old three\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        start_position: StartPosition::End,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    append(&log_path, "INFO: new\n");
+    assert!(wait_until(|| async { !transport.messages().is_empty() }).await);
+
+    // Rotation still starts the replacement file from the beginning
+    std::fs::rename(&log_path, dir.path().join("application.log.1")).unwrap();
+    append(&log_path, "INFO: rotated one\nINFO: rotated two\n");
+    assert!(wait_until(|| async { transport.messages().len() >= 3 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["new", "rotated one", "rotated two"]);
+}
+
+#[tokio::test]
+async fn test_replay_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    append(
+        &archive_path,
+        concat!(
+            "ERROR: one\n",
+            "\n",
+            "INFO: two\n",
+            r#"{"level": "INFO", "message": "three", "span_id": "def456", "operation": "db.query"}"#,
+            "\n",
+            "WARN: four\n",
+            "INFO: five\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        batch_size: 2,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    assert_eq!(report.lines_read, 6);
+    assert_eq!(report.logs_parsed, 5);
+    assert_eq!(report.spans_parsed, 1);
+    assert_eq!(report.batches_failed, 0);
+    assert_eq!(report.batches_sent, transport.batches.lock().unwrap().len());
+    assert_eq!(transport.messages(), vec!["one", "two", "three", "four", "five"]);
+}
+
+#[tokio::test]
+async fn test_priority_buffer_sends_errors_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    append(&archive_path, "INFO: one\nWARN: auth failure for bob\nERROR: two\nINFO: three\n");
+
+    for (enable_priority_buffer, expected) in [
+        (false, vec!["one", "auth failure for bob", "two", "three"]),
+        (true, vec!["auth failure for bob", "two", "one", "three"]),
+    ] {
+        let transport = RecordingTransport::default();
+        let config = Config {
+            batch_size: 10,
+            enable_priority_buffer,
+            high_priority_patterns: vec!["auth failure".to_string()],
+            ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+        };
+        let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+        collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+        assert_eq!(transport.messages(), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_priority_buffer_span_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("spans.log");
+    for (span_id, status, duration_ms) in [("slow", "OK", 20000), ("timeout", "TIMEOUT", 5), ("failed", "ERROR", 5), ("fast", "OK", 5), ("tagged", "OK", 5)] {
+        append(
+            &archive_path,
+            &format!(
+                r#"{{"trace_id": "t1", "span_id": "{}", "operation": "batch.run", "status": "{}", "duration_ms": {}, "tags": {{"schedule": "{}"}}}}"#,
+                span_id, status, duration_ms, if span_id == "tagged" { "nightly" } else { "hourly" }
+            ),
+        );
+        append(&archive_path, "\n");
+    }
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        batch_size: 10,
+        enable_priority_buffer: true,
+        high_priority_span_statuses: vec![SpanStatus::Error],
+        high_priority_span_duration_ms: 60000,
+        high_priority_span_tag_patterns: vec!["^nightly$".to_string()],
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    // Only the error and tagged spans are promoted, ahead of the others
+    let spans: Vec<_> = transport.batches.lock().unwrap()
+        .iter()
+        .flat_map(|batch| batch.spans.iter().map(|span| span.span_id.clone()))
+        .collect();
+    assert_eq!(spans, vec!["failed", "tagged", "slow", "timeout", "fast"]);
+}
+
+#[tokio::test]
+async fn test_priority_buffer_span_duration_override() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("spans.log");
+    for (span_id, operation, status, duration_ms) in [
+        ("export", "nightly.export", "OK", 300_000),
+        ("request", "GET /orders", "OK", 300_000),
+        ("failed-export", "nightly.export", "ERROR", 1_000),
+    ] {
+        append(
+            &archive_path,
+            &format!(
+                r#"{{"trace_id": "t1", "span_id": "{}", "operation": "{}", "status": "{}", "duration_ms": {}}}"#,
+                span_id, operation, status, duration_ms
+            ),
+        );
+        append(&archive_path, "\n");
+    }
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        batch_size: 10,
+        enable_priority_buffer: true,
+        high_priority_span_duration_overrides_ms: [("nightly.export".to_string(), 600_000)].into(),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    // Five minutes is normal for the export job but not for a request
+    let spans: Vec<_> = transport.batches.lock().unwrap()
+        .iter()
+        .flat_map(|batch| batch.spans.iter().map(|span| span.span_id.clone()))
+        .collect();
+    assert_eq!(spans, vec!["request", "failed-export", "export"]);
+}
+
+#[tokio::test]
+async fn test_expired_batches_written_to_dead_letter() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    let dead_letter_path = dir.path().join("failed.ndjson");
+    append(&archive_path, "ERROR: one\nINFO: two\nWARN: three\n");
+
+    let config = Config {
+        batch_size: 2,
+        dead_letter_path: Some(dead_letter_path.to_string_lossy().to_string()),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config.clone(), ExpiringTransport).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+    assert_eq!((report.batches_expired, report.batches_failed), (2, 0));
+    assert_eq!(collector.stats().await.batches_dead_lettered, 2);
+
+    // The dead letter holds every expired entry, ready to be resent
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let report = collector
+        .replay_dead_letter(&dead_letter_path.to_string_lossy(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(report.sent(), 2);
+    assert!(report.file_deleted);
+    assert_eq!(transport.messages(), vec!["one", "two", "three"]);
+}
+
+#[tokio::test]
+async fn test_failed_batches_written_to_dead_letter() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    let dead_letter_path = dir.path().join("failed.ndjson");
+    append(&archive_path, "ERROR: one\nINFO: two\nWARN: three\n");
+
+    let config = Config {
+        batch_size: 2,
+        max_retries: 0,
+        dead_letter_path: Some(dead_letter_path.to_string_lossy().to_string()),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, FailingTransport).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+    assert_eq!(report.batches_failed, 2);
+
+    let messages: Vec<String> = std::fs::read_to_string(&dead_letter_path)
+        .unwrap()
+        .lines()
+        .flat_map(|line| serde_json::from_str::<TelemetryBatch>(line).unwrap().logs)
+        .map(|log| log.message)
+        .collect();
+    assert_eq!(messages, vec!["one", "two", "three"]);
+}
+
+#[tokio::test]
+async fn test_replay_dead_letter_keeps_failed_batches() {
+    let gateway = MockGateway::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("dead-letter-2"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("schema mismatch"))
+        .with_priority(1)
+        .mount(&gateway.server)
+        .await;
+    gateway.accept_batches().await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let dead_letter_path = dir.path().join("failed.ndjson");
+    let mut lines = Vec::new();
+    for i in 1..=3 {
+        let mut batch = sample_batch(2);
+        batch.metadata.batch_id = format!("dead-letter-{}", i);
+        lines.push(serde_json::to_string(&batch).unwrap());
+    }
+    lines.insert(2, "{not a batch".to_string());
+    append(&dead_letter_path, &(lines.join("\n") + "\n"));
+
+    let config = test_config(&dir.path().join("unused.log"), gateway.url());
+    let collector = SidecarCollector::new(config).unwrap();
+    let path = dead_letter_path.to_string_lossy();
+
+    // Without --delete-on-success the file is only read
+    let report = collector.replay_dead_letter(&path, false).await.unwrap();
+    assert_eq!((report.sent(), report.failed()), (2, 2));
+    assert_eq!(std::fs::read_to_string(&dead_letter_path).unwrap().lines().count(), 4);
+
+    let report = collector.replay_dead_letter(&path, true).await.unwrap();
+    let outcomes: Vec<_> = report.batches.iter().map(|b| (b.line, b.batch_id.as_deref(), b.is_success())).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            (1, Some("dead-letter-1"), true),
+            (2, Some("dead-letter-2"), false),
+            (3, None, false),
+            (4, Some("dead-letter-3"), true),
+        ]
+    );
+    assert!(report.batches[1].error.as_deref().unwrap().contains("schema mismatch"));
+    assert!(!report.file_deleted);
+
+    // Only the rejected and malformed batches remain
+    let remaining = std::fs::read_to_string(&dead_letter_path).unwrap();
+    assert_eq!(remaining.lines().collect::<Vec<_>>(), vec![lines[1].as_str(), "{not a batch"]);
+
+    // Once the gateway takes the rest and the bad line is fixed, the file is deleted
+    gateway.server.reset().await;
+    gateway.accept_batches().await;
+    std::fs::write(&dead_letter_path, format!("{}\n", lines[1])).unwrap();
+    let report = collector.replay_dead_letter(&path, true).await.unwrap();
+    assert_eq!((report.sent(), report.failed()), (1, 0));
+    assert!(report.file_deleted);
+    assert!(!dead_letter_path.exists());
+}
+
+#[tokio::test]
+async fn test_replay_dead_letter_keeps_unconfirmed_batches() {
+    let gateway = MockGateway::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("receipt-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"accepted_batch_ids": ["receipt-1"]}"#))
+        .with_priority(1)
+        .mount(&gateway.server)
+        .await;
+    // Accepted without a receipt naming the batch
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"accepted_batch_ids": []}"#))
+        .mount(&gateway.server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let dead_letter_path = dir.path().join("failed.ndjson");
+    let mut lines = Vec::new();
+    for i in 1..=2 {
+        let mut batch = sample_batch(2);
+        batch.metadata.batch_id = format!("receipt-{}", i);
+        lines.push(serde_json::to_string(&batch).unwrap());
+    }
+    append(&dead_letter_path, &(lines.join("\n") + "\n"));
+
+    let config = Config {
+        delivery_receipts: true,
+        max_retries: 0,
+        ..test_config(&dir.path().join("unused.log"), gateway.url())
+    };
+    let collector = SidecarCollector::new(config).unwrap();
+    let report = collector.replay_dead_letter(&dead_letter_path.to_string_lossy(), true).await.unwrap();
+
+    assert_eq!((report.sent(), report.failed()), (1, 1));
+    assert!(report.batches[1].error.as_deref().unwrap().contains("not confirmed"));
+    // Only the confirmed batch is purged
+    let remaining = std::fs::read_to_string(&dead_letter_path).unwrap();
+    assert_eq!(remaining.lines().collect::<Vec<_>>(), vec![lines[1].as_str()]);
+}
+
+#[tokio::test]
+async fn test_unconfirmed_batch_retried_then_failed() {
+    let gateway = MockGateway::start().await;
+    // Accepted without a receipt naming the batch
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"accepted_batch_ids": []}"#))
+        .mount(&gateway.server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    let dead_letter_path = dir.path().join("failed.ndjson");
+    append(&log_path, "INFO: unconfirmed\n");
+
+    let config = Config {
+        delivery_receipts: true,
+        max_retries: 1,
+        dead_letter_path: Some(dead_letter_path.to_string_lossy().to_string()),
+        ..test_config(&log_path, gateway.url())
+    };
+    let collector = Arc::new(SidecarCollector::new(config).unwrap());
+    let running = Arc::clone(&collector);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    assert!(wait_until(|| async { collector.stats().await.batches_dead_lettered == 1 }).await);
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    // The first attempt and one retry, then the batch counts as failed
+    let posts = gateway.server.received_requests().await.unwrap()
+        .iter()
+        .filter(|request| request.method.as_str() == "POST")
+        .count();
+    assert_eq!(posts, 2);
+    let stats = collector.stats().await;
+    assert!(stats.errors_by_category.get("unconfirmed").is_some_and(|count| *count >= 1));
+    assert!(stats.transport_success_rate < 100.0);
+    let dead_letter = std::fs::read_to_string(&dead_letter_path).unwrap();
+    assert!(dead_letter.contains("unconfirmed"));
+}
+
+#[tokio::test]
+async fn test_batch_sequence_numbers() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    append(&archive_path, "INFO: one\nINFO: two\nINFO: three\nINFO: four\nINFO: five\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        batch_size: 2,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    // Numbering carries on across flushes and across replays
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let sequences: Vec<u64> = transport.batches.lock().unwrap()
+        .iter()
+        .map(|batch| batch.metadata.sequence)
+        .collect();
+    assert!(sequences.len() >= 4);
+    assert_eq!(sequences, (1..=sequences.len() as u64).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_replay_cri_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("0.log");
+    append(
+        &archive_path,
+        concat!(
+            "2023-12-01T10:30:45.100Z stdout F INFO: started\n",
+            "2023-12-01T10:30:45.200Z stdout P {\"level\": \"WARN\", \"message\": \"slow \n",
+            "2023-12-01T10:30:45.250Z stderr F ERROR: boom\n",
+            "2023-12-01T10:30:45.300Z stdout F query\"}\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        log_file_format: LogFileFormat::Cri,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+    assert_eq!(report.lines_read, 4);
+    assert_eq!(report.logs_parsed, 3);
+
+    let batches = transport.batches.lock().unwrap();
+    let logs: Vec<_> = batches.iter().flat_map(|batch| batch.logs.iter()).collect();
+    let by_message = |message: &str| logs.iter().find(|log| log.message == message).unwrap();
+
+    let reassembled = by_message("slow query");
+    assert_eq!(reassembled.level, LogLevel::Warn);
+    assert_eq!(reassembled.attributes["stream"], "stdout");
+    assert_eq!(reassembled.timestamp, 1701426645);
+    assert_eq!(reassembled.attributes["timestamp.source"], "runtime");
+
+    assert_eq!(by_message("boom").attributes["stream"], "stderr");
+    assert_eq!(by_message("started").level, LogLevel::Info);
+}
+
+#[tokio::test]
+async fn test_configured_collector_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    append(&archive_path, "INFO: one\nINFO: two\nINFO: three\n");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        collector_id: Some("orders-sidecar-1".to_string()),
+        batch_size: 2,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    assert_eq!(collector.stats().await.collector_id, "orders-sidecar-1");
+    let batches = transport.batches.lock().unwrap();
+    assert!(!batches.is_empty());
+    assert!(batches.iter().all(|batch| batch.metadata.collector_id == "orders-sidecar-1"));
+}
+
+#[tokio::test]
+async fn test_host_attributes_opt_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    append(&archive_path, "INFO: one\n");
+
+    let labels_path = dir.path().join("labels");
+    append(&labels_path, "team=\"sre\"\nprocess.runtime.name=\"custom\"\n");
+
+    for include_host_attributes in [false, true] {
+        let transport = RecordingTransport::default();
+        let config = Config {
+            include_host_attributes,
+            pod_info_paths: vec![labels_path.to_string_lossy().to_string()],
+            ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+        };
+        let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+        collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+        let batches = transport.batches.lock().unwrap();
+        let attributes = &batches[0].metadata.resource_attributes;
+        assert_eq!(attributes["team"], "sre");
+        assert_eq!(attributes.contains_key("process.pid"), include_host_attributes);
+        if include_host_attributes {
+            assert_eq!(attributes["process.pid"], std::process::id().to_string());
+            assert!(attributes.contains_key("process.creation.time"));
+            // Downward API files win over host attributes
+            assert_eq!(attributes["process.runtime.name"], "custom");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_preserve_raw_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("archive.log");
+    let json_line = r#"{"level": "ERROR", "message": "payment failed", "order_id": "o-1"}"#;
+    append(&archive_path, &format!("{}\nINFO: {}\n", json_line, "x".repeat(100)));
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        preserve_raw: true,
+        max_raw_bytes: 80,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let logs: Vec<_> = batches.iter().flat_map(|batch| batch.logs.iter()).collect();
+    assert_eq!(logs[0].message, "payment failed");
+    assert_eq!(logs[0].attributes["log.raw"], json_line);
+    assert_eq!(logs[1].attributes["log.raw"], format!("INFO: {}", "x".repeat(74)));
+}
+
+#[tokio::test]
+async fn test_sampling_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    let access_log = dir.path().join("access.log");
+    let mut content = String::new();
+    for i in 0..1000 {
+        content.push_str(&format!(
+            r#"{{"level": "INFO", "message": "probe {}", "attributes": {{"path": "/healthz"}}}}"#,
+            i
+        ));
+        content.push('\n');
+    }
+    content.push_str(r#"{"level": "INFO", "message": "order placed", "attributes": {"path": "/api/orders"}}"#);
+    content.push('\n');
+    append(&access_log, &content);
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        sampling_rules: vec![SamplingRule {
+            attribute: "path".to_string(),
+            pattern: "^/healthz$".to_string(),
+            sample_ratio: 0.1,
+        }],
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&access_log.to_string_lossy()).await.unwrap();
+
+    let messages = transport.messages();
+    let probes = messages.iter().filter(|m| m.starts_with("probe")).count();
+    assert!((50..=150).contains(&probes), "kept {} of 1000 probes", probes);
+    assert!(messages.contains(&"order placed".to_string()));
+    assert_eq!(report.logs_parsed + report.logs_sampled_out, 1001);
+}
+
+#[tokio::test]
+async fn test_level_rate_limits_isolate_noisy_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    let mut content = String::new();
+    for i in 0..500 {
+        content.push_str(&format!(r#"{{"level": "DEBUG", "message": "poll {}"}}"#, i));
+        content.push('\n');
+        if i % 50 == 0 {
+            content.push_str(&format!(r#"{{"level": "INFO", "message": "order {}"}}"#, i));
+            content.push('\n');
+            content.push_str(&format!(r#"{{"level": "ERROR", "message": "payment {}"}}"#, i));
+            content.push('\n');
+        }
+    }
+    append(&archive_path, &content);
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        level_rate_limits: [("debug".to_string(), 20.0), ("info".to_string(), 100.0)].into(),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    // The DEBUG flood is cut to its burst without touching other levels
+    let messages = transport.messages();
+    let polls = messages.iter().filter(|m| m.starts_with("poll")).count();
+    assert!((20..=25).contains(&polls), "kept {} of 500 DEBUG logs", polls);
+    assert_eq!(messages.iter().filter(|m| m.starts_with("order")).count(), 10);
+    assert_eq!(messages.iter().filter(|m| m.starts_with("payment")).count(), 10);
+    assert_eq!(report.logs_rate_limited, 500 - polls);
+    assert_eq!(collector.stats().await.logs_rate_limited, (500 - polls) as u64);
+}
+
+#[tokio::test]
+async fn test_line_filters_drop_before_buffering() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            "DEBUG [netty] channel registered\n",
+            r#"{"level": "INFO", "message": "order placed", "span_id": "00f067aa0ba902b7", "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "operation": "noise.poll", "duration_ms": 1}"#,
+            "\n",
+            "ERROR payment declined\n",
+            "DEBUG [netty] channel closed\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        line_filters: vec!["drop:^DEBUG \\[netty\\]".parse().unwrap(), "drop:noise\\.poll".parse().unwrap()],
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    // Filtered lines produce neither logs nor spans
+    assert_eq!(transport.messages(), vec!["ERROR payment declined".to_string()]);
+    assert!(transport.batches.lock().unwrap().iter().all(|batch| batch.spans.is_empty()));
+    assert_eq!((report.lines_read, report.lines_filtered, report.logs_parsed), (4, 3, 1));
+    assert_eq!(collector.stats().await.lines_filtered, 3);
+}
+
+#[tokio::test]
+async fn test_span_tag_allowlist_promotes_only_allowed_attributes() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"level": "INFO", "message": "order placed", "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "#,
+            r#""operation": "orders.create", "duration_ms": 12, "user_id": "42", "request_id": "r-1", "tags": {"http.route": "/v1/orders"}, "#,
+            r#""attributes": {"http.route": "/orders", "db.statement": "insert into orders"}}"#,
+            "\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        span_tag_allowlist: vec!["user_id".to_string(), "http.route".to_string(), "tenant".to_string()],
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let log = batches.iter().flat_map(|batch| batch.logs.iter()).next().unwrap();
+    let span = batches.iter().flat_map(|batch| batch.spans.iter()).next().unwrap();
+
+    assert_eq!(span.tags.get("user_id").map(String::as_str), Some("42"));
+    // The span's own tag is kept
+    assert_eq!(span.tags.get("http.route").map(String::as_str), Some("/v1/orders"));
+    assert!(!span.tags.contains_key("request_id"));
+    assert!(!span.tags.contains_key("db.statement"));
+    assert!(!span.tags.contains_key("tenant"));
+
+    // The log keeps every attribute
+    assert_eq!(log.attributes.get("request_id").map(String::as_str), Some("r-1"));
+    assert_eq!(log.attributes.get("db.statement").map(String::as_str), Some("insert into orders"));
+}
+
+#[tokio::test]
+async fn test_orphan_span_with_missing_same_service_parent_flagged() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            // Logged before its parent, which arrives on the next line
+            r#"{"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b8", "parent_span_id": "00f067aa0ba902b7", "operation": "db.query", "duration_ms": 5}"#, "\n",
+            // Its parent is in the same service and never logged
+            r#"{"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b9", "parent_span_id": "00f067aa0ba902ff", "operation": "cache.get", "duration_ms": 1}"#, "\n",
+            // Its parent is in the calling service
+            r#"{"trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "parent_span_id": "a3ce929d0e0e4736", "operation": "orders.create", "duration_ms": 12, "tags": {"span.kind": "server"}}"#, "\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        orphan_span_window: Duration::from_secs(30),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let orphans: HashMap<_, _> = transport
+        .batches
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|batch| batch.spans.iter())
+        .map(|span| (span.operation_name.clone(), span.tags.contains_key("span.orphan")))
+        .collect();
+    assert_eq!(
+        orphans,
+        HashMap::from([
+            ("db.query".to_string(), false),
+            ("cache.get".to_string(), true),
+            ("orders.create".to_string(), false),
+        ])
+    );
+    assert_eq!(collector.stats().await.orphan_spans, 1);
+}
+
+#[tokio::test]
+async fn test_concatenated_json_split_into_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"level": "ERROR", "message": "payment declined"}{"level": "INFO", "message": "retry scheduled"}"#, "\n",
+            r#"{"level": "WARN", "message": "slow response"}"#, "\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        split_concatenated_json: true,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    assert_eq!(transport.messages(), vec!["payment declined", "retry scheduled", "slow response"]);
+    assert_eq!((report.lines_read, report.logs_parsed), (2, 3));
+    let levels: Vec<LogLevel> = transport
+        .batches
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|batch| batch.logs.iter().map(|log| log.level.clone()))
+        .collect();
+    assert_eq!(levels, vec![LogLevel::Error, LogLevel::Info, LogLevel::Warn]);
+}
+
+#[tokio::test]
+async fn test_recent_logs_queried_from_stats_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"level": "ERROR", "message": "upstream timeout calling payments"}"#, "\n",
+            r#"{"level": "INFO", "message": "retrying after timeout"}"#, "\n",
+            r#"{"level": "ERROR", "message": "payment declined"}"#, "\n",
+            r#"{"level": "FATAL", "message": "connect timeout, giving up"}"#, "\n",
+        ),
+    );
+
+    let config = Config {
+        recent_logs_capacity: 3,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, RecordingTransport::default()).unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(stats_server::serve(listener, Arc::new(collector)));
+
+    let client = reqwest::Client::new();
+    let recent: Vec<LogEntry> = client
+        .get(format!("{}/recent?level=error&contains=timeout&limit=50", base_url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // The oldest entry was evicted from the ring of three
+    let messages: Vec<_> = recent.iter().map(|entry| entry.message.as_str()).collect();
+    assert_eq!(messages, vec!["connect timeout, giving up"]);
+
+    let latest: Vec<LogEntry> = client
+        .get(format!("{}/recent?limit=2", base_url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(latest.len(), 2);
+    assert_eq!(latest[0].message, "connect timeout, giving up");
+    assert_eq!(latest[1].level, LogLevel::Error);
+
+    let stats: serde_json::Value = client.get(format!("{}/stats", base_url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(stats["service_name"], "test-service");
+}
+
+#[tokio::test]
+async fn test_recent_logs_disabled_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = test_config(&dir.path().join("unused.log"), "http://unused".to_string());
+    let collector = SidecarCollector::with_transport(config, RecordingTransport::default()).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(stats_server::serve(listener, Arc::new(collector)));
+
+    let response = reqwest::get(format!("{}/recent", base_url)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_from_stats_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("app.log");
+    append(&log_path, "");
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        stats_control_token: Some("let-me-pause".to_string()),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, transport.clone()).unwrap());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(stats_server::serve(listener, Arc::clone(&collector)));
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    let client = reqwest::Client::new();
+    let unauthorized = client.post(format!("{}/pause", base_url)).bearer_auth("guess").send().await.unwrap();
+    assert_eq!(unauthorized.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(!collector.stats().await.paused);
+
+    let paused: serde_json::Value = client
+        .post(format!("{}/pause", base_url))
+        .bearer_auth("let-me-pause")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(paused["paused"], true);
+
+    append(&log_path, "INFO: one\nINFO: two\n");
+    assert!(wait_until(|| async { collector.stats().await.buffered_logs == 2 }).await);
+
+    // Several flush intervals pass without a send
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(transport.messages().is_empty());
+    let stats: serde_json::Value = client.get(format!("{}/stats", base_url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(stats["paused"], true);
+    assert_eq!(stats["buffered_logs"], 2);
+
+    client.post(format!("{}/resume", base_url)).bearer_auth("let-me-pause").send().await.unwrap();
+    assert!(wait_until(|| async { transport.messages().len() == 2 }).await);
+    assert!(!collector.stats().await.paused);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_pause_disabled_without_control_token() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // An empty token, e.g. from a config file, disables the endpoints too
+    for token in [None, Some(""), Some("  ")] {
+        let config = Config {
+            stats_control_token: token.map(str::to_string),
+            ..test_config(&dir.path().join("application.log"), "http://unused".to_string())
+        };
+        let collector = Arc::new(SidecarCollector::with_transport(config, RecordingTransport::default()).unwrap());
+        assert_eq!(collector.stats_control_token(), None);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(stats_server::serve(listener, Arc::clone(&collector)));
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/pause", base_url))
+            .bearer_auth(token.unwrap_or("anything"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND, "{:?}", token);
+        assert!(!collector.is_paused());
+    }
+}
+
+#[tokio::test]
+async fn test_validate_before_send() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("spans.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"message": "good", "span_id": "a1", "trace_id": "t1", "operation": "ok", "start_time": 100, "end_time": 101}"#,
+            "\n",
+            r#"{"message": "bad", "span_id": "b2", "trace_id": "", "operation": "untraced", "start_time": 200, "end_time": 201}"#,
+            "\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        validate_before_send: true,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    assert_eq!(report.spans_parsed, 2);
+    assert_eq!(report.batches_failed, 0);
+    let spans: Vec<String> = transport.batches.lock().unwrap()
+        .iter()
+        .flat_map(|batch| batch.spans.iter().map(|span| span.span_id.clone()))
+        .collect();
+    assert_eq!(spans, vec!["a1"]);
+    assert_eq!(transport.messages(), vec!["good", "bad"]);
+    assert_eq!(collector.stats().await.invalid_entries_dropped, 1);
+}
+
+#[tokio::test]
+async fn test_span_red_metrics() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("spans.log");
+    for (span_id, status, duration_ms) in [("a1", "OK", 100), ("a2", "OK", 300), ("a3", "ERROR", 800), ("a4", "OK", 400)] {
+        append(
+            &archive_path,
+            &format!(
+                r#"{{"trace_id": "t1", "span_id": "{}", "operation": "GET /orders", "status": "{}", "duration_ms": {}}}"#,
+                span_id, status, duration_ms
+            ),
+        );
+        append(&archive_path, "\n");
+    }
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        span_metrics_interval: Duration::from_secs(60),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    let report = collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+    assert_eq!(report.spans_parsed, 4);
+
+    let stats = collector.stats().await;
+    assert_eq!(stats.span_metrics.len(), 1);
+    let orders = &stats.span_metrics[0];
+    assert_eq!((orders.requests, orders.errors), (4, 1));
+    assert_eq!(orders.error_rate, 25.0);
+    assert_eq!(orders.avg_duration_ms, 400);
+
+    let metrics: Vec<_> = transport.batches.lock().unwrap()
+        .iter()
+        .flat_map(|batch| batch.metrics.clone())
+        .collect();
+    let error_rate = metrics.iter().find(|point| point.name == "span.error_rate").unwrap();
+    assert_eq!(error_rate.value, 25.0);
+    assert_eq!(error_rate.attributes["operation"], "GET /orders");
+}
+
+#[tokio::test]
+async fn test_trace_correlation_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("async.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"message": "job queued", "trace_id": "trace-1"}"#,
+            "\n",
+            r#"{"message": "unrelated", "trace_id": "trace-2"}"#,
+            "\n",
+            r#"{"trace_id": "trace-1", "span_id": "span-1", "operation": "job.run", "duration_ms": 5}"#,
+            "\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        trace_correlation_window: Duration::from_secs(5),
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    let batches = transport.batches.lock().unwrap();
+    let logs: Vec<_> = batches.iter().flat_map(|batch| batch.logs.iter()).collect();
+    let queued = logs.iter().find(|log| log.message == "job queued").unwrap();
+    let unrelated = logs.iter().find(|log| log.message == "unrelated").unwrap();
+
+    assert_eq!(queued.span_id.as_deref(), Some("span-1"));
+    assert_eq!(unrelated.span_id, None);
+}
+
+#[tokio::test]
+async fn test_buffer_checkpoint_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+    let checkpoint_path = dir.path().join("buffer.checkpoint");
+    append(&log_path, "INFO: before start\n");
+
+    let config = Config {
+        start_position: StartPosition::End,
+        buffer_checkpoint_path: Some(checkpoint_path.to_string_lossy().to_string()),
+        buffer_checkpoint_interval: Duration::from_secs(1),
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+
+    // First run never flushes; dropping its runtime stands in for the pod
+    // being killed with the lines still buffered
+    let first_config = Config {
+        flush_interval: Duration::from_secs(60),
+        ..config.clone()
+    };
+    let first_log_path = log_path.clone();
+    let first_checkpoint_path = checkpoint_path.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let shutdown_tx = runtime.block_on(async {
+            let collector = SidecarCollector::with_transport(first_config, RecordingTransport::default()).unwrap();
+            let (shutdown_tx, _handle) = spawn_collector(collector);
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            append(&first_log_path, "INFO: one\nINFO: two\nINFO: three\n");
+            assert!(
+                wait_until(|| async {
+                    Checkpoint::load(&first_checkpoint_path)
+                        .unwrap()
+                        .is_some_and(|checkpoint| checkpoint.logs.len() == 3)
+                })
+                .await
+            );
+            shutdown_tx
+        });
+
+        // Dropping the sender first would start a graceful shutdown
+        drop(runtime);
+        drop(shutdown_tx);
+    })
+    .join()
+    .unwrap();
+
+    let transport = RecordingTransport::default();
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    let (shutdown_tx, handle) = spawn_collector(collector);
+
+    assert!(wait_until(|| async { transport.messages().len() >= 3 }).await);
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    assert_eq!(transport.messages(), vec!["one", "two", "three"]);
+    assert!(!checkpoint_path.exists());
+}
+
+#[tokio::test]
+async fn test_idle_log_source_fails_healthz() {
+    let dir = tempfile::tempdir().unwrap();
+    let app_log = dir.path().join("app.log");
+    let batch_log = dir.path().join("batch.log");
+    append(&app_log, "");
+    append(&batch_log, "");
+
+    let config = Config {
+        log_paths: vec![app_log.to_string_lossy().to_string(), batch_log.to_string_lossy().to_string()],
+        log_max_idle: Duration::from_secs(1),
+        // The nightly batch log is legitimately quiet
+        log_max_idle_overrides_seconds: [(batch_log.to_string_lossy().to_string(), 0)].into(),
+        log_freshness_health: true,
+        ..test_config(&app_log, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, RecordingTransport::default()).unwrap());
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let healthz_url = format!("http://{}/healthz", listener.local_addr().unwrap());
+    tokio::spawn(stats_server::serve(listener, Arc::clone(&collector)));
+    let healthz_status = || async { reqwest::get(&healthz_url).await.unwrap().status().as_u16() };
+
+    append(&app_log, "INFO: started\n");
+    assert_eq!(healthz_status().await, 200);
+
+    // No writes past the threshold
+    assert!(wait_until(|| async { healthz_status().await == 503 }).await);
+    let body: serde_json::Value = reqwest::get(&healthz_url).await.unwrap().json().await.unwrap();
+    let stale = body["stale_log_sources"].as_array().unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0]["source"], "app.log");
+    assert_eq!(stale[0]["max_idle_seconds"], 1);
+
+    let sources = collector.stats().await.log_sources;
+    assert!(sources.iter().any(|source| source.source == "batch.log" && source.healthy));
+
+    // A new line makes the source healthy again
+    append(&app_log, "INFO: still here\n");
+    assert!(wait_until(|| async { healthz_status().await == 200 }).await);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_errors_queried_from_stats_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let log_path = dir.path().join("application.log");
+
+    let config = Config {
+        batch_size: 1,
+        ..test_config(&log_path, "http://unused".to_string())
+    };
+    let collector = Arc::new(SidecarCollector::with_transport(config, FailingTransport).unwrap());
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let running = Arc::clone(&collector);
+    let handle = tokio::spawn(async move {
+        running
+            .run_until(async {
+                let _ = shutdown_rx.await;
+                Ok(())
+            })
+            .await
+    });
+
+    append(&log_path, "ERROR: one\nERROR: two\n");
+    assert!(
+        wait_until(|| async {
+            collector.stats().await.errors_by_category.get("transport").is_some_and(|count| *count >= 2)
+        })
+        .await
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(stats_server::serve(listener, Arc::clone(&collector)));
+
+    let errors: Vec<RecordedError> = reqwest::get(format!("{}/errors", base_url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(errors.len() >= 2);
+    assert!(errors.iter().all(|error| error.category == "transport" && error.context == "send batch"));
+    assert!(errors[0].timestamp >= errors[1].timestamp);
+    assert_eq!(errors[0].message, "Transport error: gateway returned 503");
+
+    let stats: serde_json::Value = reqwest::get(format!("{}/stats", base_url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(stats["errors_by_category"]["transport"].as_u64().unwrap() >= 2);
+
+    shutdown_tx.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_tail_sampling_keeps_traces_with_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("app.log");
+    append(
+        &archive_path,
+        concat!(
+            r#"{"level": "INFO", "message": "checkout started", "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "00f067aa0ba902b7", "operation": "checkout", "status": "OK"}"#, "\n",
+            r#"{"level": "INFO", "message": "health check", "trace_id": "5cf92f3577b34da6a3ce929d0e0e4737", "span_id": "10f067aa0ba902b7", "operation": "healthz", "status": "OK"}"#, "\n",
+            r#"{"level": "INFO", "message": "payment call", "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "20f067aa0ba902b7", "operation": "payment", "status": "ERROR"}"#, "\n",
+            r#"{"level": "INFO", "message": "checkout finished", "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736", "span_id": "30f067aa0ba902b7", "operation": "checkout.render", "status": "OK"}"#, "\n",
+            r#"{"level": "INFO", "message": "health check done", "trace_id": "5cf92f3577b34da6a3ce929d0e0e4737", "span_id": "40f067aa0ba902b7", "operation": "healthz.reply", "status": "OK"}"#, "\n",
+            "INFO: untraced\n",
+        ),
+    );
+
+    let transport = RecordingTransport::default();
+    let config = Config {
+        tail_sampling_window: Duration::from_secs(60),
+        tail_sampling_ratio: 0.0,
+        ..test_config(&dir.path().join("unused.log"), "http://unused".to_string())
+    };
+    let collector = SidecarCollector::with_transport(config, transport.clone()).unwrap();
+    collector.replay_file(&archive_path.to_string_lossy()).await.unwrap();
+
+    // The error span keeps its OK siblings; the error-free trace is dropped whole
+    let mut operations: Vec<_> = transport
+        .batches
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|batch| batch.spans.iter().map(|span| span.operation_name.clone()))
+        .collect();
+    operations.sort();
+    assert_eq!(operations, vec!["checkout", "checkout.render", "payment"]);
+
+    let mut messages = transport.messages();
+    messages.sort();
+    assert_eq!(messages, vec!["checkout finished", "checkout started", "payment call", "untraced"]);
+    assert_eq!(collector.stats().await.traces_sampled_out, 1);
+}
@@ -0,0 +1,113 @@
+//! Shared helpers for integration tests
+
+#![allow(dead_code)]
+
+use opentel_collector::telemetry::LogLevel;
+use opentel_collector::{LogEntry, TelemetryBatch};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub const TELEMETRY_PATH: &str = "/v1/telemetry";
+
+/// Mock telemetry gateway backed by a wiremock server
+pub struct MockGateway {
+    pub server: MockServer,
+}
+
+impl MockGateway {
+    /// Start a new mock gateway with no routes mounted
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Base URL of the gateway
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Accept every telemetry batch
+    pub async fn accept_batches(&self) {
+        self.respond_with_status(200, None).await;
+    }
+
+    /// Respond to telemetry batches with a status, optionally only for the first `times` requests
+    pub async fn respond_with_status(&self, status: u16, times: Option<u64>) {
+        let mock = Mock::given(method("POST"))
+            .and(path(TELEMETRY_PATH))
+            .respond_with(ResponseTemplate::new(status).set_body_string(format!("status {}", status)));
+
+        match times {
+            Some(times) => mock.up_to_n_times(times).mount(&self.server).await,
+            None => mock.mount(&self.server).await,
+        }
+    }
+
+    /// Serve a health endpoint returning the given JSON body
+    pub async fn health(&self, status: u16, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serve a healthy health endpoint
+    pub async fn healthy(&self) {
+        self.health(200, json!({
+            "status": "healthy",
+            "service": "telemetry-gateway",
+            "version": "1.2.3",
+        })).await;
+    }
+
+    /// Number of telemetry requests received, regardless of response
+    pub async fn request_count(&self) -> usize {
+        self.telemetry_requests().await.len()
+    }
+
+    /// All batches received by the gateway, decoded
+    pub async fn received_batches(&self) -> Vec<TelemetryBatch> {
+        self.telemetry_requests()
+            .await
+            .iter()
+            .map(|request| serde_json::from_slice(&request.body).expect("valid telemetry batch"))
+            .collect()
+    }
+
+    /// Raw requests received on the telemetry endpoint
+    pub async fn telemetry_requests(&self) -> Vec<wiremock::Request> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.url.path() == TELEMETRY_PATH)
+            .collect()
+    }
+}
+
+/// Build a batch containing `log_count` info logs
+pub fn sample_batch(log_count: usize) -> TelemetryBatch {
+    let logs = (0..log_count)
+        .map(|i| {
+            LogEntry::new(
+                LogLevel::Info,
+                format!("Message {}", i),
+                "test-service".to_string(),
+                "test-pod".to_string(),
+                "test-namespace".to_string(),
+            )
+        })
+        .collect();
+
+    TelemetryBatch::new(
+        logs,
+        vec![],
+        "collector-1".to_string(),
+        "test-pod".to_string(),
+        "test-namespace".to_string(),
+    )
+}
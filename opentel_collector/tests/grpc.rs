@@ -0,0 +1,185 @@
+//! OTLP/gRPC transport tests against an in-process tonic server
+
+#![cfg(feature = "grpc")]
+
+mod common;
+
+use common::sample_batch;
+use opentel_collector::grpc::GrpcTransport;
+use opentel_collector::grpc::proto::{
+    any_value, ExportLogsServiceRequest, ExportLogsServiceResponse, ExportTraceServiceRequest,
+    ExportTraceServiceResponse,
+};
+use opentel_collector::{CollectorError, TelemetryBatch, TraceSpan};
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::body::Body;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::server::{NamedService, UnaryService};
+use tonic::{Code, Status};
+use tonic_prost::ProstCodec;
+
+/// Export service recording every request, failing each call with
+/// `failure` when set
+struct MockExportService<Req, Resp> {
+    requests: Arc<Mutex<Vec<Req>>>,
+    failure: Option<Code>,
+    response: PhantomData<fn() -> Resp>,
+}
+
+impl<Req, Resp> MockExportService<Req, Resp> {
+    fn new(failure: Option<Code>) -> Self {
+        Self {
+            requests: Arc::new(Mutex::new(Vec::new())),
+            failure,
+            response: PhantomData,
+        }
+    }
+}
+
+impl<Req, Resp> Clone for MockExportService<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            requests: Arc::clone(&self.requests),
+            failure: self.failure,
+            response: PhantomData,
+        }
+    }
+}
+
+impl NamedService for MockExportService<ExportLogsServiceRequest, ExportLogsServiceResponse> {
+    const NAME: &'static str = "opentelemetry.proto.collector.logs.v1.LogsService";
+}
+
+impl NamedService for MockExportService<ExportTraceServiceRequest, ExportTraceServiceResponse> {
+    const NAME: &'static str = "opentelemetry.proto.collector.trace.v1.TraceService";
+}
+
+impl<Req, Resp> UnaryService<Req> for MockExportService<Req, Resp>
+where
+    Req: Send + 'static,
+    Resp: Default + Send + 'static,
+{
+    type Response = Resp;
+    type Future = BoxFuture<tonic::Response<Resp>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        self.requests.lock().unwrap().push(request.into_inner());
+        let failure = self.failure;
+        Box::pin(async move {
+            match failure {
+                Some(code) => Err(Status::new(code, "mock failure")),
+                None => Ok(tonic::Response::new(Resp::default())),
+            }
+        })
+    }
+}
+
+impl<Req, Resp> Service<http::Request<Body>> for MockExportService<Req, Resp>
+where
+    Req: prost::Message + Default + Send + 'static,
+    Resp: prost::Message + Default + Send + 'static,
+{
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(ProstCodec::<Resp, Req>::default());
+            Ok(grpc.unary(service, request).await)
+        })
+    }
+}
+
+/// Three logs and one span
+fn batch() -> TelemetryBatch {
+    let mut batch = sample_batch(3);
+    batch.spans.push(TraceSpan::new(
+        "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+        "00f067aa0ba902b7".to_string(),
+        "db.query".to_string(),
+        "test-service".to_string(),
+    ));
+    batch
+}
+
+type LogsService = MockExportService<ExportLogsServiceRequest, ExportLogsServiceResponse>;
+type TraceService = MockExportService<ExportTraceServiceRequest, ExportTraceServiceResponse>;
+
+/// Serve the mock services on a local port, returning the endpoint
+async fn serve(logs: LogsService, traces: TraceService) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(logs)
+            .add_service(traces)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    endpoint
+}
+
+#[tokio::test]
+async fn test_batch_exported_to_logs_and_traces_services() {
+    let logs = LogsService::new(None);
+    let traces = TraceService::new(None);
+    let endpoint = serve(logs.clone(), traces.clone()).await;
+
+    let transport = GrpcTransport::new(&endpoint, Duration::from_secs(5), 0, 1).unwrap();
+    assert!(transport.test_connectivity().await);
+
+    let size = transport.send_batch(batch()).await.unwrap();
+    assert!(size.uncompressed_bytes > 0);
+
+    let logs_requests = logs.requests.lock().unwrap();
+    assert_eq!(logs_requests.len(), 1);
+    let records = &logs_requests[0].resource_logs[0].scope_logs[0].log_records;
+    assert_eq!(records.len(), 3);
+    let body = records[0].body.as_ref().and_then(|body| body.value.clone());
+    assert_eq!(body, Some(any_value::Value::StringValue("Message 0".to_string())));
+
+    let traces_requests = traces.requests.lock().unwrap();
+    assert_eq!(traces_requests.len(), 1);
+    let spans = &traces_requests[0].resource_spans[0].scope_spans[0].spans;
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "db.query");
+    assert_eq!(spans[0].trace_id.len(), 16);
+}
+
+#[tokio::test]
+async fn test_invalid_argument_is_not_retried() {
+    let logs = LogsService::new(Some(Code::InvalidArgument));
+    let endpoint = serve(logs.clone(), TraceService::new(None)).await;
+
+    let transport = GrpcTransport::new(&endpoint, Duration::from_secs(5), 3, 1).unwrap();
+    let result = transport.send_batch(batch()).await;
+
+    match result {
+        Err(CollectorError::Transport(message)) => assert!(message.contains("InvalidArgument"), "{}", message),
+        other => panic!("expected a transport error, got {:?}", other),
+    }
+    assert_eq!(logs.requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_unavailable_is_retried() {
+    let logs = LogsService::new(Some(Code::Unavailable));
+    let endpoint = serve(logs.clone(), TraceService::new(None)).await;
+
+    let transport = GrpcTransport::new(&endpoint, Duration::from_secs(5), 2, 1).unwrap();
+    assert!(transport.send_batch(batch()).await.is_err());
+    assert_eq!(logs.requests.lock().unwrap().len(), 3);
+}
@@ -0,0 +1,139 @@
+//! NATS transport tests against a minimal in-process NATS server
+
+#![cfg(feature = "nats")]
+
+mod common;
+
+use common::sample_batch;
+use opentel_collector::nats::NatsTransport;
+use opentel_collector::{CollectorError, TelemetryBatch};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// A message received by the mock server
+#[derive(Debug, Clone)]
+struct Published {
+    subject: String,
+    headers: String,
+    payload: Vec<u8>,
+}
+
+/// Speaks just enough of the NATS client protocol to accept publishes:
+/// INFO on connect, PONG for every PING, and PUB/HPUB recorded
+struct MockNatsServer {
+    url: String,
+    published: Arc<Mutex<Vec<Published>>>,
+}
+
+impl MockNatsServer {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let published = Arc::new(Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&published);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = Arc::clone(&received);
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut reader = BufReader::new(reader);
+                    let info = format!(
+                        "INFO {{\"server_id\":\"mock\",\"version\":\"2.10.0\",\"host\":\"127.0.0.1\",\"port\":{},\"headers\":true,\"max_payload\":1048576,\"proto\":1}}\r\n",
+                        port
+                    );
+                    writer.write_all(info.as_bytes()).await.unwrap();
+
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+                        let parts: Vec<&str> = line.split_whitespace().collect();
+                        match parts.first().copied() {
+                            Some("PING") => writer.write_all(b"PONG\r\n").await.unwrap(),
+                            Some("PUB") | Some("HPUB") => {
+                                let headers_len = if parts[0] == "HPUB" {
+                                    parts[parts.len() - 2].parse().unwrap()
+                                } else {
+                                    0
+                                };
+                                let total_len: usize = parts[parts.len() - 1].parse().unwrap();
+                                let mut body = vec![0; total_len + 2];
+                                reader.read_exact(&mut body).await.unwrap();
+                                received.lock().unwrap().push(Published {
+                                    subject: parts[1].to_string(),
+                                    headers: String::from_utf8_lossy(&body[..headers_len]).into_owned(),
+                                    payload: body[headers_len..total_len].to_vec(),
+                                });
+                            }
+                            _ => {}
+                        }
+                        line.clear();
+                    }
+                });
+            }
+        });
+
+        Self {
+            url: format!("nats://127.0.0.1:{}", port),
+            published,
+        }
+    }
+
+    /// Messages received so far, waiting briefly for at least `count`
+    async fn wait_for(&self, count: usize) -> Vec<Published> {
+        for _ in 0..100 {
+            if self.published.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        self.published.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_publish_batch_with_headers() {
+    let server = MockNatsServer::start().await;
+    let transport = NatsTransport::new(
+        server.url.clone(),
+        "telemetry.orders".to_string(),
+        Duration::from_secs(2),
+        0,
+        1,
+    );
+
+    assert!(transport.test_connectivity().await);
+
+    let batch = sample_batch(2);
+    let batch_id = batch.metadata.batch_id.clone();
+    let size = transport.send_batch(batch).await.unwrap();
+
+    // A flush only guarantees the bytes left the client
+    let published = server.wait_for(1).await;
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0].subject, "telemetry.orders");
+    assert!(published[0].headers.contains("Collector-Id: collector-1"));
+    assert!(published[0].headers.contains(&format!("Batch-Id: {}", batch_id)));
+    assert_eq!(size.uncompressed_bytes, published[0].payload.len() as u64);
+
+    let received: TelemetryBatch = serde_json::from_slice(&published[0].payload).unwrap();
+    assert_eq!(received.metadata.batch_id, batch_id);
+    assert_eq!(received.logs.len(), 2);
+}
+
+#[tokio::test]
+async fn test_unreachable_server_is_transport_error() {
+    // Reserve a port, then free it so nothing is listening
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("nats://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let transport = NatsTransport::new(url, "telemetry.orders".to_string(), Duration::from_millis(500), 2, 1);
+
+    assert!(!transport.test_connectivity().await);
+    match transport.send_batch(sample_batch(1)).await {
+        Err(CollectorError::Transport(message)) => assert!(message.contains("connect")),
+        other => panic!("expected a transport error, got {:?}", other),
+    }
+}
@@ -0,0 +1,404 @@
+//! Transport integration tests against a mock gateway
+
+mod common;
+
+use common::{sample_batch, MockGateway};
+use opentel_collector::config::{GatewayFormat, HttpVersion, TlsBackend, TlsVersion};
+use opentel_collector::file_sink::{FileTransport, MirroredTransport};
+use opentel_collector::transport::{ConnectionOptions, EnhancedTransport, HttpTransport, PayloadFormat, PayloadSize, Transport};
+use opentel_collector::{CollectorError, TelemetryBatch};
+use serde_json::json;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+fn transport(gateway: &MockGateway, max_retries: u32) -> HttpTransport {
+    HttpTransport::new(gateway.url(), Duration::from_secs(2), max_retries, 1).unwrap()
+}
+
+fn transport_error_message(result: opentel_collector::Result<PayloadSize>) -> String {
+    match result {
+        Err(CollectorError::Transport(message)) => message,
+        other => panic!("expected transport error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_send_batch_success() {
+    let gateway = MockGateway::start().await;
+    gateway.accept_batches().await;
+
+    let batch = sample_batch(3);
+    let batch_id = batch.metadata.batch_id.clone();
+
+    transport(&gateway, 3).send_batch(batch).await.unwrap();
+
+    let received = gateway.received_batches().await;
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].metadata.batch_id, batch_id);
+    assert_eq!(received[0].logs.len(), 3);
+    assert_eq!(received[0].logs[0].message, "Message 0");
+}
+
+#[tokio::test]
+async fn test_retry_then_success() {
+    let gateway = MockGateway::start().await;
+    gateway.respond_with_status(503, Some(2)).await;
+    gateway.accept_batches().await;
+
+    let enhanced = EnhancedTransport::new(transport(&gateway, 3));
+    enhanced.send_batch(sample_batch(1)).await.unwrap();
+
+    assert_eq!(gateway.request_count().await, 3);
+
+    let metrics = enhanced.metrics().await;
+    assert_eq!(metrics.attempts, 1);
+    assert_eq!(metrics.successes, 1);
+    assert_eq!(metrics.failures, 0);
+}
+
+#[tokio::test]
+async fn test_payload_size_metrics() {
+    let gateway = MockGateway::start().await;
+    gateway.respond_with_status(503, Some(1)).await;
+    gateway.accept_batches().await;
+
+    let enhanced = EnhancedTransport::new(transport(&gateway, 3));
+    let batches = [sample_batch(1), sample_batch(5)];
+    let expected: Vec<u64> = batches
+        .iter()
+        .map(|batch| serde_json::to_vec(batch).unwrap().len() as u64)
+        .collect();
+
+    for batch in batches {
+        let size = enhanced.send_batch(batch).await.unwrap();
+        assert_eq!(size.uncompressed_bytes, size.compressed_bytes);
+    }
+
+    // Retried attempts are not double counted
+    let metrics = enhanced.metrics().await;
+    let total: u64 = expected.iter().sum();
+    assert_eq!(metrics.bytes_sent_uncompressed, total);
+    assert_eq!(metrics.bytes_sent_compressed, total);
+    assert_eq!(metrics.compression_ratio, 1.0);
+    assert_eq!(metrics.avg_batch_bytes, total / 2);
+
+    enhanced.reset_metrics().await;
+    assert_eq!(enhanced.metrics().await.bytes_sent_uncompressed, 0);
+}
+
+#[tokio::test]
+async fn test_batch_below_compression_threshold_sent_uncompressed() {
+    let gateway = MockGateway::start().await;
+    gateway.accept_batches().await;
+
+    let batch = sample_batch(1);
+    let expected = serde_json::to_vec(&batch).unwrap().len() as u64;
+    let size = transport(&gateway, 0).with_gzip(Some(64 * 1024)).send_batch(batch).await.unwrap();
+
+    assert_eq!(size, PayloadSize::uncompressed(expected as usize));
+    let requests = gateway.telemetry_requests().await;
+    assert!(!requests[0].headers.contains_key("content-encoding"));
+    assert_eq!(gateway.received_batches().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_batch_over_compression_threshold_gzipped() {
+    let gateway = MockGateway::start().await;
+    gateway.accept_batches().await;
+
+    let batch = sample_batch(200);
+    let batch_id = batch.metadata.batch_id.clone();
+    let json = serde_json::to_vec(&batch).unwrap();
+    let enhanced = EnhancedTransport::new(transport(&gateway, 0).with_gzip(Some(1024)));
+    let size = enhanced.send_batch(batch).await.unwrap();
+
+    let requests = gateway.telemetry_requests().await;
+    assert_eq!(requests[0].headers["content-encoding"], "gzip");
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(requests[0].body.as_slice()).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, json);
+    let received: TelemetryBatch = serde_json::from_slice(&decoded).unwrap();
+    assert_eq!(received.metadata.batch_id, batch_id);
+
+    assert_eq!(size.uncompressed_bytes, json.len() as u64);
+    assert_eq!(size.compressed_bytes, requests[0].body.len() as u64);
+    assert!(size.compressed_bytes < size.uncompressed_bytes / 2);
+    assert!(enhanced.metrics().await.compression_ratio > 2.0);
+}
+
+#[tokio::test]
+async fn test_retry_exhaustion() {
+    let gateway = MockGateway::start().await;
+    gateway.respond_with_status(500, None).await;
+
+    let enhanced = EnhancedTransport::new(transport(&gateway, 2));
+    let result = enhanced.send_batch(sample_batch(1)).await;
+
+    let message = transport_error_message(result);
+    assert!(message.starts_with("Gateway server error"), "{}", message);
+    assert_eq!(gateway.request_count().await, 3); // initial attempt + 2 retries
+
+    let metrics = enhanced.metrics().await;
+    assert_eq!(metrics.failures, 1);
+    assert_eq!(metrics.success_rate, 0.0);
+}
+
+/// A batch drained `age` ago
+fn aged_batch(age: Duration) -> TelemetryBatch {
+    let mut batch = sample_batch(1);
+    batch.created_at = Instant::now().checked_sub(age).unwrap();
+    batch
+}
+
+#[tokio::test]
+async fn test_stale_batch_dropped_before_send() {
+    let gateway = MockGateway::start().await;
+    gateway.accept_batches().await;
+
+    let enhanced = EnhancedTransport::new(transport(&gateway, 3))
+        .with_max_batch_send_age(Duration::from_secs(60));
+
+    let result = enhanced.send_batch(aged_batch(Duration::from_secs(120))).await;
+    assert!(matches!(result, Err(CollectorError::BatchExpired(_))), "{:?}", result);
+    assert_eq!(gateway.request_count().await, 0);
+
+    // Fresh telemetry still goes through
+    enhanced.send_batch(sample_batch(1)).await.unwrap();
+    assert_eq!(gateway.request_count().await, 1);
+
+    let metrics = enhanced.metrics().await;
+    assert_eq!(metrics.expired, 1);
+    assert_eq!(metrics.successes, 1);
+}
+
+#[tokio::test]
+async fn test_stale_batch_not_retried() {
+    let gateway = MockGateway::start().await;
+    gateway.respond_with_status(503, None).await;
+
+    let transport = transport(&gateway, 3).with_max_batch_send_age(Duration::from_secs(60));
+    let result = transport.send_batch(aged_batch(Duration::from_secs(120))).await;
+
+    assert!(matches!(result, Err(CollectorError::BatchExpired(_))), "{:?}", result);
+    assert_eq!(gateway.request_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_connect_timeout_fails_fast() {
+    // A non-routable address: connecting hangs (or is refused) instead of completing
+    let options = ConnectionOptions {
+        connect_timeout: Some(Duration::from_millis(200)),
+        ..ConnectionOptions::default()
+    };
+    let transport =
+        HttpTransport::with_connection_options("http://10.255.255.1:8080".to_string(), Duration::from_secs(30), 0, 1, options)
+            .unwrap();
+
+    let started = Instant::now();
+    let result = transport.send_batch(sample_batch(1)).await;
+
+    assert!(matches!(result, Err(CollectorError::Http(_))), "{:?}", result);
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+}
+
+/// Accept one connection, answer the client's hello with a TLS 1.1
+/// ServerHello and return what the client sends back
+async fn tls11_only_server(listener: tokio::net::TcpListener) -> Vec<u8> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut client_hello = [0u8; 4096];
+    let _ = stream.read(&mut client_hello).await.unwrap();
+
+    // Handshake: ServerHello, version 3.2 (TLS 1.1), zero random, no session
+    // id, TLS_RSA_WITH_AES_128_CBC_SHA, no compression
+    let mut server_hello = vec![0x02, 0x00, 0x00, 0x26, 0x03, 0x02];
+    server_hello.extend([0u8; 32]);
+    server_hello.extend([0x00, 0x00, 0x2f, 0x00]);
+    let mut record = vec![0x16, 0x03, 0x02, 0x00, server_hello.len() as u8];
+    record.extend(server_hello);
+    stream.write_all(&record).await.unwrap();
+
+    let mut reply = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await;
+    reply
+}
+
+#[tokio::test]
+async fn test_tls_below_minimum_version_rejected() {
+    for tls_backend in [TlsBackend::Native, TlsBackend::Rustls] {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(tls11_only_server(listener));
+
+        let options = ConnectionOptions {
+            tls_min_version: TlsVersion::Tls12,
+            tls_backend,
+            ..ConnectionOptions::default()
+        };
+        let transport = HttpTransport::with_connection_options(url, Duration::from_secs(5), 0, 1, options).unwrap();
+
+        let result = transport.send_batch(sample_batch(1)).await;
+        assert!(matches!(result, Err(CollectorError::Http(_))), "{:?}: {:?}", tls_backend, result);
+
+        // The handshake ends with a fatal protocol_version alert
+        let reply = server.await.unwrap();
+        assert!(reply.windows(2).any(|alert| alert == [0x02, 0x46]), "{:?}: {:02x?}", tls_backend, reply);
+    }
+}
+
+#[tokio::test]
+async fn test_error_status_messages() {
+    let cases = [
+        (400, "Bad request for batch"),
+        (401, "Unauthorized for batch"),
+        (403, "Forbidden for batch"),
+        (404, "Gateway endpoint not found for batch"),
+        (413, "too large"),
+        (429, "Rate limited for batch"),
+        (502, "Gateway server error for batch"),
+        (418, "Unexpected response 418"),
+    ];
+
+    for (status, expected) in cases {
+        let gateway = MockGateway::start().await;
+        gateway.respond_with_status(status, None).await;
+
+        let batch = sample_batch(1);
+        let batch_id = batch.metadata.batch_id.clone();
+        let message = transport_error_message(transport(&gateway, 0).send_batch(batch).await);
+
+        assert!(message.contains(expected), "status {}: {}", status, message);
+        assert!(message.contains(&batch_id), "status {}: {}", status, message);
+        assert!(message.contains(&format!("status {}", status)), "status {}: {}", status, message);
+    }
+}
+
+#[tokio::test]
+async fn test_health_check_parsing() {
+    let gateway = MockGateway::start().await;
+    gateway.healthy().await;
+
+    let transport = transport(&gateway, 0);
+    let health = transport.health_check().await.unwrap();
+
+    assert_eq!(health.status, "healthy");
+    assert_eq!(health.service, "telemetry-gateway");
+    assert_eq!(health.version, "1.2.3");
+    assert!(transport.test_connectivity().await);
+}
+
+#[tokio::test]
+async fn test_health_check_reports_protocol() {
+    let gateway = MockGateway::start().await;
+    gateway.healthy().await;
+
+    let health = transport(&gateway, 0).health_check().await.unwrap();
+    assert_eq!(health.protocol, "HTTP/1.1");
+
+    let options = ConnectionOptions {
+        http_version: HttpVersion::Http2,
+        ..ConnectionOptions::default()
+    };
+    let h2c = HttpTransport::with_connection_options(gateway.url(), Duration::from_secs(2), 0, 1, options).unwrap();
+    let health = h2c.health_check().await.unwrap();
+    assert_eq!(health.protocol, "HTTP/2.0");
+}
+
+#[tokio::test]
+async fn test_health_check_missing_fields() {
+    let gateway = MockGateway::start().await;
+    gateway.health(200, json!({"status": "degraded"})).await;
+
+    let health = transport(&gateway, 0).health_check().await.unwrap();
+
+    assert_eq!(health.status, "degraded");
+    assert_eq!(health.service, "unknown");
+    assert_eq!(health.version, "unknown");
+}
+
+#[tokio::test]
+async fn test_format_negotiated_from_health() {
+    let gateway = MockGateway::start().await;
+    gateway.health(200, json!({"status": "healthy", "supported_formats": ["json", "otlp"]})).await;
+    wiremock::Mock::given(wiremock::matchers::method("POST"))
+        .and(wiremock::matchers::path("/v1/logs"))
+        .respond_with(wiremock::ResponseTemplate::new(200))
+        .mount(&gateway.server)
+        .await;
+
+    // JSON until the gateway has been asked
+    let transport = transport(&gateway, 0);
+    assert_eq!(transport.payload_format(), PayloadFormat::Json);
+    assert!(transport.test_connectivity().await);
+    assert_eq!(transport.payload_format(), PayloadFormat::Otlp);
+
+    transport.send_batch(sample_batch(2)).await.unwrap();
+    assert_eq!(gateway.request_count().await, 0);
+
+    let requests = gateway.server.received_requests().await.unwrap();
+    let export = requests.iter().find(|request| request.url.path() == "/v1/logs").unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&export.body).unwrap();
+    let records = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+    assert_eq!(records.as_array().unwrap().len(), 2);
+
+    // An explicit format is never overridden by the gateway
+    let pinned = HttpTransport::new(gateway.url(), Duration::from_secs(2), 0, 1)
+        .unwrap()
+        .with_format(GatewayFormat::Json);
+    assert!(pinned.test_connectivity().await);
+    assert_eq!(pinned.payload_format(), PayloadFormat::Json);
+}
+
+#[tokio::test]
+async fn test_health_check_failure_status() {
+    let gateway = MockGateway::start().await;
+    gateway.health(503, json!({"status": "down"})).await;
+
+    let transport = transport(&gateway, 0);
+
+    assert!(matches!(transport.health_check().await, Err(CollectorError::Transport(_))));
+    assert!(!transport.test_connectivity().await);
+}
+
+fn file_batches(path: &std::path::Path) -> Vec<TelemetryBatch> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_file_transport_appends_batches() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sink").join("batches.jsonl");
+    let transport = FileTransport::new(&path);
+
+    assert!(transport.test_connectivity().await);
+    let first = sample_batch(2);
+    let second = sample_batch(1);
+    let ids = vec![first.metadata.batch_id.clone(), second.metadata.batch_id.clone()];
+    transport.send_batch(first).await.unwrap();
+    transport.send_batch(second).await.unwrap();
+
+    let written = file_batches(&path);
+    assert_eq!(written.iter().map(|batch| batch.metadata.batch_id.clone()).collect::<Vec<_>>(), ids);
+    assert_eq!(written[0].logs[1].message, "Message 1");
+}
+
+#[tokio::test]
+async fn test_mirrored_transport_keeps_copy_when_gateway_fails() {
+    let gateway = MockGateway::start().await;
+    gateway.respond_with_status(400, None).await;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mirror.jsonl");
+
+    let mirrored = MirroredTransport::new(transport(&gateway, 0), FileTransport::new(&path));
+    let batch = sample_batch(1);
+    let batch_id = batch.metadata.batch_id.clone();
+
+    // The gateway decides the outcome, the file still gets the batch
+    assert!(mirrored.send_batch(batch).await.is_err());
+    assert_eq!(file_batches(&path)[0].metadata.batch_id, batch_id);
+}